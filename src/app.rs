@@ -1,15 +1,39 @@
 //! Application state and business logic.
 //!
-//! `App` owns the theme list, the current selection cursor, and the UI mode
-//! (browsing vs. confirming a selection).  It exposes a `handle_key` method
-//! that the event loop calls; that method returns `Some(ExitAction)` when the
-//! loop should terminate.
+//! `App` owns the theme list, the current selection cursor, the search
+//! filter, and the UI mode (browsing, searching, confirming a selection, or
+//! showing an error).  It exposes `handle_key` and `handle_mouse` methods
+//! that the event loop calls; they return `Some(ExitAction)` when the loop
+//! should terminate.
 
-use crossterm::event::KeyCode;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+use ratatui::layout::{Position, Rect};
 use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
 
+use crate::clipboard;
 use crate::config::SddmConfig;
-use crate::theme::SddmTheme;
+use crate::display_manager::SDDM;
+use crate::favorites;
+use crate::history;
+use crate::image_preview::ImagePreview;
+use crate::prefs::{self, Prefs};
+use crate::theme::{SddmTheme, ThemeSource};
+
+/// Two clicks on the same row within this interval count as a double-click.
+const DOUBLE_CLICK_INTERVAL: Duration = Duration::from_millis(400);
+
+/// Environment variable that, when set and non-empty, starts with
+/// wrap-around navigation off.
+pub const NO_WRAP_ENV: &str = "THEMEWALKER_NO_WRAP";
+
+/// Themes distributions commonly treat as the default, tried in order to
+/// place the cursor when the config sets no theme.
+const DEFAULT_THEME_NAMES: [&str; 3] = ["breeze", "elarun", "maldives"];
 
 // ---------------------------------------------------------------------------
 // Public types
@@ -22,43 +46,241 @@ pub enum ExitAction {
     Quit,
     /// User confirmed a theme – call `SddmConfig::write_theme` with this name.
     ApplyTheme(String),
+    /// User asked to open this theme directory in a file manager (`xdg-open`).
+    OpenDir(PathBuf),
+    /// User confirmed restoring the config from its backup.
+    RestoreBackup,
+    /// User asked to restart SDDM after a theme was applied inside the TUI.
+    RestartDisplayManager,
+    /// User confirmed deleting the directories of these themes.
+    DeleteThemes(Vec<SddmTheme>),
+    /// User saved edited `[Theme]` keys that cannot be written inside the
+    /// TUI – call `SddmConfig::write_keys` with these.
+    WriteThemeKeys(HashMap<String, String>),
+}
+
+/// Outcome of the last theme write made inside the TUI, summarised by
+/// `Mode::Result` before quitting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApplyResult {
+    /// Theme that was written (or failed to be).
+    pub theme: String,
+    /// Config file written to.
+    pub config: PathBuf,
+    /// Where the previous config was saved, when it had content.
+    pub backup: Option<PathBuf>,
+    /// Whether SDDM must restart to show the theme, i.e. it differs from the
+    /// one active when the session started.
+    pub restart_needed: bool,
+    /// Why the write failed; `None` when it succeeded.
+    pub error: Option<String>,
 }
 
 /// UI modes that drive which widgets are rendered and which keys are active.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Mode {
     /// Normal list navigation.
     Browsing,
+    /// Typing a search query that filters the list.
+    Searching,
     /// Floating confirmation dialog.
     Confirming,
+    /// Choosing which of several config files to write the given theme to
+    /// (`SddmConfig::candidates`), after it was confirmed.
+    ChooseTarget(String),
+    /// Confirmation dialog for restoring the config backup, which selects
+    /// the given theme (if any).
+    ConfirmingRestore(Option<String>),
+    /// Error popup with a message; any of Enter/Esc/o dismisses it.
+    Error(String),
+    /// Full keybinding reference; `?`, Esc or `q` dismisses it.
+    Help,
+    /// The theme was written from inside the TUI; Enter/Esc keeps browsing,
+    /// `q` quits.
+    Applied(String),
+    /// Confirmation for deleting the themes in `names`, which only goes
+    /// ahead once `input` (what the user has typed so far) equals
+    /// `delete_prompt(names)`.
+    ConfirmDelete { names: Vec<String>, input: String },
+    /// The config's `[Theme]` keys (`e`): `keys` holds the values as edited
+    /// so far, `selected` the highlighted row, and `input` the value being
+    /// typed for it, while one is.  `w` writes the changed keys.
+    EditKeys {
+        keys: Vec<(String, String)>,
+        selected: usize,
+        input: Option<String>,
+    },
+    /// Summary of `App::last_apply` shown on quitting after a write inside
+    /// the TUI; any key quits.
+    Result,
+    /// The loaded config file as it is on disk (`v`), scrolled down by
+    /// `scroll` lines with j/k.  Esc, `q` or `v` closes it.
+    ViewConfig { scroll: u16 },
+    /// Quitting while another theme than the active one is highlighted,
+    /// with `confirm_quit` on: `a` applies it, `d` quits without applying,
+    /// Esc or `c` keeps browsing.
+    ConfirmQuit,
+    /// Prompt for a theme directory or `.tar.gz` to install (`i`); `input`
+    /// is the path typed so far.  Enter imports it, Esc cancels.
+    Import { input: String },
+}
+
+/// Order of the theme list; `s` cycles through these.  Stored in
+/// prefs.toml by its `label`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortKey {
+    /// Alphabetical by directory name.
+    Name,
+    /// Alphabetical by author; themes without an author go last.
+    Author,
+    /// Most recently modified theme directory first.
+    Modified,
+}
+
+impl SortKey {
+    /// Next key in the `s` cycle.
+    pub fn next(self) -> Self {
+        match self {
+            SortKey::Name => SortKey::Author,
+            SortKey::Author => SortKey::Modified,
+            SortKey::Modified => SortKey::Name,
+        }
+    }
+
+    /// Label shown in the list title.
+    pub fn label(self) -> &'static str {
+        match self {
+            SortKey::Name => "name",
+            SortKey::Author => "author",
+            SortKey::Modified => "modified",
+        }
+    }
 }
 
 /// Central application state.
 pub struct App {
     /// All installed themes, sorted alphabetically.
     pub themes: Vec<SddmTheme>,
+    /// Indices into `themes` that pass the search filter, in display order.
+    pub visible: Vec<usize>,
+    /// Search text; empty means the list is unfiltered.
+    pub search_query: String,
+    /// Theme highlighted when the search started, restored when the search is
+    /// cleared.  Dropped once the user moves the cursor within the results.
+    pub pre_search_selection: Option<String>,
     /// ratatui list state (tracks scroll offset and selection highlight).
+    /// The selected index refers to a position in `visible`, not `themes`.
     pub list_state: ListState,
-    /// Currently active theme name (from config).
+    /// Currently active theme name: the one SDDM uses, after drop-ins
+    /// override each other (`SddmConfig::effective_current`).
     pub current_theme: Option<String>,
+    /// Active theme as it was when the session started; `u` jumps back to it.
+    pub original_theme: Option<String>,
+    /// Whether a theme was written from inside the TUI this session.
+    pub applied: bool,
+    /// The last theme write attempted inside the TUI, if any.
+    pub last_apply: Option<ApplyResult>,
     /// Loaded configuration (used when writing back).
     pub config: SddmConfig,
     /// Current UI mode.
     pub mode: Mode,
+    /// Order of `themes`.
+    pub sort_key: SortKey,
+    /// Problems `SddmTheme::validate` found in the theme being confirmed.
+    /// While non-empty only `Y` (force) applies it.
+    pub validation_problems: Vec<String>,
     /// Non-fatal notice shown in the status bar (e.g. "No themes found").
     pub status: Option<String>,
+    /// Write the config without leaving the TUI when no sudo is needed.
+    /// When unset (or sudo is needed) confirming exits with
+    /// `ExitAction::ApplyTheme` (or saving edited keys with
+    /// `ExitAction::WriteThemeKeys`) and the write happens after the TUI.
+    pub apply_in_tui: bool,
+    /// Where the theme list was last drawn (set by `ui::draw`); used to map
+    /// mouse clicks to rows.
+    pub list_area: Rect,
+    /// Row (index into `visible`) and time of the last left click, for
+    /// double-click detection.
+    pub last_click: Option<(usize, Instant)>,
+    /// Close the confirmation dialog after this long without a key press;
+    /// `None` (the default) keeps it open.
+    pub confirm_timeout: Option<Duration>,
+    /// When the confirmation dialog was opened or last received a key.
+    pub confirm_opened: Option<Instant>,
+    /// Set by the reload key; the event loop re-scans themes and re-reads the
+    /// config, then hands them to `reload`.
+    pub reload_requested: bool,
+    /// Show only themes with incomplete metadata (`m`), on top of any search.
+    pub incomplete_only: bool,
+    /// Show only themes with this tag, on top of any search.  `t` cycles
+    /// through `all_tags`.
+    pub tag_filter: Option<String>,
+    /// Whether moving past the first/last theme wraps to the other end
+    /// (the default) or stops there.  Toggled with `w`.
+    pub wrap_navigation: bool,
+    /// `wrap_navigation` holds a session-only override (`NO_WRAP_ENV`), so
+    /// saving writes `saved_prefs`' value instead.  `w` clears it.
+    wrap_overridden: bool,
+    /// Likewise for `table` (`--table`); Tab clears it.
+    table_overridden: bool,
+    /// The settings as `set_prefs` restored them.
+    saved_prefs: Prefs,
+    /// List only theme names, without descriptions, to fit more rows on
+    /// small terminals.  Toggled with `d`.
+    pub dense: bool,
+    /// Draw the list as a table with Name, Author and Description columns,
+    /// one row per theme.  Toggled with Tab.
+    pub table: bool,
+    /// Draws screenshots inline when the terminal supports images; `None`
+    /// shows only the screenshot path.
+    pub image_preview: Option<ImagePreview>,
+    /// Names of favorite themes; they sort to the top of the list.
+    pub favorites: HashSet<String>,
+    /// Names of themes marked with Space.  While any are marked, `f` and
+    /// `x` act on them instead of the highlighted theme.
+    pub marked: HashSet<String>,
+    /// State file favorites are saved to on change; `None` keeps them in
+    /// memory only.
+    pub favorites_path: Option<PathBuf>,
+    /// Where sort order, dense mode and wrap-around are saved when they
+    /// change; `None` keeps them for this session only.
+    pub prefs_path: Option<PathBuf>,
+    /// Log that themes applied inside the TUI are recorded in; `None`
+    /// keeps no history.
+    pub history_path: Option<PathBuf>,
+    /// Ask before quitting when the highlighted theme is not the active one
+    /// (`--confirm-quit`); off by default, so `q` quits straight away.
+    pub confirm_quit: bool,
+    /// Themes directory `i` imports into; `None` (no `$HOME`) refuses to
+    /// import.
+    pub import_dir: Option<PathBuf>,
+    /// Name of a theme just imported, selected by the next `reload`.
+    pub imported: Option<String>,
+    /// Highlighted entry of `config.candidates` in `Mode::ChooseTarget`.
+    pub target_selected: usize,
+    /// The display manager the system starts, when it could be detected.
+    pub display_manager: Option<String>,
 }
 
 impl App {
     /// Build the initial state.
     ///
     /// The list cursor is pre-positioned on the currently active theme when
-    /// it can be found in the theme list; otherwise it starts at index 0.
+    /// it can be found in the theme list.  When the config sets no theme, it
+    /// starts on the first installed of `DEFAULT_THEME_NAMES`; otherwise at
+    /// index 0.
     pub fn new(themes: Vec<SddmTheme>, config: SddmConfig) -> Self {
-        let initial_selection = config
-            .current_theme
+        let (current_theme, _) = config.effective_current();
+        let position = |name: &str| themes.iter().position(|t| t.answers_to(name) && !t.shadowed);
+        let default_theme = match current_theme {
+            Some(_) => None,
+            None => DEFAULT_THEME_NAMES.into_iter().find(|name| position(name).is_some()),
+        };
+        let initial_selection = current_theme
             .as_deref()
-            .and_then(|name| themes.iter().position(|t| t.name == name))
+            .or(default_theme)
+            .and_then(position)
             .unwrap_or(0);
 
         let mut list_state = ListState::default();
@@ -67,18 +289,59 @@ impl App {
         }
 
         let status = if themes.is_empty() {
-            Some("No themes found in /usr/share/sddm/themes/".to_string())
+            // `report_scanned_roots` names the directories
+            Some("No themes found".to_string())
         } else {
-            None
+            match current_theme.as_deref() {
+                Some(name) if !themes.iter().any(|t| t.answers_to(name)) => {
+                    Some(format!("Active theme '{name}' is not installed"))
+                }
+                None => default_theme
+                    .map(|name| format!("No theme is set; starting on the default '{name}'")),
+                _ => None,
+            }
         };
 
         Self {
-            current_theme: config.current_theme.clone(),
+            original_theme: current_theme.clone(),
+            current_theme,
+            applied: false,
+            visible: (0..themes.len()).collect(),
             themes,
+            search_query: String::new(),
+            pre_search_selection: None,
             list_state,
             config,
             mode: Mode::Browsing,
+            sort_key: SortKey::Name,
+            validation_problems: Vec::new(),
             status,
+            apply_in_tui: false,
+            last_apply: None,
+            list_area: Rect::default(),
+            last_click: None,
+            confirm_timeout: None,
+            confirm_opened: None,
+            reload_requested: false,
+            incomplete_only: false,
+            tag_filter: None,
+            wrap_navigation: true,
+            wrap_overridden: false,
+            table_overridden: false,
+            saved_prefs: Prefs::default(),
+            dense: false,
+            table: false,
+            image_preview: None,
+            favorites: HashSet::new(),
+            marked: HashSet::new(),
+            favorites_path: None,
+            prefs_path: None,
+            history_path: None,
+            confirm_quit: false,
+            import_dir: None,
+            imported: None,
+            target_selected: 0,
+            display_manager: None,
         }
     }
 
@@ -86,14 +349,118 @@ impl App {
     // Queries
     // -----------------------------------------------------------------------
 
-    /// Index of the highlighted item (guaranteed valid when themes is non-empty).
+    /// Index of the highlighted item within the visible (filtered) list.
     pub fn selected_index(&self) -> Option<usize> {
         self.list_state.selected()
     }
 
     /// The theme currently highlighted in the list.
     pub fn highlighted_theme(&self) -> Option<&SddmTheme> {
-        self.selected_index().and_then(|i| self.themes.get(i))
+        self.selected_index()
+            .and_then(|i| self.visible.get(i))
+            .and_then(|&i| self.themes.get(i))
+    }
+
+    /// Whether the configured theme is among the installed themes.  Also
+    /// `true` when no theme is configured, since nothing is missing then.
+    pub fn current_theme_installed(&self) -> bool {
+        match self.current_theme.as_deref() {
+            Some(name) => self.themes.iter().any(|t| t.answers_to(name)),
+            None => true,
+        }
+    }
+
+    /// Whether the config value `current` selects the theme named `name`,
+    /// directly or through one of its aliases (see `SddmTheme::answers_to`).
+    fn selects(&self, current: Option<&str>, name: &str) -> bool {
+        current.is_some_and(|current| {
+            current == name
+                || self.themes.iter().any(|t| t.name == name && !t.shadowed && t.answers_to(current))
+        })
+    }
+
+    /// Whether the highlighted theme is the one the config already selects,
+    /// so confirming it would only re-write the same value.
+    pub fn is_already_active(&self) -> bool {
+        match (self.highlighted_theme(), self.current_theme.as_deref()) {
+            (Some(theme), Some(current)) => theme.answers_to(current),
+            _ => false,
+        }
+    }
+
+    /// The detected display manager when it is not SDDM, so a theme change
+    /// would not show.  `None` when it is SDDM or unknown.
+    pub fn other_display_manager(&self) -> Option<&str> {
+        self.display_manager.as_deref().filter(|dm| *dm != SDDM)
+    }
+
+    /// Whether `theme` is marked as a favorite.
+    pub fn is_favorite(&self, theme: &SddmTheme) -> bool {
+        self.favorites.contains(&theme.name)
+    }
+
+    /// Group header drawn above the theme at `pos` in `visible`: "Favorites"
+    /// above the first favorite and "All themes" above the first other theme
+    /// that follows them.  `None` when no favorites are visible.
+    pub fn group_header(&self, pos: usize) -> Option<&'static str> {
+        let theme = &self.themes[*self.visible.get(pos)?];
+        if self.is_favorite(theme) {
+            (pos == 0).then_some("Favorites")
+        } else {
+            let previous = &self.themes[self.visible[pos.checked_sub(1)?]];
+            self.is_favorite(previous).then_some("All themes")
+        }
+    }
+
+    /// Rows the list item at `pos` takes: one for the name, plus one for a
+    /// group header and one for the description line outside dense mode.
+    /// Always one in the table, which has neither.
+    pub fn item_height(&self, pos: usize) -> u16 {
+        let Some(&index) = self.visible.get(pos) else {
+            return 0;
+        };
+        if self.table {
+            return 1;
+        }
+        let has_description =
+            !self.dense && self.themes[index].description().is_some_and(|d| !d.is_empty());
+        1 + u16::from(self.group_header(pos).is_some()) + u16::from(has_description)
+    }
+
+    /// Whether the visible themes need more than `rows` terminal rows, so
+    /// the list has to scroll.
+    pub fn list_overflows(&self, rows: u16) -> bool {
+        let total: u32 =
+            (0..self.visible.len()).map(|pos| u32::from(self.item_height(pos))).sum();
+        total > u32::from(rows)
+    }
+
+    /// Number of installed themes per source, as `(system, user)`.
+    pub fn theme_source_counts(&self) -> (usize, usize) {
+        let user = self
+            .themes
+            .iter()
+            .filter(|t| t.source == ThemeSource::User)
+            .count();
+        (self.themes.len() - user, user)
+    }
+
+    /// Every tag declared by an installed theme, sorted and listed once
+    /// regardless of case.
+    pub fn all_tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = Vec::new();
+        for tag in self.themes.iter().flat_map(|t| &t.tags) {
+            if !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+                tags.push(tag.clone());
+            }
+        }
+        tags.sort_by_key(|t| t.to_lowercase());
+        tags
+    }
+
+    /// Themes that pass the current search filter, in display order.
+    pub fn visible_themes(&self) -> impl Iterator<Item = &SddmTheme> + '_ {
+        self.visible.iter().map(move |&i| &self.themes[i])
     }
 
     // -----------------------------------------------------------------------
@@ -102,11 +469,75 @@ impl App {
 
     /// Process a key press.  Returns `Some(ExitAction)` to signal the event
     /// loop to break; returns `None` to continue.
-    pub fn handle_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+    ///
+    /// `Ctrl-C` quits from any mode: raw mode turns off SIGINT, so it would
+    /// otherwise do nothing.  `Ctrl-R` is an alias for F5 (reload), not
+    /// plain `r`.
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<ExitAction> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let code = match key.code {
+            KeyCode::Char('c') if ctrl => return Some(ExitAction::Quit),
+            KeyCode::Char('r') if ctrl => KeyCode::F(5),
+            code => code,
+        };
         match self.mode {
             Mode::Browsing => self.handle_browsing_key(code),
+            Mode::Searching => self.handle_searching_key(code),
             Mode::Confirming => self.handle_confirming_key(code),
+            Mode::ChooseTarget(_) => self.handle_choose_target_key(code),
+            Mode::ConfirmingRestore(_) => self.handle_confirming_restore_key(code),
+            Mode::Error(_) => self.handle_error_key(code),
+            Mode::Help => self.handle_help_key(code),
+            Mode::Applied(_) => self.handle_applied_key(code),
+            Mode::ConfirmDelete { .. } => self.handle_confirm_delete_key(code),
+            Mode::EditKeys { .. } => self.handle_edit_keys_key(code),
+            Mode::Result => Some(ExitAction::Quit),
+            Mode::ViewConfig { .. } => self.handle_view_config_key(code),
+            Mode::Import { .. } => self.handle_import_key(code),
+            Mode::ConfirmQuit => self.handle_confirm_quit_key(code),
+        }
+    }
+
+    /// Feed `keys` to `handle_key` in order, stopping at the first key that
+    /// ends the TUI.  Returns that key's `ExitAction`, or `None` when every
+    /// key was handled and the TUI would keep running.  Lets a whole session
+    /// be replayed in one call, e.g. in tests.
+    pub fn handle_keys<K: Into<KeyEvent>>(
+        &mut self,
+        keys: impl IntoIterator<Item = K>,
+    ) -> Option<ExitAction> {
+        keys.into_iter().find_map(|key| self.handle_key(key.into()))
+    }
+
+    /// Process a mouse event.  A left click selects the row under the cursor,
+    /// a double-click opens the confirmation dialog for it, and the scroll
+    /// wheel moves the selection.  Ignored while a popup is open.
+    pub fn handle_mouse(&mut self, event: MouseEvent) -> Option<ExitAction> {
+        if !matches!(self.mode, Mode::Browsing | Mode::Searching) {
+            return None;
+        }
+        match event.kind {
+            MouseEventKind::ScrollUp => self.move_up(),
+            MouseEventKind::ScrollDown => self.move_down(),
+            MouseEventKind::Down(MouseButton::Left) => {
+                let index = self.row_at(event.column, event.row)?;
+                let now = Instant::now();
+                let double = matches!(
+                    self.last_click,
+                    Some((i, at)) if i == index && now.duration_since(at) <= DOUBLE_CLICK_INTERVAL
+                );
+                self.pre_search_selection = None;
+                self.list_state.select(Some(index));
+                if double {
+                    self.last_click = None;
+                    self.mode = Mode::Browsing;
+                    return self.handle_browsing_key(KeyCode::Enter);
+                }
+                self.last_click = Some((index, now));
+            }
+            _ => {}
         }
+        None
     }
 
     fn handle_browsing_key(&mut self, code: KeyCode) -> Option<ExitAction> {
@@ -120,26 +551,230 @@ impl App {
                 None
             }
             KeyCode::Enter => {
-                if self.themes.is_empty() {
-                    None
-                } else {
-                    self.mode = Mode::Confirming;
+                self.open_confirmation();
+                None
+            }
+            KeyCode::Char('u') => {
+                self.jump_to_original_theme();
+                None
+            }
+            KeyCode::Char('a') => {
+                self.select_active();
+                None
+            }
+            // The event loop also maps Ctrl-R to F5
+            KeyCode::F(5) => {
+                self.reload_requested = true;
+                None
+            }
+            KeyCode::Char('s') => {
+                self.set_sort_key(self.sort_key.next());
+                self.save_prefs();
+                None
+            }
+            KeyCode::Char('f') => {
+                self.toggle_favorite();
+                None
+            }
+            KeyCode::Char('r') => {
+                match self.config.read_backup() {
+                    Ok((_, theme)) => self.mode = Mode::ConfirmingRestore(theme),
+                    Err(e) => self.status = Some(e.to_string()),
+                }
+                None
+            }
+            KeyCode::Char('o') => match self.highlighted_theme() {
+                Some(theme) => Some(ExitAction::OpenDir(theme.path.clone())),
+                None => {
+                    self.status = Some("No theme selected to open".to_string());
                     None
                 }
+            },
+            KeyCode::Char('c') => {
+                self.copy_theme_name();
+                None
+            }
+            KeyCode::Delete | KeyCode::Char('x') => {
+                self.start_delete();
+                None
+            }
+            KeyCode::Char(' ') => {
+                self.toggle_mark();
+                None
+            }
+            KeyCode::Char('m') => {
+                self.incomplete_only = !self.incomplete_only;
+                let keep = self.highlighted_theme().map(|t| t.name.clone());
+                self.refilter(keep);
+                self.save_prefs();
+                None
+            }
+            KeyCode::Char('t') => {
+                self.cycle_tag_filter();
+                None
+            }
+            KeyCode::Char('e') => {
+                let keys = self.config.theme_keys();
+                self.mode = Mode::EditKeys { keys, selected: 0, input: None };
+                None
+            }
+            KeyCode::Char('v') => {
+                self.mode = Mode::ViewConfig { scroll: 0 };
+                None
+            }
+            KeyCode::Char('i') => {
+                self.mode = Mode::Import { input: String::new() };
+                None
+            }
+            KeyCode::Char('d') => {
+                self.dense = !self.dense;
+                self.save_prefs();
+                None
+            }
+            KeyCode::Tab => {
+                self.table = !self.table;
+                self.table_overridden = false;
+                self.save_prefs();
+                None
+            }
+            KeyCode::Char('w') => {
+                self.wrap_navigation = !self.wrap_navigation;
+                self.wrap_overridden = false;
+                let state = if self.wrap_navigation { "on" } else { "off" };
+                self.status = Some(format!("Wrap-around navigation {state}"));
+                self.save_prefs();
+                None
+            }
+            KeyCode::Char('?') => {
+                self.mode = Mode::Help;
+                None
+            }
+            KeyCode::Char('/') => {
+                if self.search_query.is_empty() {
+                    self.pre_search_selection = self.highlighted_theme().map(|t| t.name.clone());
+                }
+                self.mode = Mode::Searching;
+                None
+            }
+            // Esc drops a committed filter first; a second Esc quits
+            KeyCode::Esc if !self.search_query.is_empty() => {
+                self.clear_search();
+                None
+            }
+            // ...and then the marks
+            KeyCode::Esc if !self.marked.is_empty() => {
+                self.marked.clear();
+                self.status = Some("Cleared marks".to_string());
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc if self.quit_discards_selection() => {
+                self.mode = Mode::ConfirmQuit;
+                None
+            }
+            KeyCode::Char('q') | KeyCode::Esc => self.quit(),
+            // Any other letter or digit jumps to a theme starting with it;
+            // the command keys above take precedence
+            KeyCode::Char(c) if c.is_alphanumeric() => {
+                self.jump_to_letter(c);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn handle_searching_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        match code {
+            KeyCode::Up => self.move_up(),
+            KeyCode::Down => self.move_down(),
+            KeyCode::Enter => self.mode = Mode::Browsing,
+            KeyCode::Esc => {
+                self.clear_search();
+                self.mode = Mode::Browsing;
+            }
+            KeyCode::Backspace => {
+                self.search_query.pop();
+                self.apply_filter();
+            }
+            KeyCode::Char(c) => {
+                self.search_query.push(c);
+                self.apply_filter();
+            }
+            _ => {}
+        }
+        None
+    }
+
+    /// Check the highlighted theme and ask for confirmation to apply it.
+    fn open_confirmation(&mut self) {
+        if let Some(theme) = self.highlighted_theme() {
+            let mut problems = theme.validate().err().unwrap_or_default();
+            if theme.shadowed {
+                problems.push(format!(
+                    "another '{}' directory takes precedence; SDDM loads that one",
+                    theme.name
+                ));
+            }
+            self.validation_problems = problems;
+            self.mode = Mode::Confirming;
+            self.confirm_opened = Some(Instant::now());
+        }
+    }
+
+    /// Whether quitting now would leave a highlighted theme other than the
+    /// active one unapplied, and `confirm_quit` asks to check first.
+    fn quit_discards_selection(&self) -> bool {
+        self.confirm_quit
+            && self.highlighted_theme().is_some_and(|theme| {
+                !theme.shadowed && !self.selects(self.current_theme.as_deref(), &theme.name)
+            })
+    }
+
+    fn handle_confirm_quit_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        match code {
+            // The usual confirmation, answered yes: a theme with problems
+            // still shows them and needs `Y`
+            KeyCode::Char('a') | KeyCode::Enter => {
+                self.open_confirmation();
+                self.handle_confirming_key(KeyCode::Enter)
+            }
+            KeyCode::Char('d') | KeyCode::Char('q') => self.quit(),
+            KeyCode::Char('c') | KeyCode::Esc => {
+                self.mode = Mode::Browsing;
+                None
             }
-            KeyCode::Char('q') | KeyCode::Esc => Some(ExitAction::Quit),
             _ => None,
         }
     }
 
     fn handle_confirming_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        // Any key shows someone is there, so the timeout starts over
+        self.confirm_opened = Some(Instant::now());
         match code {
             KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
-                let theme_name = self
+                let theme = self
                     .highlighted_theme()
-                    .map(|t| t.name.clone())
                     .expect("Confirming mode requires a selected theme");
-                Some(ExitAction::ApplyTheme(theme_name))
+                // Catch failures we can see now, while the TUI can still show them
+                if let Err(msg) = self.precheck_apply(theme) {
+                    self.mode = Mode::Error(msg);
+                    return None;
+                }
+                // A theme with problems needs the capital `Y` to force it
+                if !self.validation_problems.is_empty() && code != KeyCode::Char('Y') {
+                    return None;
+                }
+                let name = theme.name.clone();
+                if self.config.candidates.len() > 1 {
+                    self.target_selected = self
+                        .config
+                        .candidates
+                        .iter()
+                        .position(|p| *p == self.config.path)
+                        .unwrap_or(0);
+                    self.mode = Mode::ChooseTarget(name);
+                    return None;
+                }
+                self.apply_or_exit(name)
             }
             KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
                 self.mode = Mode::Browsing;
@@ -149,124 +784,2627 @@ impl App {
         }
     }
 
-    // -----------------------------------------------------------------------
-    // Cursor movement
-    // -----------------------------------------------------------------------
-
-    fn move_up(&mut self) {
-        if self.themes.is_empty() {
-            return;
+    fn handle_choose_target_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        let count = self.config.candidates.len();
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.target_selected = (self.target_selected + count - 1) % count;
+                None
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.target_selected = (self.target_selected + 1) % count;
+                None
+            }
+            KeyCode::Enter => {
+                let Mode::ChooseTarget(name) = std::mem::replace(&mut self.mode, Mode::Browsing)
+                else {
+                    return None;
+                };
+                let path = self.config.candidates[self.target_selected].clone();
+                if path != self.config.path {
+                    match self.config.with_target(&path) {
+                        Ok(config) => self.config = config,
+                        Err(e) => {
+                            self.mode = Mode::Error(e.to_string());
+                            return None;
+                        }
+                    }
+                }
+                if path.is_dir() {
+                    self.mode = Mode::Error(format!(
+                        "Config path {} is a directory, not a file.",
+                        path.display()
+                    ));
+                    return None;
+                }
+                self.apply_or_exit(name)
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = Mode::Browsing;
+                None
+            }
+            _ => None,
         }
-        let next = match self.list_state.selected() {
-            Some(0) | None => self.themes.len() - 1, // wrap to bottom
-            Some(i) => i - 1,
-        };
-        self.list_state.select(Some(next));
     }
 
-    fn move_down(&mut self) {
-        if self.themes.is_empty() {
-            return;
+    fn handle_confirming_restore_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        match code {
+            KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                Some(ExitAction::RestoreBackup)
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.mode = Mode::Browsing;
+                None
+            }
+            _ => None,
         }
-        let next = match self.list_state.selected() {
-            None => 0,
-            Some(i) => (i + 1) % self.themes.len(), // wrap to top
-        };
-        self.list_state.select(Some(next));
     }
-}
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+    fn handle_applied_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        match code {
+            KeyCode::Enter | KeyCode::Esc => {
+                self.mode = Mode::Browsing;
+                None
+            }
+            KeyCode::Char('q') => self.quit(),
+            KeyCode::Char('r') => Some(ExitAction::RestartDisplayManager),
+            _ => None,
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::theme::SddmTheme;
-    use std::path::PathBuf;
+    /// Quit, first showing the summary of a write made inside the TUI.
+    fn quit(&mut self) -> Option<ExitAction> {
+        if self.last_apply.is_some() {
+            self.mode = Mode::Result;
+            return None;
+        }
+        Some(ExitAction::Quit)
+    }
 
-    fn make_theme(name: &str) -> SddmTheme {
-        SddmTheme {
-            name: name.to_string(),
-            path: PathBuf::from("/tmp"),
-            description: None,
-            author: None,
+    fn handle_confirm_delete_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        let Mode::ConfirmDelete { names, input } = &mut self.mode else {
+            return None;
+        };
+        match code {
+            KeyCode::Esc => {
+                self.mode = Mode::Browsing;
+                self.status = Some("Nothing was deleted".to_string());
+            }
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            // Enter does nothing until the prompt is typed exactly
+            KeyCode::Enter if *input == delete_prompt(names) => {
+                let themes = names
+                    .iter()
+                    .filter_map(|name| self.themes.iter().find(|t| t.name == *name && !t.shadowed))
+                    .cloned()
+                    .collect();
+                return Some(ExitAction::DeleteThemes(themes));
+            }
+            _ => {}
         }
+        None
     }
 
-    fn make_app(names: &[&str], current: Option<&str>) -> App {
-        let themes: Vec<SddmTheme> = names.iter().map(|n| make_theme(n)).collect();
-        let config = SddmConfig::empty();
-        let mut app = App::new(themes, config);
-        // Override current_theme for test convenience
-        app.current_theme = current.map(|s| s.to_string());
-        app
+    fn handle_import_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        let Mode::Import { input } = &mut self.mode else {
+            return None;
+        };
+        match code {
+            KeyCode::Esc => self.mode = Mode::Browsing,
+            KeyCode::Backspace => {
+                input.pop();
+            }
+            KeyCode::Char(c) => input.push(c),
+            KeyCode::Enter if !input.trim().is_empty() => {
+                let source = expand_home(input.trim());
+                self.mode = Mode::Browsing;
+                self.import_theme(&source);
+            }
+            _ => {}
+        }
+        None
     }
 
-    #[test]
-    fn initial_selection_starts_at_zero_when_no_current() {
-        let app = make_app(&["alpha", "beta", "gamma"], None);
-        assert_eq!(app.selected_index(), Some(0));
+    /// Install the theme at `source` into `import_dir` and ask the event
+    /// loop to rescan, so `reload` can select it.
+    fn import_theme(&mut self, source: &Path) {
+        let Some(dir) = &self.import_dir else {
+            self.status = Some("Cannot import: $HOME is not set".to_string());
+            return;
+        };
+        match crate::import::import(source, dir) {
+            Ok(dest) => {
+                let name = dest.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                self.status = Some(format!("Imported '{name}' into {}", dir.display()));
+                self.imported = Some(name);
+                self.reload_requested = true;
+            }
+            Err(e) => self.status = Some(format!("Import failed: {e:#}")),
+        }
     }
 
-    #[test]
-    fn initial_selection_preselects_current_theme() {
-        let themes = vec![make_theme("alpha"), make_theme("beta"), make_theme("gamma")];
-        let mut config = SddmConfig::empty();
-        config.current_theme = Some("beta".to_string());
-        let app = App::new(themes, config);
-        assert_eq!(app.selected_index(), Some(1));
+    fn handle_edit_keys_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        let Mode::EditKeys { keys, selected, input } = &mut self.mode else {
+            return None;
+        };
+        // Typing a value: Enter keeps it, Esc drops it
+        if let Some(value) = input {
+            match code {
+                KeyCode::Enter => {
+                    keys[*selected].1 = std::mem::take(value);
+                    *input = None;
+                }
+                KeyCode::Esc => *input = None,
+                KeyCode::Backspace => {
+                    value.pop();
+                }
+                KeyCode::Char(c) => value.push(c),
+                _ => {}
+            }
+            return None;
+        }
+        match code {
+            KeyCode::Up | KeyCode::Char('k') => *selected = selected.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                *selected = (*selected + 1).min(keys.len().saturating_sub(1));
+            }
+            KeyCode::Enter if !keys.is_empty() => *input = Some(keys[*selected].1.clone()),
+            KeyCode::Char('w') => {
+                let keys = std::mem::take(keys);
+                return self.save_theme_keys(keys);
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('e') => {
+                let keys = std::mem::take(keys);
+                if !self.changed_theme_keys(&keys).is_empty() {
+                    self.status = Some("Discarded changes to [Theme]".to_string());
+                }
+                self.mode = Mode::Browsing;
+            }
+            _ => {}
+        }
+        None
     }
 
-    #[test]
-    fn move_down_wraps_at_end() {
-        let mut app = make_app(&["a", "b", "c"], None);
-        app.list_state.select(Some(2));
-        app.move_down();
-        assert_eq!(app.selected_index(), Some(0));
+    /// The keys in `keys` whose value differs from the config on disk.
+    fn changed_theme_keys(&self, keys: &[(String, String)]) -> HashMap<String, String> {
+        let original = self.config.theme_keys();
+        keys.iter()
+            .filter(|(key, value)| !original.iter().any(|(k, v)| k == key && v == value))
+            .cloned()
+            .collect()
     }
 
-    #[test]
-    fn move_up_wraps_at_start() {
-        let mut app = make_app(&["a", "b", "c"], None);
-        app.list_state.select(Some(0));
-        app.move_up();
-        assert_eq!(app.selected_index(), Some(2));
+    /// Save the edited `[Theme]` keys that changed the way `apply_or_exit`
+    /// saves a theme: written right away when that needs no sudo, otherwise
+    /// handed to the caller to write once the TUI has exited.
+    fn save_theme_keys(&mut self, keys: Vec<(String, String)>) -> Option<ExitAction> {
+        let changed = self.changed_theme_keys(&keys);
+        self.mode = Mode::Browsing;
+        if changed.is_empty() {
+            self.status = Some("No changes to [Theme]".to_string());
+            return None;
+        }
+        if self.apply_in_tui && !self.config.needs_sudo() {
+            self.write_theme_keys_now(&changed);
+            return None;
+        }
+        Some(ExitAction::WriteThemeKeys(changed))
     }
 
-    #[test]
-    fn enter_switches_to_confirming_mode() {
+    /// Write the changed `[Theme]` keys without leaving the TUI, like
+    /// `apply_now` writes a theme, and go back to browsing.
+    fn write_theme_keys_now(&mut self, changed: &HashMap<String, String>) {
+        let old = self.config.current_theme.clone();
+        let result = self.config.apply_keys(changed);
+        self.last_apply = Some(ApplyResult {
+            theme: self.config.effective_current().0.unwrap_or_default(),
+            config: self.config.path.clone(),
+            backup: result.as_ref().ok().cloned().flatten(),
+            // The greeter only picks up the new keys when it starts again
+            restart_needed: true,
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        match result {
+            Ok(backup) => {
+                self.applied = true;
+                let count = match changed.len() {
+                    1 => "1 key".to_string(),
+                    n => format!("{n} keys"),
+                };
+                self.status = Some(match backup {
+                    Some(b) => format!(
+                        "Saved {count} to {} (backup at {})",
+                        self.config.path.display(),
+                        b.display()
+                    ),
+                    None => format!("Saved {count} to {}", self.config.path.display()),
+                });
+                self.current_theme = self.config.effective_current().0;
+                if let (Some(name), Some(log)) = (changed.get("Current"), &self.history_path) {
+                    if let Err(e) =
+                        history::log_apply(log, old.as_deref(), name, &self.config.path)
+                    {
+                        self.status = Some(format!("Could not record history: {e:#}"));
+                    }
+                }
+            }
+            Err(e) => self.mode = Mode::Error(e.to_string()),
+        }
+    }
+
+    fn handle_help_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        if matches!(code, KeyCode::Char('?') | KeyCode::Esc | KeyCode::Char('q')) {
+            self.mode = Mode::Browsing;
+        }
+        None
+    }
+
+    /// Scroll the config view, stopping with its last line at the top.
+    fn handle_view_config_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        let last_line = self.config.raw_content().lines().count().saturating_sub(1);
+        let last_line = u16::try_from(last_line).unwrap_or(u16::MAX);
+        let Mode::ViewConfig { scroll } = &mut self.mode else {
+            return None;
+        };
+        match code {
+            KeyCode::Down | KeyCode::Char('j') => *scroll = (*scroll + 1).min(last_line),
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('v') => self.mode = Mode::Browsing,
+            _ => {}
+        }
+        None
+    }
+
+    fn handle_error_key(&mut self, code: KeyCode) -> Option<ExitAction> {
+        if matches!(code, KeyCode::Enter | KeyCode::Esc | KeyCode::Char('o')) {
+            self.mode = Mode::Browsing;
+        }
+        None
+    }
+
+    /// Time left before the confirmation dialog cancels itself, when it is
+    /// open and `confirm_timeout` is set.
+    pub fn confirm_remaining(&self, now: Instant) -> Option<Duration> {
+        if self.mode != Mode::Confirming {
+            return None;
+        }
+        let deadline = self.confirm_opened? + self.confirm_timeout?;
+        Some(deadline.saturating_duration_since(now))
+    }
+
+    /// Cancel the confirmation dialog once `confirm_timeout` has passed
+    /// without a key press.  Called by the event loop on every tick.
+    pub fn expire_confirmation(&mut self, now: Instant) {
+        if self.confirm_remaining(now) == Some(Duration::ZERO) {
+            self.mode = Mode::Browsing;
+            self.confirm_opened = None;
+            self.status = Some("Confirmation timed out; nothing was applied".to_string());
+        }
+    }
+
+    /// Index into `visible` of the list row drawn at the given screen cell,
+    /// if that cell is inside the list borders and holds a theme.
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        let inner = Rect::new(
+            area.x.saturating_add(1),
+            area.y.saturating_add(1),
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        );
+        if !inner.contains(Position::new(column, row)) {
+            return None;
+        }
+        // The table's column headings take the first row
+        let mut y = inner.y + u16::from(self.table);
+        if row < y {
+            return None;
+        }
+        for pos in self.list_state.offset()..self.visible.len() {
+            y += self.item_height(pos);
+            if row < y {
+                return Some(pos);
+            }
+        }
+        None
+    }
+
+    /// Open the delete confirmation for the marked themes (or the
+    /// highlighted one), unless the active theme is among them: deleting
+    /// that would leave SDDM without its theme.
+    fn start_delete(&mut self) {
+        let names = self.action_targets();
+        if names.is_empty() {
+            self.status = Some("No theme selected to delete".to_string());
+            return;
+        }
+        // Deletion goes by name, which would hit the copy SDDM loads
+        if let Some(theme) = self.highlighted_theme().filter(|t| t.shadowed) {
+            if self.marked.is_empty() {
+                self.status = Some(format!(
+                    "{} is a shadowed copy; delete it by hand",
+                    theme.path.display()
+                ));
+                return;
+            }
+        }
+        let current = self.current_theme.as_deref();
+        if let Some(name) = names.iter().find(|n| self.selects(current, n)) {
+            self.status = Some(format!(
+                "'{name}' is the active theme; apply another one before deleting it"
+            ));
+            return;
+        }
+        self.mode = Mode::ConfirmDelete {
+            names,
+            input: String::new(),
+        };
+    }
+
+    /// Names `f` and `x` act on: the marked themes in name order, or else
+    /// the highlighted one.
+    fn action_targets(&self) -> Vec<String> {
+        if self.marked.is_empty() {
+            return self.highlighted_theme().map(|t| t.name.clone()).into_iter().collect();
+        }
+        let mut names: Vec<String> = self.marked.iter().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Mark or unmark the highlighted theme.
+    fn toggle_mark(&mut self) {
+        let Some(name) = self.highlighted_theme().map(|t| t.name.clone()) else {
+            return;
+        };
+        if !self.marked.remove(&name) {
+            self.marked.insert(name);
+        }
+        self.status = Some(match self.marked.len() {
+            0 => "No themes marked".to_string(),
+            1 => "1 theme marked".to_string(),
+            n => format!("{n} themes marked"),
+        });
+    }
+
+    /// Checks that can be made before leaving the TUI.  Permission problems
+    /// are not errors here: the write escalates to sudo after exit.
+    fn precheck_apply(&self, theme: &SddmTheme) -> Result<(), String> {
+        if !theme.path.is_dir() {
+            return Err(format!(
+                "Theme directory {} no longer exists.",
+                theme.path.display()
+            ));
+        }
+        if self.config.path.is_dir() {
+            return Err(format!(
+                "Config path {} is a directory, not a file.",
+                self.config.path.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Write `name` right away when that needs no sudo, otherwise return it
+    /// for the caller to write once the TUI has exited.
+    fn apply_or_exit(&mut self, name: String) -> Option<ExitAction> {
+        if self.apply_in_tui && !self.config.needs_sudo() {
+            self.apply_now(&name);
+            return None;
+        }
+        Some(ExitAction::ApplyTheme(name))
+    }
+
+    /// Write `name` to the config without leaving the TUI, then show the
+    /// result in a popup.
+    fn apply_now(&mut self, name: &str) {
+        let old = self.config.current_theme.clone();
+        let result = self.config.apply_theme(name);
+        self.last_apply = Some(ApplyResult {
+            theme: name.to_string(),
+            config: self.config.path.clone(),
+            backup: result.as_ref().ok().cloned().flatten(),
+            restart_needed: !self.selects(self.original_theme.as_deref(), name),
+            error: result.as_ref().err().map(|e| e.to_string()),
+        });
+        match result {
+            Ok(backup) => {
+                self.applied = true;
+                self.status =
+                    backup.map(|b| format!("Previous config backed up to {}", b.display()));
+                let (effective, source) = self.config.effective_current();
+                if !self.selects(effective.as_deref(), name) {
+                    self.status = Some(format!(
+                        "{} overrides it with '{}'",
+                        source.display(),
+                        effective.as_deref().unwrap_or_default()
+                    ));
+                }
+                self.current_theme = effective;
+                if let Some(log) = &self.history_path {
+                    if let Err(e) =
+                        history::log_apply(log, old.as_deref(), name, &self.config.path)
+                    {
+                        self.status = Some(format!("Could not record history: {e:#}"));
+                    }
+                }
+                self.mode = Mode::Applied(name.to_string());
+            }
+            Err(e) => self.mode = Mode::Error(e.to_string()),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Favorites
+    // -----------------------------------------------------------------------
+
+    /// Replace the favorites set (e.g. with the one loaded at startup) and
+    /// re-sort so they move to the top.
+    pub fn set_favorites(&mut self, favorites: HashSet<String>, path: Option<PathBuf>) {
+        self.favorites = favorites;
+        self.favorites_path = path;
+        self.set_sort_key(self.sort_key);
+    }
+
+    /// Add or remove the highlighted theme from the favorites, re-sort, and
+    /// save the set.  A failed save is reported in the status bar.
+    ///
+    /// With themes marked, all of them are added instead (and the marks
+    /// cleared), or removed when every one is a favorite already.
+    fn toggle_favorite(&mut self) {
+        if !self.marked.is_empty() {
+            let names = std::mem::take(&mut self.marked);
+            let count = names.len();
+            if names.is_subset(&self.favorites) {
+                self.favorites.retain(|n| !names.contains(n));
+                self.status = Some(format!("Removed {count} marked themes from favorites"));
+            } else {
+                self.favorites.extend(names);
+                self.status = Some(format!("Added {count} marked themes to favorites"));
+            }
+        } else {
+            let Some(name) = self.highlighted_theme().map(|t| t.name.clone()) else {
+                self.status = Some("No theme selected".to_string());
+                return;
+            };
+            if self.favorites.remove(&name) {
+                self.status = Some(format!("Removed '{name}' from favorites"));
+            } else {
+                self.favorites.insert(name.clone());
+                self.status = Some(format!("Added '{name}' to favorites"));
+            }
+        }
+        self.set_sort_key(self.sort_key);
+
+        if let Some(path) = &self.favorites_path {
+            if let Err(e) = favorites::save(path, &self.favorites) {
+                self.status = Some(e.to_string());
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Preferences
+    // -----------------------------------------------------------------------
+
+    /// Adopt saved settings (e.g. the ones loaded at startup), saving later
+    /// changes to `path`.
+    pub fn set_prefs(&mut self, prefs: Prefs, path: Option<PathBuf>) {
+        self.dense = prefs.dense;
+        self.table = prefs.table;
+        self.wrap_navigation = prefs.wrap_navigation;
+        self.tag_filter = prefs.tag_filter.clone();
+        self.incomplete_only = prefs.incomplete_only;
+        self.prefs_path = path;
+        self.set_sort_key(prefs.sort_key);
+        self.saved_prefs = prefs;
+    }
+
+    /// Turn wrap-around navigation on or off for this session only: saving
+    /// the settings keeps the saved value until `w` is pressed.
+    pub fn override_wrap_navigation(&mut self, wrap: bool) {
+        self.wrap_navigation = wrap;
+        self.wrap_overridden = true;
+    }
+
+    /// Draw the list as a table (or not) for this session only, like
+    /// `override_wrap_navigation`; Tab makes the choice a saved one.
+    pub fn override_table(&mut self, table: bool) {
+        self.table = table;
+        self.table_overridden = true;
+    }
+
+    /// The settings `set_prefs` restores, as they are now, leaving out
+    /// session-only overrides.
+    pub fn prefs(&self) -> Prefs {
+        Prefs {
+            sort_key: self.sort_key,
+            dense: self.dense,
+            wrap_navigation: if self.wrap_overridden {
+                self.saved_prefs.wrap_navigation
+            } else {
+                self.wrap_navigation
+            },
+            table: if self.table_overridden { self.saved_prefs.table } else { self.table },
+            tag_filter: self.tag_filter.clone(),
+            incomplete_only: self.incomplete_only,
+        }
+    }
+
+    /// Write the settings to `prefs_path`, reporting a failure in the status
+    /// bar.
+    fn save_prefs(&mut self) {
+        if let Some(path) = &self.prefs_path {
+            if let Err(e) = prefs::save(path, &self.prefs()) {
+                self.status = Some(e.to_string());
+            }
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Clipboard
+    // -----------------------------------------------------------------------
+
+    /// Copy the highlighted theme's name to the clipboard, reporting the
+    /// outcome in the status bar.
+    fn copy_theme_name(&mut self) {
+        let Some(name) = self.highlighted_theme().map(|t| t.name.clone()) else {
+            self.status = Some("No theme selected to copy".to_string());
+            return;
+        };
+        self.status = Some(match clipboard::copy(&name) {
+            Ok(()) => format!("Copied '{name}' to clipboard"),
+            Err(e) => format!("Could not copy '{name}' to clipboard: {e}"),
+        });
+    }
+
+    // -----------------------------------------------------------------------
+    // Reload
+    // -----------------------------------------------------------------------
+
+    /// Swap in a freshly scanned theme list and config, keeping the sort
+    /// order, search filter and (when still installed) the highlighted theme.
+    pub fn reload(&mut self, themes: Vec<SddmTheme>, config: SddmConfig) {
+        let keep = self.highlighted_theme().map(|t| t.name.clone());
+        self.themes = themes;
+        let themes = &self.themes;
+        self.marked.retain(|name| themes.iter().any(|t| t.name == *name));
+        self.current_theme = config.effective_current().0;
+        self.config = config;
+        self.last_click = None;
+        self.sort_themes();
+        self.refilter(keep);
+        self.status = Some(format!("Reloaded: {} themes found", self.themes.len()));
+        if let Some(name) = self.imported.take() {
+            self.status = Some(if self.jump_to_theme(&name) {
+                format!("Imported '{name}'")
+            } else {
+                format!("Imported '{name}', but it was not found when rescanning")
+            });
+        }
+    }
+
+    /// Name the theme roots that were scanned in the "no themes found"
+    /// status, since `ThemeDir=`, `$THEMEWALKER_THEMES_DIR` and `theme_dirs`
+    /// all move them.
+    pub fn report_scanned_roots(&mut self, roots: &[(PathBuf, ThemeSource)]) {
+        if !self.themes.is_empty() || roots.is_empty() {
+            return;
+        }
+        let dirs: Vec<String> = roots.iter().map(|(d, _)| d.display().to_string()).collect();
+        self.status = Some(format!("No themes found in {}", dirs.join(", ")));
+    }
+
+    /// Explain theme roots that could not be listed for lack of permission,
+    /// replacing the "no themes found" status when nothing else was found.
+    pub fn report_unreadable_dirs(&mut self, dirs: &[PathBuf]) {
+        if dirs.is_empty() {
+            return;
+        }
+        let dirs: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+        let dirs = dirs.join(", ");
+        self.status = Some(if self.themes.is_empty() {
+            format!("Cannot read themes directory (permission denied): {dirs}")
+        } else {
+            format!("Cannot read {dirs} (permission denied); its themes are not listed")
+        });
+    }
+
+    /// Explain theme roots skipped because they are files, not directories.
+    pub fn report_non_directory_roots(&mut self, dirs: &[PathBuf]) {
+        if dirs.is_empty() {
+            return;
+        }
+        let dirs: Vec<String> = dirs.iter().map(|d| d.display().to_string()).collect();
+        self.status = Some(format!("Themes path is not a directory: {}", dirs.join(", ")));
+    }
+
+    /// Explain theme directories skipped because their names could corrupt
+    /// the config (see `is_safe_theme_name`).
+    pub fn report_unsafe_theme_dirs(&mut self, dirs: &[PathBuf]) {
+        if dirs.is_empty() {
+            return;
+        }
+        let names: Vec<String> = dirs
+            .iter()
+            .filter_map(|d| d.file_name())
+            .map(|n| format!("{:?}", n.to_string_lossy()))
+            .collect();
+        self.status = Some(format!(
+            "Skipped theme directories with unsafe names: {}",
+            names.join(", ")
+        ));
+    }
+
+    // -----------------------------------------------------------------------
+    // Background metadata
+    // -----------------------------------------------------------------------
+
+    /// Replace themes with their metadata-loaded versions (matched by name),
+    /// then re-sort and re-filter since both may depend on the new fields.
+    pub fn apply_metadata(&mut self, loaded: impl IntoIterator<Item = SddmTheme>) {
+        let mut changed = false;
+        for theme in loaded {
+            // By path too, as a shadowed copy shares its name
+            let slot = self
+                .themes
+                .iter_mut()
+                .find(|t| t.name == theme.name && t.path == theme.path);
+            if let Some(slot) = slot {
+                *slot = theme;
+                changed = true;
+            }
+        }
+        if changed {
+            self.set_sort_key(self.sort_key);
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Sorting
+    // -----------------------------------------------------------------------
+
+    /// Re-sort `themes` by `key`, keeping the highlighted theme selected.
+    pub fn set_sort_key(&mut self, key: SortKey) {
+        let keep = self.highlighted_theme().map(|t| t.name.clone());
+        self.sort_key = key;
+        self.sort_themes();
+        self.refilter(keep);
+    }
+
+    /// Sort `themes` by `sort_key`, favorites first.  Callers must refilter
+    /// afterwards.
+    fn sort_themes(&mut self) {
+        self.sort_by_current_key();
+        // Stable, so each group keeps the `sort_key` order
+        let favorites = &self.favorites;
+        self.themes.sort_by_key(|t| !favorites.contains(&t.name));
+    }
+
+    fn sort_by_current_key(&mut self) {
+        match self.sort_key {
+            SortKey::Name => self.themes.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortKey::Author => self.themes.sort_by(|a, b| {
+                let author = |t: &SddmTheme| t.author().map(str::to_lowercase);
+                match (author(a), author(b)) {
+                    (Some(x), Some(y)) => x.cmp(&y),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                }
+                .then_with(|| a.name.cmp(&b.name))
+            }),
+            // Newest first; `None` sorts before `Some`, so reversing puts it last
+            SortKey::Modified => self
+                .themes
+                .sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| a.name.cmp(&b.name))),
+        }
+    }
+
+    // -----------------------------------------------------------------------
+    // Search filter
+    // -----------------------------------------------------------------------
+
+    /// Recompute `visible` from `search_query`.  The highlighted theme stays
+    /// selected when it still matches; otherwise the first match is selected.
+    fn apply_filter(&mut self) {
+        let previous = self.highlighted_theme().map(|t| t.name.clone());
+        self.refilter(previous);
+    }
+
+    /// Recompute `visible` and select `keep` if it is visible, else the first item.
+    fn refilter(&mut self, keep: Option<String>) {
+        let query = self.search_query.to_lowercase();
+
+        self.visible = self
+            .themes
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| query.is_empty() || t.matches_query(&query))
+            .filter(|(_, t)| !self.incomplete_only || t.metadata_incomplete())
+            .filter(|(_, t)| self.tag_filter.iter().all(|tag| t.has_tag(tag)))
+            .map(|(i, _)| i)
+            .collect();
+
+        let no_match = format!("No themes match '{}'", self.search_query);
+        if !query.is_empty() && self.visible.is_empty() {
+            self.status = Some(no_match);
+        } else if self
+            .status
+            .as_deref()
+            .is_some_and(|s| s.starts_with("No themes match '"))
+        {
+            self.status = None;
+        }
+
+        let selection = keep
+            .and_then(|name| {
+                self.visible
+                    .iter()
+                    .position(|&i| self.themes[i].name == name)
+            })
+            .or(if self.visible.is_empty() { None } else { Some(0) });
+        self.list_state.select(selection);
+    }
+
+    /// Filter by the tag after the current one in `all_tags`, or stop
+    /// filtering after the last.  Does nothing when no theme declares tags.
+    fn cycle_tag_filter(&mut self) {
+        let tags = self.all_tags();
+        if tags.is_empty() {
+            self.status = Some("No theme declares any tags".to_string());
+            return;
+        }
+        let next = match &self.tag_filter {
+            None => 0,
+            Some(current) => tags
+                .iter()
+                .position(|t| t.to_lowercase() == current.to_lowercase())
+                .map_or(0, |i| i + 1),
+        };
+        self.tag_filter = tags.get(next).cloned();
+        let keep = self.highlighted_theme().map(|t| t.name.clone());
+        self.refilter(keep);
+        self.save_prefs();
+    }
+
+    /// Drop the filter.  The cursor returns to the theme highlighted before
+    /// the search, unless the user picked another one from the results.
+    fn clear_search(&mut self) {
+        let keep = self
+            .pre_search_selection
+            .take()
+            .or_else(|| self.highlighted_theme().map(|t| t.name.clone()));
+        self.search_query.clear();
+        self.refilter(keep);
+    }
+
+    // -----------------------------------------------------------------------
+    // Cursor movement
+    // -----------------------------------------------------------------------
+
+    /// Move the cursor to the theme that was active when the session started,
+    /// clearing the filters if they hide that theme.
+    fn jump_to_original_theme(&mut self) {
+        let Some(name) = self.original_theme.clone() else {
+            self.status = Some("No previously active theme to return to".to_string());
+            return;
+        };
+        if self.jump_to_theme(&name) {
+            self.status = Some("Jumped to previously active theme".to_string());
+        } else {
+            self.status = Some(format!("Previously active theme '{name}' is not installed"));
+        }
+    }
+
+    /// Move the cursor to the active theme (`current_theme`), clearing the
+    /// filters if they hide it.
+    pub fn select_active(&mut self) {
+        let Some(name) = self.current_theme.clone() else {
+            self.status = Some("No theme is set in the config".to_string());
+            return;
+        };
+        if self.jump_to_theme(&name) {
+            self.status = Some(format!("Jumped to active theme '{name}'"));
+        } else {
+            self.status = Some(format!("Active theme '{name}' is not installed"));
+        }
+    }
+
+    /// Select the theme named `name`, dropping the search, tag and metadata
+    /// filters when the theme is currently filtered out.  Returns `false` if
+    /// it is not installed.  An alias selects the theme it links to.
+    fn jump_to_theme(&mut self, name: &str) -> bool {
+        let Some(name) = self.themes.iter().find(|t| t.answers_to(name)).map(|t| t.name.clone())
+        else {
+            return false;
+        };
+        if !self.visible.iter().any(|&i| self.themes[i].name == name) {
+            self.search_query.clear();
+            self.pre_search_selection = None;
+            self.tag_filter = None;
+            self.incomplete_only = false;
+        }
+        self.refilter(Some(name));
+        true
+    }
+
+    fn move_up(&mut self) {
+        if self.visible.is_empty() {
+            return;
+        }
+        self.pre_search_selection = None;
+        let next = match self.list_state.selected() {
+            Some(0) if !self.wrap_navigation => 0,
+            Some(0) | None => self.visible.len() - 1, // wrap to bottom
+            Some(i) => i - 1,
+        };
+        self.list_state.select(Some(next));
+    }
+
+    /// Select the next visible theme after the highlighted one whose name
+    /// (as displayed) starts with `letter`, ignoring case and wrapping
+    /// around the list.  Pressing the same letter again cycles through the
+    /// matches.
+    fn jump_to_letter(&mut self, letter: char) {
+        let count = self.visible.len();
+        let start = self.list_state.selected().map_or(0, |i| i + 1);
+        let prefix = letter.to_lowercase().to_string();
+        let found = (0..count).map(|k| (start + k) % count).find(|&pos| {
+            self.themes[self.visible[pos]]
+                .display_name()
+                .to_lowercase()
+                .starts_with(&prefix)
+        });
+        if let Some(pos) = found {
+            self.pre_search_selection = None;
+            self.list_state.select(Some(pos));
+        }
+    }
+
+    fn move_down(&mut self) {
+        if self.visible.is_empty() {
+            return;
+        }
+        self.pre_search_selection = None;
+        let last = self.visible.len() - 1;
+        let next = match self.list_state.selected() {
+            None => 0,
+            Some(i) if i >= last && !self.wrap_navigation => last,
+            Some(i) => (i + 1) % self.visible.len(), // wrap to top
+        };
+        self.list_state.select(Some(next));
+    }
+}
+
+/// `path` with a leading `~/` replaced by `$HOME`, as a shell would.
+fn expand_home(path: &str) -> PathBuf {
+    match (path.strip_prefix("~/"), std::env::var_os("HOME")) {
+        (Some(rest), Some(home)) => PathBuf::from(home).join(rest),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// What the user must type to confirm deleting `names`: the theme's name
+/// for a single theme, or `delete <count>` for several.
+pub fn delete_prompt(names: &[String]) -> String {
+    match names {
+        [name] => name.clone(),
+        _ => format!("delete {}", names.len()),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::OnceLock;
+
+    /// A theme directory that passes `SddmTheme::validate`, created once per
+    /// test run and shared by all tests.
+    fn valid_theme_dir() -> PathBuf {
+        static DIR: OnceLock<tempfile::TempDir> = OnceLock::new();
+        let dir = DIR.get_or_init(|| {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(dir.path().join("metadata.desktop"), "[SddmGreeterTheme]\n").unwrap();
+            std::fs::write(dir.path().join("Main.qml"), "").unwrap();
+            dir
+        });
+        dir.path().to_path_buf()
+    }
+
+    fn make_theme(name: &str) -> SddmTheme {
+        SddmTheme {
+            name: name.to_string(),
+            pretty_name: None,
+            path: valid_theme_dir(),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            screenshot: None,
+            background_size: None,
+            source: ThemeSource::System,
+            modified: None,
+            configurable: false,
+            colors: Vec::new(),
+            metadata_loaded: true,
+            shadowed: false,
+            aliases: Vec::new(),
+        }
+    }
+
+    fn make_app(names: &[&str], current: Option<&str>) -> App {
+        let themes: Vec<SddmTheme> = names.iter().map(|n| make_theme(n)).collect();
+        let config = SddmConfig::empty();
+        let mut app = App::new(themes, config);
+        // Override current_theme for test convenience
+        app.current_theme = current.map(|s| s.to_string());
+        app
+    }
+
+    #[test]
+    fn initial_selection_starts_at_zero_when_no_current() {
+        let app = make_app(&["alpha", "beta", "gamma"], None);
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn initial_selection_falls_back_to_a_default_theme() {
+        let themes = ["alpha", "breeze", "maldives"].map(make_theme).to_vec();
+        let app = App::new(themes, SddmConfig::empty());
+        assert_eq!(highlighted_name(&app), Some("breeze"));
+        assert_eq!(
+            app.status.as_deref(),
+            Some("No theme is set; starting on the default 'breeze'")
+        );
+
+        // Without a known default the cursor starts at the top
+        let themes = ["alpha", "beta"].map(make_theme).to_vec();
+        let app = App::new(themes, SddmConfig::empty());
+        assert_eq!(app.selected_index(), Some(0));
+        assert_eq!(app.status, None);
+
+        // A set theme wins over the defaults
+        let themes = ["alpha", "beta", "breeze"].map(make_theme).to_vec();
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("beta".to_string());
+        assert_eq!(highlighted_name(&App::new(themes, config)), Some("beta"));
+    }
+
+    #[test]
+    fn active_match_uses_directory_name_not_pretty_name() {
+        let mut themes = vec![make_theme("alpha"), make_theme("beta")];
+        themes[0].pretty_name = Some("beta".to_string());
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("beta".to_string());
+        let app = App::new(themes, config);
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn initial_selection_preselects_current_theme() {
+        let themes = vec![make_theme("alpha"), make_theme("beta"), make_theme("gamma")];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("beta".to_string());
+        let app = App::new(themes, config);
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn missing_current_theme_sets_warning() {
+        let themes = vec![make_theme("alpha"), make_theme("beta")];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("gone".to_string());
+        let app = App::new(themes, config);
+        assert!(!app.current_theme_installed());
+        assert_eq!(app.status.as_deref(), Some("Active theme 'gone' is not installed"));
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn installed_current_theme_has_no_warning() {
+        let themes = vec![make_theme("alpha"), make_theme("beta")];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("beta".to_string());
+        let app = App::new(themes, config);
+        assert!(app.current_theme_installed());
+        assert!(app.status.is_none());
+    }
+
+    #[test]
+    fn current_theme_named_by_symlink_is_installed() {
+        let mut themes = vec![make_theme("alpha"), make_theme("breeze")];
+        themes[1].aliases = vec!["default".to_string()];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("default".to_string());
+        let app = App::new(themes, config);
+        assert!(app.current_theme_installed());
+        assert!(app.status.is_none());
+        assert_eq!(app.selected_index(), Some(1));
+        assert!(app.is_already_active());
+    }
+
+    #[test]
+    fn counts_themes_per_source() {
+        let mut themes = vec![make_theme("alpha"), make_theme("beta"), make_theme("gamma")];
+        themes[1].source = ThemeSource::User;
+        let app = App::new(themes, SddmConfig::empty());
+        assert_eq!(app.theme_source_counts(), (2, 1));
+    }
+
+    #[test]
+    fn move_down_wraps_at_end() {
+        let mut app = make_app(&["a", "b", "c"], None);
+        app.list_state.select(Some(2));
+        app.move_down();
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn move_up_wraps_at_start() {
+        let mut app = make_app(&["a", "b", "c"], None);
+        app.list_state.select(Some(0));
+        app.move_up();
+        assert_eq!(app.selected_index(), Some(2));
+    }
+
+    #[test]
+    fn clamped_navigation_stops_at_both_ends() {
+        let mut app = make_app(&["a", "b", "c"], None);
+        app.wrap_navigation = false;
+        app.list_state.select(Some(2));
+        app.move_down();
+        assert_eq!(app.selected_index(), Some(2));
+        app.list_state.select(Some(0));
+        app.move_up();
+        assert_eq!(app.selected_index(), Some(0));
+        app.move_down();
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn typing_a_letter_cycles_through_matching_themes() {
+        let mut app = make_app(&["aerial", "bloom", "bliss", "maya", "Blue"], None);
+        let names = |app: &App| app.highlighted_theme().unwrap().name.clone();
+        assert_eq!(names(&app), "aerial");
+
+        app.handle_key(KeyCode::Char('b').into());
+        let first = names(&app);
+        app.handle_key(KeyCode::Char('b').into());
+        let second = names(&app);
+        app.handle_key(KeyCode::Char('b').into());
+        let third = names(&app);
+        let mut seen = vec![first.clone(), second, third];
+        seen.sort();
+        assert_eq!(seen, ["Blue", "bliss", "bloom"]);
+
+        // Wraps back to the first match
+        app.handle_key(KeyCode::Char('B').into());
+        assert_eq!(names(&app), first);
+
+        // No match leaves the selection alone
+        app.handle_key(KeyCode::Char('z').into());
+        assert_eq!(names(&app), first);
+    }
+
+    #[test]
+    fn command_keys_are_not_used_for_jumping() {
+        let mut app = make_app(&["alpha", "beta", "jade", "quartz"], None);
+        app.handle_key(KeyCode::Char('j').into());
+        assert_eq!(app.highlighted_theme().unwrap().name, "beta");
+        assert!(matches!(app.handle_key(KeyCode::Char('q').into()), Some(ExitAction::Quit)));
+
+        // Upper case reaches themes that start with a command letter
+        let mut app = make_app(&["alpha", "beta", "jade", "quartz"], None);
+        app.handle_key(KeyCode::Char('Q').into());
+        assert_eq!(app.highlighted_theme().unwrap().name, "quartz");
+    }
+
+    #[test]
+    fn only_a_known_non_sddm_display_manager_is_reported() {
+        let mut app = make_app(&["a"], None);
+        assert_eq!(app.other_display_manager(), None);
+        app.display_manager = Some("sddm".to_string());
+        assert_eq!(app.other_display_manager(), None);
+        app.display_manager = Some("gdm".to_string());
+        assert_eq!(app.other_display_manager(), Some("gdm"));
+    }
+
+    #[test]
+    fn d_toggles_dense_list() {
+        let mut app = make_app(&["a", "b"], None);
+        assert!(!app.dense);
+        app.handle_key(KeyCode::Char('d').into());
+        assert!(app.dense);
+        assert_eq!(app.selected_index(), Some(0));
+        app.handle_key(KeyCode::Char('d').into());
+        assert!(!app.dense);
+    }
+
+    #[test]
+    fn w_toggles_wrap_navigation() {
+        let mut app = make_app(&["a", "b"], None);
+        app.handle_key(KeyCode::Char('w').into());
+        assert!(!app.wrap_navigation);
+        assert_eq!(app.status.as_deref(), Some("Wrap-around navigation off"));
+        app.handle_key(KeyCode::Up.into());
+        assert_eq!(app.selected_index(), Some(0));
+
+        app.handle_key(KeyCode::Char('w').into());
+        assert!(app.wrap_navigation);
+        app.handle_key(KeyCode::Up.into());
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn confirmation_times_out_without_a_key() {
+        let mut app = make_app(&["alpha"], None);
+        app.confirm_timeout = Some(Duration::from_secs(10));
+        app.handle_key(KeyCode::Enter.into());
+        let opened = app.confirm_opened.unwrap();
+
+        app.expire_confirmation(opened + Duration::from_secs(9));
+        assert_eq!(app.mode, Mode::Confirming);
+        assert_eq!(
+            app.confirm_remaining(opened + Duration::from_secs(9)),
+            Some(Duration::from_secs(1))
+        );
+
+        app.expire_confirmation(opened + Duration::from_secs(10));
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Confirmation timed out; nothing was applied")
+        );
+    }
+
+    #[test]
+    fn key_press_restarts_confirmation_timeout() {
+        let mut app = make_app(&["alpha"], None);
+        app.confirm_timeout = Some(Duration::from_secs(10));
+        app.handle_key(KeyCode::Enter.into());
+        app.confirm_opened = Some(Instant::now() - Duration::from_secs(9));
+
+        app.handle_key(KeyCode::Char('x').into());
+        app.expire_confirmation(Instant::now() + Duration::from_secs(5));
+        assert_eq!(app.mode, Mode::Confirming);
+    }
+
+    #[test]
+    fn confirmation_stays_open_without_timeout() {
+        let mut app = make_app(&["alpha"], None);
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.confirm_remaining(Instant::now()), None);
+        app.expire_confirmation(Instant::now() + Duration::from_secs(3600));
+        assert_eq!(app.mode, Mode::Confirming);
+    }
+
+    #[test]
+    fn enter_switches_to_confirming_mode() {
+        let mut app = make_app(&["alpha"], None);
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(result.is_none());
+        assert_eq!(app.mode, Mode::Confirming);
+    }
+
+    #[test]
+    fn confirming_enter_returns_apply_action() {
+        let mut app = make_app(&["alpha"], None);
+        app.mode = Mode::Confirming;
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "alpha"));
+    }
+
+    #[test]
+    fn confirming_missing_theme_dir_shows_error() {
+        let mut app = make_app(&["alpha"], None);
+        app.themes[0].path = PathBuf::from("/nonexistent/themewalker/alpha");
+        app.mode = Mode::Confirming;
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(result.is_none());
+        assert!(matches!(app.mode, Mode::Error(ref m) if m.contains("no longer exists")));
+
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn theme_with_problems_needs_capital_y() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = make_app(&["alpha"], None);
+        app.themes[0].path = dir.path().to_path_buf();
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Confirming);
+        assert_eq!(app.validation_problems, ["no metadata.desktop"]);
+
+        assert!(app.handle_key(KeyCode::Enter.into()).is_none());
+        assert!(app.handle_key(KeyCode::Char('y').into()).is_none());
+        assert_eq!(app.mode, Mode::Confirming);
+        let result = app.handle_key(KeyCode::Char('Y').into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "alpha"));
+    }
+
+    #[test]
+    fn clean_theme_accepts_lowercase_y() {
+        let mut app = make_app(&["alpha"], None);
+        app.handle_key(KeyCode::Enter.into());
+        assert!(app.validation_problems.is_empty());
+        let result = app.handle_key(KeyCode::Char('y').into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "alpha"));
+    }
+
+    #[test]
+    fn confirming_esc_returns_to_browsing() {
+        let mut app = make_app(&["alpha"], None);
+        app.mode = Mode::Confirming;
+        let result = app.handle_key(KeyCode::Esc.into());
+        assert!(result.is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn open_key_returns_theme_dir() {
+        let mut app = make_app(&["alpha"], None);
+        let result = app.handle_key(KeyCode::Char('o').into());
+        assert!(matches!(result, Some(ExitAction::OpenDir(ref p)) if *p == valid_theme_dir()));
+    }
+
+    #[test]
+    fn open_key_without_selection_sets_status() {
+        let mut app = make_app(&[], None);
+        assert!(app.handle_key(KeyCode::Char('o').into()).is_none());
+        assert_eq!(app.status.as_deref(), Some("No theme selected to open"));
+    }
+
+    #[test]
+    fn restore_key_without_backup_sets_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = SddmConfig::load_from(&dir.path().join("sddm.conf")).unwrap();
+        let mut app = App::new(vec![make_theme("alpha")], config);
+        assert!(app.handle_key(KeyCode::Char('r').into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert!(app.status.as_deref().is_some_and(|s| s.contains("No backup found")));
+    }
+
+    #[test]
+    fn restore_key_confirms_with_backup_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(dir.path().join("sddm.conf.themewalker.bak"), "[Theme]\nCurrent=beta\n")
+            .unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha")], config);
+
+        app.handle_key(KeyCode::Char('r').into());
+        assert_eq!(app.mode, Mode::ConfirmingRestore(Some("beta".to_string())));
+        let result = app.handle_key(KeyCode::Char('y').into());
+        assert!(matches!(result, Some(ExitAction::RestoreBackup)));
+    }
+
+    #[test]
+    fn delete_requires_typing_the_theme_name() {
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Char('x').into());
+        assert!(matches!(app.mode, Mode::ConfirmDelete { ref names, .. } if names == &["beta"]));
+
+        // Enter does nothing until the name matches, and typing `y` is not
+        // mistaken for a confirmation
+        assert!(app.handle_keys([KeyCode::Char('y'), KeyCode::Enter]).is_none());
+        let keys = [KeyCode::Backspace, KeyCode::Char('b'), KeyCode::Char('e')];
+        assert!(app.handle_keys(keys).is_none());
+        let keys = [KeyCode::Char('t'), KeyCode::Char('a'), KeyCode::Enter];
+        let result = app.handle_keys(keys);
+        assert!(matches!(result, Some(ExitAction::DeleteThemes(ref t)) if t[0].name == "beta"));
+    }
+
+    #[test]
+    fn shadowed_copy_warns_on_apply_and_is_not_deleted() {
+        let mut shadowed = make_theme("alpha");
+        shadowed.shadowed = true;
+        shadowed.path = PathBuf::from("/nonexistent/system/alpha");
+        let themes = vec![make_theme("alpha"), shadowed, make_theme("beta")];
+        let mut app = App::new(themes, SddmConfig::empty());
+        app.handle_key(KeyCode::Down.into());
+        assert!(app.highlighted_theme().is_some_and(|t| t.shadowed));
+
+        app.handle_key(KeyCode::Char('x').into());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("/nonexistent/system/alpha is a shadowed copy; delete it by hand")
+        );
+
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Confirming);
+        assert!(app.validation_problems.iter().any(|p| p.contains("takes precedence")));
+    }
+
+    #[test]
+    fn space_marks_and_unmarks() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.handle_keys([KeyCode::Char(' '), KeyCode::Down, KeyCode::Char(' ')]);
+        assert_eq!(app.marked, HashSet::from(["alpha".to_string(), "beta".to_string()]));
+        assert_eq!(app.status.as_deref(), Some("2 themes marked"));
+
+        app.handle_key(KeyCode::Char(' ').into());
+        assert_eq!(app.marked, HashSet::from(["alpha".to_string()]));
+
+        // Esc clears the marks before it quits
+        assert!(app.handle_key(KeyCode::Esc.into()).is_none());
+        assert!(app.marked.is_empty());
+        assert!(matches!(app.handle_key(KeyCode::Esc.into()), Some(ExitAction::Quit)));
+    }
+
+    #[test]
+    fn favorite_acts_on_marked_themes() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.handle_keys([KeyCode::Char(' '), KeyCode::Down, KeyCode::Down, KeyCode::Char(' ')]);
+        app.handle_key(KeyCode::Char('f').into());
+        assert_eq!(app.favorites, HashSet::from(["alpha".to_string(), "gamma".to_string()]));
+        assert_eq!(app.status.as_deref(), Some("Added 2 marked themes to favorites"));
+        assert!(app.marked.is_empty());
+
+        // Marking only favorites removes them all
+        app.marked = app.favorites.clone();
+        app.handle_key(KeyCode::Char('f').into());
+        assert!(app.favorites.is_empty());
+
+        // A mix is added, not toggled one by one
+        app.favorites.insert("alpha".to_string());
+        app.marked = HashSet::from(["alpha".to_string(), "beta".to_string()]);
+        app.handle_key(KeyCode::Char('f').into());
+        assert_eq!(app.favorites, HashSet::from(["alpha".to_string(), "beta".to_string()]));
+    }
+
+    #[test]
+    fn delete_acts_on_marked_themes() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], Some("alpha"));
+        app.marked = HashSet::from(["gamma".to_string(), "beta".to_string()]);
+        app.handle_key(KeyCode::Char('x').into());
+        let names = ["beta".to_string(), "gamma".to_string()];
+        assert!(matches!(app.mode, Mode::ConfirmDelete { names: ref n, .. } if *n == names));
+
+        let result = app.handle_keys("delete 2\n".chars().map(|c| match c {
+            '\n' => KeyCode::Enter,
+            c => KeyCode::Char(c),
+        }));
+        let Some(ExitAction::DeleteThemes(themes)) = result else {
+            panic!("expected DeleteThemes, got {result:?}");
+        };
+        let deleted: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(deleted, ["beta", "gamma"]);
+
+        // The active theme spoils the whole batch
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.marked = HashSet::from(["alpha".to_string(), "beta".to_string()]);
+        app.handle_key(KeyCode::Char('x').into());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn reload_drops_marks_of_removed_themes() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.marked = HashSet::from(["alpha".to_string(), "beta".to_string()]);
+        app.reload(vec![make_theme("alpha")], SddmConfig::empty());
+        assert_eq!(app.marked, HashSet::from(["alpha".to_string()]));
+    }
+
+    #[test]
+    fn esc_cancels_delete() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_keys([KeyCode::Delete, KeyCode::Char('a'), KeyCode::Esc]);
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.status.as_deref(), Some("Nothing was deleted"));
+    }
+
+    #[test]
+    fn refuses_to_delete_active_theme() {
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.handle_key(KeyCode::Char('a').into());
+        assert!(app.handle_key(KeyCode::Char('x').into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert!(app.status.as_deref().is_some_and(|s| s.contains("is the active theme")));
+    }
+
+    #[test]
+    fn refuses_to_delete_theme_active_through_alias() {
+        let mut app = make_app(&["alpha", "breeze"], Some("default"));
+        app.themes[1].aliases = vec!["default".to_string()];
+        app.handle_key(KeyCode::Char('a').into());
+        assert_eq!(highlighted_name(&app), Some("breeze"));
+        assert!(app.handle_key(KeyCode::Char('x').into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert!(app.status.as_deref().is_some_and(|s| s.contains("'breeze' is the active theme")));
+
+        // Nor is quitting on it leaving a theme unapplied
+        app.confirm_quit = true;
+        assert!(!app.quit_discards_selection());
+    }
+
+    #[test]
+    fn reports_unsafe_theme_dirs_escaped() {
+        let mut app = make_app(&["alpha"], None);
+        app.report_unsafe_theme_dirs(&[]);
+        assert!(app.status.is_none());
+        app.report_unsafe_theme_dirs(&[PathBuf::from("/themes/evil\nRootLogin=true")]);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Skipped theme directories with unsafe names: \"evil\\nRootLogin=true\"")
+        );
+    }
+
+    #[test]
+    fn apply_metadata_refilters_and_keeps_selection() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        for t in &mut app.themes {
+            t.metadata_loaded = false;
+        }
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "dark");
+        assert!(app.visible.is_empty());
+
+        let mut beta = make_theme("beta");
+        beta.metadata.insert("Description".to_string(), "Dark theme".to_string());
+        let mut gamma = make_theme("gamma");
+        gamma.metadata.insert("Description".to_string(), "Dark too".to_string());
+        app.apply_metadata(vec![beta]);
+        assert_eq!(highlighted_name(&app), Some("beta"));
+
+        app.apply_metadata(vec![gamma]);
+        assert_eq!(app.visible.len(), 2);
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert!(!app.themes[0].metadata_loaded);
+        assert!(app.themes[2].metadata_loaded);
+    }
+
+    #[test]
+    fn is_already_active_tracks_highlight() {
+        let mut app = make_app(&["alpha", "beta"], Some("beta"));
+        assert!(!app.is_already_active());
+        app.handle_key(KeyCode::Down.into());
+        assert!(app.is_already_active());
+
+        // Re-applying the active theme is still allowed
+        app.handle_key(KeyCode::Enter.into());
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "beta"));
+    }
+
+    #[test]
+    fn is_already_active_false_without_current() {
+        let app = make_app(&["alpha"], None);
+        assert!(!app.is_already_active());
+    }
+
+    #[test]
+    fn apply_in_tui_writes_directly_and_keeps_browsing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=alpha\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha"), make_theme("beta")], config);
+        app.apply_in_tui = true;
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Enter.into());
+        assert!(!app.applied);
+        assert!(app.handle_key(KeyCode::Enter.into()).is_none());
+        assert!(app.applied);
+        assert_eq!(app.mode, Mode::Applied("beta".to_string()));
+        assert_eq!(app.current_theme.as_deref(), Some("beta"));
+        assert!(app.status.as_deref().is_some_and(|s| s.contains("backed up")));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=beta\n");
+
+        assert!(app.handle_key(KeyCode::Enter.into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn reapplying_the_original_theme_through_its_alias_needs_no_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=default\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut themes = vec![make_theme("alpha"), make_theme("breeze")];
+        themes[1].aliases = vec!["default".to_string()];
+        let mut app = App::new(themes, config);
+        app.apply_in_tui = true;
+
+        app.apply_now("alpha");
+        assert!(app.last_apply.as_ref().unwrap().restart_needed);
+        app.apply_now("breeze");
+        assert!(!app.last_apply.as_ref().unwrap().restart_needed);
+        // The theme SDDM ends up with is the one just written
+        assert!(app.status.is_none_or(|s| !s.contains("overrides")));
+    }
+
+    #[test]
+    fn e_edits_theme_keys_and_w_writes_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=alpha\nCursorTheme=Adwaita\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha")], config);
+        app.apply_in_tui = true;
+
+        app.handle_key(KeyCode::Char('e').into());
+        let Mode::EditKeys { keys, .. } = &app.mode else {
+            panic!("expected the key editor, got {:?}", app.mode);
+        };
+        assert_eq!(keys[1], ("CursorTheme".to_string(), "Adwaita".to_string()));
+
+        // Esc while typing drops the value; the key keeps its old one
+        app.handle_keys([KeyCode::Down, KeyCode::Enter, KeyCode::Char('x'), KeyCode::Esc]);
+        assert!(matches!(&app.mode, Mode::EditKeys { keys, input: None, .. }
+            if keys[1].1 == "Adwaita"));
+
+        let mut keys = vec![KeyCode::Enter];
+        keys.extend([KeyCode::Backspace; 7]);
+        keys.extend("breeze_cursors".chars().map(KeyCode::Char));
+        keys.extend([KeyCode::Enter, KeyCode::Char('w')]);
+        assert!(app.handle_keys(keys).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert!(app.applied);
+        assert!(app.status.as_deref().is_some_and(|s| s.starts_with("Saved 1 key to")));
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "[Theme]\nCurrent=alpha\nCursorTheme=breeze_cursors\n"
+        );
+        let result = app.last_apply.as_ref().unwrap();
+        assert_eq!((result.theme.as_str(), &result.config), ("alpha", &path));
+        assert_eq!(result.error, None);
+    }
+
+    #[test]
+    fn saving_keys_without_in_tui_writes_exits_to_write_them() {
+        // As under --dry-run, --run-hooks or --restart
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=alpha\n").unwrap();
+        let mut app = App::new(vec![make_theme("alpha")], SddmConfig::load_from(&path).unwrap());
+
+        let mut keys = vec![KeyCode::Char('e'), KeyCode::Enter];
+        keys.extend([KeyCode::Backspace; 5]);
+        keys.extend("beta".chars().map(KeyCode::Char));
+        keys.extend([KeyCode::Enter, KeyCode::Char('w')]);
+        let result = app.handle_keys(keys);
+        let Some(ExitAction::WriteThemeKeys(keys)) = result else {
+            panic!("expected WriteThemeKeys, got {result:?}");
+        };
+        assert_eq!(keys, HashMap::from([("Current".to_string(), "beta".to_string())]));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=alpha\n");
+        assert!(!app.applied);
+        assert_eq!(app.last_apply, None);
+    }
+
+    #[test]
+    fn saving_keys_that_need_sudo_exits_to_write_them() {
+        // Nothing on this relative path exists, so writing it needs sudo
+        let mut config = SddmConfig::empty();
+        config.path = PathBuf::from("themewalker-missing-dir/sddm.conf");
+        config.current_theme = Some("alpha".to_string());
+        assert!(config.needs_sudo());
+        let mut app = App::new(vec![make_theme("alpha")], config);
+        app.apply_in_tui = true;
+        app.mode = Mode::EditKeys {
+            keys: vec![("Current".to_string(), "beta".to_string())],
+            selected: 0,
+            input: None,
+        };
+
+        let result = app.handle_key(KeyCode::Char('w').into());
+        assert!(matches!(result, Some(ExitAction::WriteThemeKeys(ref keys))
+            if keys.get("Current").map(String::as_str) == Some("beta")));
+        assert!(!app.config.path.exists());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn closing_the_key_editor_discards_edits() {
+        let mut app = make_app(&["alpha"], None);
+        app.config = SddmConfig::empty();
+        app.handle_key(KeyCode::Char('e').into());
+        assert!(matches!(&app.mode, Mode::EditKeys { keys, .. } if keys.is_empty()));
+        // Nothing to edit, and nothing to save
+        app.handle_keys([KeyCode::Enter, KeyCode::Char('w')]);
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.status.as_deref(), Some("No changes to [Theme]"));
+
+        app.mode = Mode::EditKeys {
+            keys: vec![("Font".to_string(), "Sans".to_string())],
+            selected: 0,
+            input: None,
+        };
+        app.handle_key(KeyCode::Esc.into());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.status.as_deref(), Some("Discarded changes to [Theme]"));
+    }
+
+    #[test]
+    fn v_views_the_config_and_scrolls_within_it() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[General]\nNumlock=on\n\n[Theme]\nCurrent=alpha\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha")], config);
+
+        app.handle_key(KeyCode::Char('v').into());
+        assert_eq!(app.mode, Mode::ViewConfig { scroll: 0 });
+        app.handle_key(KeyCode::Char('k').into());
+        assert_eq!(app.mode, Mode::ViewConfig { scroll: 0 });
+        // Stops once the last of the five lines is at the top
+        app.handle_keys([KeyCode::Char('j'); 9]);
+        assert_eq!(app.mode, Mode::ViewConfig { scroll: 4 });
+        app.handle_key(KeyCode::Up.into());
+        assert_eq!(app.mode, Mode::ViewConfig { scroll: 3 });
+        assert!(app.handle_key(KeyCode::Char('q').into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    /// An app whose config has two candidate files, `a.conf` (the target,
+    /// setting `alpha`) and `b.conf`.
+    fn app_with_two_targets(dir: &std::path::Path) -> App {
+        let a = dir.join("a.conf");
+        let b = dir.join("b.conf");
+        std::fs::write(&a, "[Theme]\nCurrent=alpha\n").unwrap();
+        std::fs::write(&b, "[General]\nNumlock=on\n").unwrap();
+        let mut config = SddmConfig::load_from(&a).unwrap();
+        config.candidates = vec![a, b];
+        App::new(vec![make_theme("alpha"), make_theme("beta")], config)
+    }
+
+    #[test]
+    fn confirming_with_several_targets_asks_where_to_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with_two_targets(dir.path());
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Enter.into());
+        assert!(app.handle_key(KeyCode::Enter.into()).is_none());
+        assert_eq!(app.mode, Mode::ChooseTarget("beta".to_string()));
+        assert_eq!(app.target_selected, 0);
+
+        app.handle_key(KeyCode::Down.into());
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "beta"));
+        assert_eq!(app.config.path, dir.path().join("b.conf"));
+        assert_eq!(app.config.raw_content(), "[General]\nNumlock=on\n");
+        assert_eq!(app.config.candidates.len(), 2);
+    }
+
+    #[test]
+    fn chosen_target_is_written_in_tui() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with_two_targets(dir.path());
+        app.apply_in_tui = true;
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Up.into());
+        assert!(app.handle_key(KeyCode::Enter.into()).is_none());
+        assert_eq!(app.mode, Mode::Applied("beta".to_string()));
+        let b = std::fs::read_to_string(dir.path().join("b.conf")).unwrap();
+        assert!(b.starts_with("[General]\nNumlock=on\n"));
+        assert!(b.ends_with("[Theme]\nCurrent=beta\n"));
+        let a = std::fs::read_to_string(dir.path().join("a.conf")).unwrap();
+        assert_eq!(a, "[Theme]\nCurrent=alpha\n");
+    }
+
+    #[test]
+    fn esc_in_target_chooser_cancels() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut app = app_with_two_targets(dir.path());
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Down.into());
+        assert!(app.handle_key(KeyCode::Esc.into()).is_none());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.config.path, dir.path().join("a.conf"));
+    }
+
+    #[test]
+    fn apply_in_tui_records_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=alpha\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha"), make_theme("beta")], config);
+        app.apply_in_tui = true;
+        let log = dir.path().join("state/history.log");
+        app.history_path = Some(log.clone());
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Enter.into());
+        let content = std::fs::read_to_string(&log).unwrap();
+        assert!(content.ends_with(&format!("alpha -> beta  {}\n", path.display())));
+    }
+
+    #[test]
+    fn q_in_applied_popup_quits() {
+        let mut app = make_app(&["alpha"], None);
+        app.mode = Mode::Applied("alpha".to_string());
+        assert!(matches!(app.handle_key(KeyCode::Char('q').into()), Some(ExitAction::Quit)));
+    }
+
+    #[test]
+    fn quitting_after_an_apply_shows_the_result_first() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=alpha\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        let mut app = App::new(vec![make_theme("alpha"), make_theme("beta")], config);
+        app.apply_in_tui = true;
+
+        app.handle_keys([KeyCode::Down, KeyCode::Enter, KeyCode::Enter]);
+        assert!(app.handle_key(KeyCode::Char('q').into()).is_none());
+        assert_eq!(app.mode, Mode::Result);
+        assert_eq!(
+            app.last_apply,
+            Some(ApplyResult {
+                theme: "beta".to_string(),
+                config: path.clone(),
+                backup: Some(app.config.backup_path()),
+                restart_needed: true,
+                error: None,
+            })
+        );
+        // Any key quits from the summary
+        assert!(matches!(app.handle_key(KeyCode::Char('x').into()), Some(ExitAction::Quit)));
+    }
+
+    #[test]
+    fn failed_apply_is_summarised_on_quit() {
         let mut app = make_app(&["alpha"], None);
-        let result = app.handle_key(KeyCode::Enter);
-        assert!(result.is_none());
+        app.last_apply = Some(ApplyResult {
+            theme: "alpha".to_string(),
+            config: PathBuf::from("/etc/sddm.conf"),
+            backup: None,
+            restart_needed: true,
+            error: Some("Permission denied".to_string()),
+        });
+        assert!(app.handle_key(KeyCode::Esc.into()).is_none());
+        assert_eq!(app.mode, Mode::Result);
+        assert!(matches!(app.handle_key(KeyCode::Enter.into()), Some(ExitAction::Quit)));
+    }
+
+    #[test]
+    fn r_in_applied_popup_asks_for_a_restart() {
+        let mut app = make_app(&["alpha"], None);
+        app.mode = Mode::Applied("alpha".to_string());
+        assert!(matches!(
+            app.handle_key(KeyCode::Char('r').into()),
+            Some(ExitAction::RestartDisplayManager)
+        ));
+    }
+
+    fn click(column: u16, row: u16) -> MouseEvent {
+        MouseEvent {
+            kind: MouseEventKind::Down(MouseButton::Left),
+            column,
+            row,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+
+    #[test]
+    fn click_selects_row_and_double_click_confirms() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.list_area = Rect::new(0, 3, 40, 10);
+
+        // Row 4 is the first line inside the top border, row 5 the second
+        assert!(app.handle_mouse(click(5, 5)).is_none());
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert_eq!(app.mode, Mode::Browsing);
+
+        assert!(app.handle_mouse(click(5, 5)).is_none());
         assert_eq!(app.mode, Mode::Confirming);
     }
 
     #[test]
-    fn confirming_enter_returns_apply_action() {
+    fn click_outside_rows_is_ignored() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.list_area = Rect::new(0, 3, 40, 10);
+        app.handle_key(KeyCode::Down.into());
+
+        for (column, row) in [(5, 3), (0, 5), (5, 8), (50, 5)] {
+            app.handle_mouse(click(column, row));
+            assert_eq!(highlighted_name(&app), Some("beta"));
+        }
+        assert!(app.last_click.is_none());
+    }
+
+    #[test]
+    fn click_on_different_rows_is_not_a_double_click() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.list_area = Rect::new(0, 0, 40, 10);
+        app.handle_mouse(click(5, 1));
+        app.handle_mouse(click(5, 2));
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(highlighted_name(&app), Some("beta"));
+    }
+
+    #[test]
+    fn scroll_wheel_moves_selection() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        let scroll = |kind| MouseEvent {
+            kind,
+            column: 0,
+            row: 0,
+            modifiers: KeyModifiers::NONE,
+        };
+        app.handle_mouse(scroll(MouseEventKind::ScrollDown));
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        app.handle_mouse(scroll(MouseEventKind::ScrollUp));
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn mouse_ignored_while_popup_open() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.list_area = Rect::new(0, 0, 40, 10);
+        app.mode = Mode::Help;
+        app.handle_mouse(click(5, 2));
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn f5_requests_reload() {
         let mut app = make_app(&["alpha"], None);
-        app.mode = Mode::Confirming;
-        let result = app.handle_key(KeyCode::Enter);
-        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "alpha"));
+        assert!(app.handle_key(KeyCode::F(5).into()).is_none());
+        assert!(app.reload_requested);
+
+        // Ctrl-R is an alias; plain `r` asks to restore the backup instead
+        let mut app = make_app(&["alpha"], None);
+        app.handle_key(ctrl('r'));
+        assert!(app.reload_requested);
     }
 
     #[test]
-    fn confirming_esc_returns_to_browsing() {
+    fn scanned_roots_explain_empty_list() {
+        let roots = vec![
+            (PathBuf::from("/srv/sddm/themes"), ThemeSource::System),
+            (PathBuf::from("/opt/sddm-themes"), ThemeSource::User),
+        ];
         let mut app = make_app(&["alpha"], None);
-        app.mode = Mode::Confirming;
-        let result = app.handle_key(KeyCode::Esc);
-        assert!(result.is_none());
+        app.report_scanned_roots(&roots);
+        assert!(app.status.is_none());
+
+        let mut app = make_app(&[], None);
+        app.report_scanned_roots(&roots);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("No themes found in /srv/sddm/themes, /opt/sddm-themes")
+        );
+    }
+
+    #[test]
+    fn non_directory_roots_are_reported() {
+        let mut app = make_app(&[], None);
+        app.report_non_directory_roots(&[]);
+        assert!(app.status.as_deref().unwrap().starts_with("No themes found"));
+
+        app.report_non_directory_roots(&[PathBuf::from("/usr/share/sddm/themes")]);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Themes path is not a directory: /usr/share/sddm/themes")
+        );
+    }
+
+    #[test]
+    fn unreadable_dirs_explain_empty_list() {
+        let mut app = make_app(&[], None);
+        app.report_unreadable_dirs(&[]);
+        assert!(app.status.as_deref().unwrap().starts_with("No themes found"));
+
+        app.report_unreadable_dirs(&[PathBuf::from("/usr/share/sddm/themes")]);
+        assert_eq!(
+            app.status.as_deref(),
+            Some("Cannot read themes directory (permission denied): /usr/share/sddm/themes")
+        );
+
+        let mut app = make_app(&["alpha"], None);
+        app.report_unreadable_dirs(&[PathBuf::from("/home/u/.local/share/sddm/themes")]);
+        assert_eq!(
+            app.status.as_deref(),
+            Some(
+                "Cannot read /home/u/.local/share/sddm/themes (permission denied); \
+                 its themes are not listed"
+            )
+        );
+    }
+
+    #[test]
+    fn reload_keeps_selection_by_name() {
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.handle_key(KeyCode::Down.into());
+
+        let themes = ["aardvark", "alpha", "beta"].map(make_theme).to_vec();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        std::fs::write(&path, "[Theme]\nCurrent=aardvark\n").unwrap();
+        app.reload(themes, SddmConfig::load_from(&path).unwrap());
+
+        assert_eq!(app.themes.len(), 3);
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert_eq!(app.current_theme.as_deref(), Some("aardvark"));
+        assert_eq!(app.status.as_deref(), Some("Reloaded: 3 themes found"));
+    }
+
+    #[test]
+    fn import_installs_the_theme_and_selects_it_after_reload() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("download/maya");
+        std::fs::create_dir_all(&source).unwrap();
+        std::fs::write(source.join("Main.qml"), "Rectangle {}\n").unwrap();
+        let themes_dir = tmp.path().join("themes");
+
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.import_dir = Some(themes_dir.clone());
+        app.handle_key(KeyCode::Char('i').into());
+        assert!(matches!(app.mode, Mode::Import { .. }));
+        for c in source.to_str().unwrap().chars() {
+            app.handle_key(KeyCode::Char(c).into());
+        }
+        app.handle_key(KeyCode::Enter.into());
+
+        assert!(matches!(app.mode, Mode::Browsing));
+        assert!(themes_dir.join("maya/Main.qml").exists());
+        assert!(app.reload_requested);
+        let themes = ["alpha", "beta", "maya"].map(make_theme).to_vec();
+        app.reload(themes, SddmConfig::empty());
+        assert_eq!(highlighted_name(&app), Some("maya"));
+        assert_eq!(app.status.as_deref(), Some("Imported 'maya'"));
+    }
+
+    #[test]
+    fn failed_import_reports_the_error() {
+        let tmp = tempfile::tempdir().unwrap();
+        let mut app = make_app(&["alpha"], None);
+        app.import_dir = Some(tmp.path().join("themes"));
+        app.handle_keys("iX".chars().map(KeyCode::Char));
+        app.handle_key(KeyCode::Backspace.into());
+        app.handle_keys("/no/such/theme".chars().map(KeyCode::Char));
+        app.handle_key(KeyCode::Enter.into());
+
+        assert!(!app.reload_requested);
+        let status = app.status.clone().unwrap();
+        assert!(status.starts_with("Import failed: /no/such/theme does not exist"), "{status}");
+    }
+
+    #[test]
+    fn quit_asks_first_when_another_theme_is_highlighted() {
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.confirm_quit = true;
+        // Nothing to lose on the active theme
+        assert!(matches!(app.handle_key(KeyCode::Char('q').into()), Some(ExitAction::Quit)));
+
+        app.handle_key(KeyCode::Down.into());
+        assert!(app.handle_key(KeyCode::Char('q').into()).is_none());
+        assert!(matches!(app.mode, Mode::ConfirmQuit));
+        app.handle_key(KeyCode::Esc.into());
+        assert!(matches!(app.mode, Mode::Browsing));
+        assert_eq!(highlighted_name(&app), Some("beta"));
+
+        app.handle_key(KeyCode::Esc.into());
+        assert!(matches!(app.handle_key(KeyCode::Char('d').into()), Some(ExitAction::Quit)));
+
+        // Off by default
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.handle_key(KeyCode::Down.into());
+        assert!(matches!(app.handle_key(KeyCode::Char('q').into()), Some(ExitAction::Quit)));
+    }
+
+    #[test]
+    fn confirm_quit_applies_the_highlighted_theme() {
+        let mut app = make_app(&["alpha", "beta"], Some("alpha"));
+        app.confirm_quit = true;
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Char('q').into());
+        let result = app.handle_key(KeyCode::Char('a').into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref name)) if name == "beta"));
+    }
+
+    #[test]
+    fn reload_falls_back_to_first_when_selection_removed() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Down.into());
+        app.reload(vec![make_theme("gamma"), make_theme("alpha")], SddmConfig::empty());
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn favorites_sort_first_and_persist() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("themewalker/favorites.toml");
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.set_favorites(HashSet::new(), Some(path.clone()));
+
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Char('f').into());
+        let names: Vec<&str> = app.visible_themes().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["gamma", "alpha", "beta"]);
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        assert_eq!(app.status.as_deref(), Some("Added 'gamma' to favorites"));
+        assert!(favorites::load(&path).contains("gamma"));
+
+        app.handle_key(KeyCode::Char('f').into());
+        let names: Vec<&str> = app.visible_themes().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["alpha", "beta", "gamma"]);
+        assert!(favorites::load(&path).is_empty());
+    }
+
+    #[test]
+    fn group_headers_mark_favorites_and_rest() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        assert_eq!(app.group_header(0), None);
+
+        app.set_favorites(["beta".to_string()].into(), None);
+        assert_eq!(app.group_header(0), Some("Favorites"));
+        assert_eq!(app.group_header(1), Some("All themes"));
+        assert_eq!(app.group_header(2), None);
+        assert_eq!(app.group_header(3), None);
+    }
+
+    #[test]
+    fn click_accounts_for_group_header_rows() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.set_favorites(["gamma".to_string()].into(), None);
+        app.list_area = Rect::new(0, 0, 40, 10);
+
+        // Rows 1-2: "Favorites" header + gamma, rows 3-4: "All themes" + alpha
+        app.handle_mouse(click(5, 2));
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        app.handle_mouse(click(5, 3));
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+        app.handle_mouse(click(5, 5));
+        assert_eq!(highlighted_name(&app), Some("beta"));
+    }
+
+    #[test]
+    fn click_accounts_for_description_rows() {
+        let mut themes: Vec<SddmTheme> =
+            ["alpha", "beta", "gamma"].iter().map(|n| make_theme(n)).collect();
+        themes[0].metadata.insert("Description".to_string(), "first".to_string());
+        let mut app = App::new(themes, SddmConfig::empty());
+        app.list_area = Rect::new(0, 0, 40, 10);
+        assert_eq!(app.item_height(0), 2);
+        assert_eq!(app.item_height(1), 1);
+
+        // Rows 1-2: alpha and its description, row 3: beta
+        app.handle_mouse(click(5, 2));
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+        app.handle_mouse(click(5, 3));
+        assert_eq!(highlighted_name(&app), Some("beta"));
+
+        // Dense mode drops the description line
+        app.dense = true;
+        assert_eq!(app.item_height(0), 1);
+        app.handle_mouse(click(5, 3));
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+    }
+
+    #[test]
+    fn list_overflows_counts_description_rows() {
+        let mut themes: Vec<SddmTheme> =
+            ["alpha", "beta", "gamma"].iter().map(|n| make_theme(n)).collect();
+        themes[0].metadata.insert("Description".to_string(), "first".to_string());
+        let mut app = App::new(themes, SddmConfig::empty());
+        assert!(!app.list_overflows(4));
+        assert!(app.list_overflows(3));
+        app.dense = true;
+        assert!(!app.list_overflows(3));
+        app.visible.clear();
+        assert!(!app.list_overflows(0));
+    }
+
+    #[test]
+    fn table_rows_start_below_the_column_headings() {
+        let mut themes: Vec<SddmTheme> =
+            ["alpha", "beta", "gamma"].iter().map(|n| make_theme(n)).collect();
+        themes[0].metadata.insert("Description".to_string(), "first".to_string());
+        let mut app = App::new(themes, SddmConfig::empty());
+        app.list_area = Rect::new(0, 0, 40, 10);
+        app.handle_key(KeyCode::Tab.into());
+        assert!(app.table);
+        assert_eq!(app.item_height(0), 1);
+
+        // Row 1 holds the headings, so the themes are on rows 2-4
+        app.handle_mouse(click(5, 4));
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        app.handle_mouse(click(5, 1));
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        app.handle_mouse(click(5, 2));
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn copy_without_selection_reports_status() {
+        let mut app = make_app(&[], None);
+        app.handle_key(KeyCode::Char('c').into());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.status.as_deref(), Some("No theme selected to copy"));
+    }
+
+    #[test]
+    fn m_filters_to_themes_with_incomplete_metadata() {
+        let mut themes: Vec<SddmTheme> =
+            ["alpha", "beta", "gamma", "delta"].iter().map(|n| make_theme(n)).collect();
+        themes[0].metadata.insert("Author".to_string(), "KDE".to_string());
+        themes[0].metadata.insert("Description".to_string(), "complete".to_string());
+        themes[1].metadata.insert("Author".to_string(), "KDE".to_string());
+        themes[2].metadata.insert("Description".to_string(), "no author".to_string());
+        themes[3].metadata_loaded = false;
+        let mut app = App::new(themes, SddmConfig::empty());
+
+        app.handle_key(KeyCode::Char('m').into());
+        assert!(app.incomplete_only);
+        let names: Vec<&str> = app.visible_themes().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["beta", "gamma"]);
+
+        // Combines with a text search
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "gam");
+        assert_eq!(app.visible_themes().count(), 1);
+        app.handle_key(KeyCode::Esc.into());
+
+        app.handle_key(KeyCode::Char('m').into());
+        assert_eq!(app.visible_themes().count(), 4);
+    }
+
+    #[test]
+    fn set_prefs_restores_settings_and_changes_are_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prefs.toml");
+        let mut app = make_app(&["alpha", "beta"], None);
+        let saved = Prefs {
+            sort_key: SortKey::Author,
+            dense: true,
+            wrap_navigation: false,
+            table: true,
+            tag_filter: Some("Plasma".to_string()),
+            incomplete_only: true,
+        };
+        app.set_prefs(saved.clone(), Some(path.clone()));
+        assert_eq!(app.prefs(), saved);
+        // No theme has the saved tag
+        assert_eq!(app.visible_themes().count(), 0);
+        // Restoring alone does not write the file
+        assert!(!path.exists());
+
+        app.handle_key(KeyCode::Char('m').into());
+        let loaded = prefs::load(&path);
+        assert!(!loaded.incomplete_only);
+        assert_eq!(loaded.tag_filter.as_deref(), Some("Plasma"));
+
+        app.handle_key(KeyCode::Char('s').into());
+        assert_eq!(prefs::load(&path).sort_key, SortKey::Modified);
+        app.handle_keys([KeyCode::Char('d'), KeyCode::Char('w'), KeyCode::Tab]);
+        let loaded = prefs::load(&path);
+        assert!(!loaded.dense);
+        assert!(loaded.wrap_navigation);
+        assert!(!loaded.table);
+    }
+
+    #[test]
+    fn session_wrap_override_is_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prefs.toml");
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.set_prefs(Prefs::default(), Some(path.clone()));
+        app.override_wrap_navigation(false);
+        assert!(!app.wrap_navigation);
+
+        app.handle_key(KeyCode::Char('d').into());
+        let loaded = prefs::load(&path);
+        assert!(loaded.dense);
+        assert!(loaded.wrap_navigation);
+        assert!(!app.wrap_navigation);
+
+        // Choosing with `w` makes it the saved setting again
+        app.handle_keys([KeyCode::Char('w'), KeyCode::Char('w')]);
+        assert!(!prefs::load(&path).wrap_navigation);
+    }
+
+    #[test]
+    fn table_flag_is_not_saved() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prefs.toml");
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.set_prefs(Prefs::default(), Some(path.clone()));
+        app.override_table(true);
+
+        app.handle_keys([KeyCode::Char('s'), KeyCode::Char('d'), KeyCode::Char('w')]);
+        assert!(!prefs::load(&path).table);
+        assert!(app.table);
+
+        app.handle_keys([KeyCode::Tab, KeyCode::Tab]);
+        assert!(prefs::load(&path).table);
+    }
+
+    fn tagged_app() -> App {
+        let tagged = [
+            ("alpha", &["Dark", "Minimal"][..]),
+            ("beta", &["minimal", "Anime"]),
+            ("gamma", &["Dark"]),
+            ("delta", &[]),
+        ];
+        let themes = tagged
+            .iter()
+            .map(|(name, tags)| SddmTheme {
+                tags: tags.iter().map(|t| t.to_string()).collect(),
+                ..make_theme(name)
+            })
+            .collect();
+        App::new(themes, SddmConfig::empty())
+    }
+
+    fn visible_names(app: &App) -> Vec<&str> {
+        app.visible_themes().map(|t| t.name.as_str()).collect()
+    }
+
+    #[test]
+    fn all_tags_are_sorted_and_merged_across_case() {
+        assert_eq!(tagged_app().all_tags(), ["Anime", "Dark", "Minimal"]);
+    }
+
+    #[test]
+    fn t_cycles_through_tag_filters() {
+        let mut app = tagged_app();
+
+        app.handle_key(KeyCode::Char('t').into());
+        assert_eq!(app.tag_filter.as_deref(), Some("Anime"));
+        assert_eq!(visible_names(&app), ["beta"]);
+
+        app.handle_key(KeyCode::Char('t').into());
+        assert_eq!(app.tag_filter.as_deref(), Some("Dark"));
+        assert_eq!(visible_names(&app), ["alpha", "gamma"]);
+
+        // Matches the tag whatever case each theme declared it in
+        app.handle_key(KeyCode::Char('t').into());
+        assert_eq!(app.tag_filter.as_deref(), Some("Minimal"));
+        assert_eq!(visible_names(&app), ["alpha", "beta"]);
+
+        // Combines with a text search
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "bet");
+        assert_eq!(visible_names(&app), ["beta"]);
+        app.handle_key(KeyCode::Esc.into());
+
+        // After the last tag the filter is off again
+        app.handle_key(KeyCode::Char('t').into());
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.visible_themes().count(), 4);
+    }
+
+    #[test]
+    fn t_without_tags_leaves_the_list_alone() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Char('t').into());
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(app.visible_themes().count(), 2);
+        // Not taken as a jump to a theme starting with "t"
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn question_mark_toggles_help() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Char('?').into());
+        assert_eq!(app.mode, Mode::Help);
+
+        // Navigation keys are ignored while the overlay is open
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(app.selected_index(), Some(0));
+
+        app.handle_key(KeyCode::Char('?').into());
         assert_eq!(app.mode, Mode::Browsing);
     }
 
+    #[test]
+    fn help_dismissed_by_esc_and_q_without_quitting() {
+        let mut app = make_app(&["alpha"], None);
+        for key in [KeyCode::Esc, KeyCode::Char('q')] {
+            app.handle_key(KeyCode::Char('?').into());
+            assert!(app.handle_key(key.into()).is_none());
+            assert_eq!(app.mode, Mode::Browsing);
+        }
+    }
+
     #[test]
     fn quit_key_returns_quit_action() {
         let mut app = make_app(&["alpha"], None);
-        let result = app.handle_key(KeyCode::Char('q'));
+        let result = app.handle_key(KeyCode::Char('q').into());
         assert!(matches!(result, Some(ExitAction::Quit)));
     }
 
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn ctrl_c_quits_from_any_mode() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        assert!(matches!(app.handle_key(ctrl('c')), Some(ExitAction::Quit)));
+
+        // Even where plain `c` is text or another command
+        app.handle_key(KeyCode::Char('/').into());
+        assert!(matches!(app.handle_key(ctrl('c')), Some(ExitAction::Quit)));
+        assert!(app.search_query.is_empty());
+
+        app.mode = Mode::Confirming;
+        assert!(matches!(app.handle_key(ctrl('c')), Some(ExitAction::Quit)));
+        app.mode = Mode::Help;
+        assert!(matches!(app.handle_key(ctrl('c')), Some(ExitAction::Quit)));
+    }
+
+    fn type_query(app: &mut App, query: &str) {
+        for c in query.chars() {
+            app.handle_key(KeyCode::Char(c).into());
+        }
+    }
+
+    #[test]
+    fn slash_enters_search_and_filters_list() {
+        let mut app = make_app(&["breeze", "maya", "sugar-candy"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        assert_eq!(app.mode, Mode::Searching);
+        type_query(&mut app, "MAY");
+        assert_eq!(app.visible, vec![1]);
+        assert_eq!(app.highlighted_theme().map(|t| t.name.as_str()), Some("maya"));
+    }
+
+    #[test]
+    fn search_matches_author_names() {
+        let mut app = make_app(&["chili", "maya", "sugar-candy"], None);
+        for (i, author) in ["KDE", "Marian Arlt", "marian arlt"].iter().enumerate() {
+            app.themes[i].metadata.insert("Author".to_string(), author.to_string());
+        }
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "arlt");
+        assert_eq!(app.visible, vec![1, 2]);
+        assert!(app.status.is_none());
+    }
+
+    #[test]
+    fn search_without_matches_sets_status() {
+        let mut app = make_app(&["breeze", "maya"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "zzz");
+        assert!(app.visible.is_empty());
+        assert_eq!(app.status.as_deref(), Some("No themes match 'zzz'"));
+        // Matching again drops the notice
+        for _ in 0..3 {
+            app.handle_key(KeyCode::Backspace.into());
+        }
+        assert_eq!(app.visible.len(), 2);
+        assert!(app.status.is_none());
+    }
+
+    #[test]
+    fn search_navigation_stays_within_matches() {
+        let mut app = make_app(&["alpha", "beta", "alphabet"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "alpha");
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(app.highlighted_theme().map(|t| t.name.as_str()), Some("alphabet"));
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(app.highlighted_theme().map(|t| t.name.as_str()), Some("alpha"));
+    }
+
+    #[test]
+    fn search_esc_clears_query() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "zzz");
+        assert!(app.highlighted_theme().is_none());
+        app.handle_key(KeyCode::Esc.into());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert!(app.search_query.is_empty());
+        assert_eq!(app.visible.len(), 2);
+    }
+
+    #[test]
+    fn search_enter_keeps_filter_and_confirms_filtered_theme() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "bet");
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Browsing);
+        assert_eq!(app.visible, vec![1]);
+
+        app.handle_key(KeyCode::Enter.into());
+        let result = app.handle_key(KeyCode::Enter.into());
+        assert!(matches!(result, Some(ExitAction::ApplyTheme(ref n)) if n == "beta"));
+    }
+
+    fn highlighted_name(app: &App) -> Option<&str> {
+        app.highlighted_theme().map(|t| t.name.as_str())
+    }
+
+    #[test]
+    fn clearing_search_restores_pre_search_theme() {
+        let mut app = make_app(&["alpha", "beta", "gamma", "gamut"], None);
+        app.list_state.select(Some(1)); // beta
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "gam");
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        app.handle_key(KeyCode::Esc.into());
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert_eq!(app.selected_index(), Some(1));
+    }
+
+    #[test]
+    fn clearing_search_with_no_matches_restores_pre_search_theme() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.list_state.select(Some(2));
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "zzz");
+        app.handle_key(KeyCode::Esc.into());
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+    }
+
+    #[test]
+    fn clearing_search_keeps_theme_chosen_from_results() {
+        let mut app = make_app(&["alpha", "beta", "gamma", "gamut"], None);
+        app.list_state.select(Some(1)); // beta
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "gam");
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(highlighted_name(&app), Some("gamut"));
+        app.handle_key(KeyCode::Esc.into());
+        assert_eq!(highlighted_name(&app), Some("gamut"));
+        assert_eq!(app.selected_index(), Some(3));
+    }
+
+    #[test]
+    fn clearing_committed_filter_restores_pre_search_theme() {
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "gam");
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Browsing);
+        app.handle_key(KeyCode::Esc.into());
+        assert!(app.search_query.is_empty());
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn enter_with_no_matches_does_not_confirm() {
+        let mut app = make_app(&["alpha"], None);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "zzz");
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Enter.into());
+        assert_eq!(app.mode, Mode::Browsing);
+    }
+
+    #[test]
+    fn undo_key_jumps_to_original_theme() {
+        let themes = vec![make_theme("alpha"), make_theme("beta"), make_theme("gamma")];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("beta".to_string());
+        let mut app = App::new(themes, config);
+        app.handle_key(KeyCode::Down.into());
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+
+        app.handle_key(KeyCode::Char('u').into());
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert_eq!(app.status.as_deref(), Some("Jumped to previously active theme"));
+    }
+
+    #[test]
+    fn a_jumps_to_active_theme() {
+        let mut app = make_app(&["alpha", "beta", "gamma", "delta"], None);
+        app.current_theme = Some("gamma".to_string());
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+
+        app.handle_key(KeyCode::Char('a').into());
+        assert_eq!(highlighted_name(&app), Some("gamma"));
+        assert_eq!(app.status.as_deref(), Some("Jumped to active theme 'gamma'"));
+    }
+
+    #[test]
+    fn a_clears_filters_hiding_active_theme() {
+        let mut app = tagged_app();
+        app.current_theme = Some("delta".to_string());
+        app.handle_key(KeyCode::Char('t').into());
+        assert!(app.tag_filter.is_some());
+
+        app.handle_key(KeyCode::Char('a').into());
+        assert_eq!(app.tag_filter, None);
+        assert_eq!(highlighted_name(&app), Some("delta"));
+    }
+
+    #[test]
+    fn a_reports_missing_active_theme() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Char('a').into());
+        assert_eq!(app.status.as_deref(), Some("No theme is set in the config"));
+
+        app.current_theme = Some("ghost".to_string());
+        app.handle_key(KeyCode::Char('a').into());
+        assert_eq!(app.status.as_deref(), Some("Active theme 'ghost' is not installed"));
+        assert_eq!(highlighted_name(&app), Some("beta"));
+    }
+
+    #[test]
+    fn undo_key_clears_filter_hiding_original_theme() {
+        let themes = vec![make_theme("alpha"), make_theme("beta")];
+        let mut config = SddmConfig::empty();
+        config.current_theme = Some("alpha".to_string());
+        let mut app = App::new(themes, config);
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "bet");
+        app.handle_key(KeyCode::Enter.into());
+
+        app.handle_key(KeyCode::Char('u').into());
+        assert!(app.search_query.is_empty());
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+    }
+
+    #[test]
+    fn undo_key_without_original_theme_sets_status() {
+        let mut app = make_app(&["alpha", "beta"], None);
+        app.handle_key(KeyCode::Down.into());
+        app.handle_key(KeyCode::Char('u').into());
+        assert_eq!(highlighted_name(&app), Some("beta"));
+        assert!(app.status.is_some());
+    }
+
+    #[test]
+    fn sort_key_cycles_and_keeps_selection() {
+        use std::time::{Duration, SystemTime};
+
+        let mut app = make_app(&["alpha", "beta", "gamma"], None);
+        app.themes[0].metadata.insert("Author".to_string(), "Zed".to_string());
+        app.themes[2].metadata.insert("Author".to_string(), "amy".to_string());
+        app.themes[0].modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(10));
+        app.themes[1].modified = Some(SystemTime::UNIX_EPOCH + Duration::from_secs(30));
+        app.list_state.select(Some(0)); // alpha
+
+        app.handle_key(KeyCode::Char('s').into());
+        assert_eq!(app.sort_key, SortKey::Author);
+        let names: Vec<&str> = app.themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["gamma", "alpha", "beta"]);
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+        assert_eq!(app.selected_index(), Some(1));
+
+        app.handle_key(KeyCode::Char('s').into());
+        assert_eq!(app.sort_key, SortKey::Modified);
+        let names: Vec<&str> = app.themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["beta", "alpha", "gamma"]);
+        assert_eq!(highlighted_name(&app), Some("alpha"));
+
+        app.handle_key(KeyCode::Char('s').into());
+        assert_eq!(app.sort_key, SortKey::Name);
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
+    #[test]
+    fn sorting_keeps_search_filter() {
+        let mut app = make_app(&["alpha", "beta", "alphabet"], None);
+        app.themes[2].metadata.insert("Author".to_string(), "a".to_string());
+        app.handle_key(KeyCode::Char('/').into());
+        type_query(&mut app, "alpha");
+        app.handle_key(KeyCode::Enter.into());
+        app.handle_key(KeyCode::Down.into()); // alphabet
+        app.handle_key(KeyCode::Char('s').into());
+        assert_eq!(app.visible.len(), 2);
+        assert_eq!(highlighted_name(&app), Some("alphabet"));
+        assert_eq!(app.selected_index(), Some(0));
+    }
+
     #[test]
     fn empty_theme_list_has_no_selection() {
         let app = make_app(&[], None);
@@ -0,0 +1,36 @@
+//! Copying text to the system clipboard.
+//!
+//! Backed by `arboard` when the `clipboard` feature is enabled; without it,
+//! every copy fails with an explanatory error so callers need no `cfg`s.
+
+use anyhow::Result;
+
+#[cfg(feature = "clipboard")]
+thread_local! {
+    // On X11 and Wayland the copied text is served by whoever owns the
+    // clipboard, so the handle is kept for as long as the process runs.
+    static CLIPBOARD: std::cell::RefCell<Option<arboard::Clipboard>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+/// Put `text` on the system clipboard.  Fails when no clipboard is
+/// available (e.g. no display server on a headless session).
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<()> {
+    CLIPBOARD.with(|slot| {
+        let mut slot = slot.borrow_mut();
+        if slot.is_none() {
+            *slot = Some(arboard::Clipboard::new()?);
+        }
+        if let Some(clipboard) = slot.as_mut() {
+            clipboard.set_text(text)?;
+        }
+        Ok(())
+    })
+}
+
+/// Always fails: this build has no clipboard support.
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<()> {
+    anyhow::bail!("clipboard support is not built in (enable the `clipboard` feature)")
+}
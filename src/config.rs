@@ -7,38 +7,167 @@
 //!   2. /etc/sddm.conf.d/*.conf (drop-in directory, modern)
 //!
 //! The theme identifier lives in the [Theme] section under the key `Current`.
+//! When several files set it, the one read last wins, so the effective theme
+//! can differ from the value in the file Themewalker edits.  The same
+//! section's `ThemeDir` moves the system theme directory; its other keys
+//! (`CursorTheme`, `Font`, ...) can be listed and patched like `Current`.
+//!
+//! Reading and patching `Current` go through a [`ConfigFormat`], so a config
+//! written in another syntax can be handled by plugging in an implementation
+//! with [`SddmConfig::with_format`].  [`IniFormat`] is the default.
 
-use std::fs;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, OpenOptions};
 use std::io::Write as IoWrite;
+use std::os::unix::fs::MetadataExt;
 use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use anyhow::{bail, Context, Result};
 
 const SDDM_CONF: &str = "/etc/sddm.conf";
 const SDDM_CONF_D: &str = "/etc/sddm.conf.d";
 
+/// Environment variable naming a config file to use instead of resolving
+/// one under `/etc`.
+pub const CONFIG_ENV: &str = "THEMEWALKER_CONFIG";
+
+/// Drop-in Themewalker writes to with `--prefer-dropin`; the `zz-` prefix
+/// sorts it after other drop-ins.
+pub const DROPIN_FILE: &str = "zz-themewalker.conf";
+
+/// Suffix appended to the config path to form the backup file name.
+const BACKUP_SUFFIX: &str = ".themewalker.bak";
+
+/// Mount table read to detect read-only filesystems.
+const PROC_MOUNTS: &str = "/proc/mounts";
+
 // ---------------------------------------------------------------------------
 // Public API
 // ---------------------------------------------------------------------------
 
+/// How the selected theme is read from and written into a config file's
+/// content.  `Send + Sync` so an `SddmConfig` can move to another thread.
+pub trait ConfigFormat: Send + Sync {
+    /// The theme `content` selects, if any.
+    fn parse_current(&self, content: &str) -> Option<String>;
+
+    /// A copy of `content` that selects `theme_name`, leaving everything
+    /// else as intact as the format allows.
+    fn apply(&self, content: &str, theme_name: &str) -> String;
+
+    /// One-line summary of what `apply(content, theme_name)` changes, shown
+    /// in the confirmation dialog.  The default compares `parse_current`
+    /// before and after.
+    fn describe_change(&self, content: &str, theme_name: &str) -> String {
+        match self.parse_current(content) {
+            Some(old) if old == theme_name => format!("{old} (unchanged)"),
+            Some(old) => format!("{old} → {theme_name}"),
+            None => format!("sets the theme to {theme_name}"),
+        }
+    }
+}
+
+/// SDDM's own INI syntax: `Current=` in the `[Theme]` section.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IniFormat;
+
+impl ConfigFormat for IniFormat {
+    fn parse_current(&self, content: &str) -> Option<String> {
+        theme_section_value(content, "Current")
+    }
+
+    fn apply(&self, content: &str, theme_name: &str) -> String {
+        apply_theme_on(content, theme_name, &today())
+    }
+
+    /// The `Current=` line before and after, e.g.
+    /// "Current=breeze → Current=maya" or "adds [Theme] section with …".
+    fn describe_change(&self, content: &str, theme_name: &str) -> String {
+        let new_line = format!("Current={theme_name}");
+        match find_current_line(content) {
+            (_, Some(line)) if current_value(line) == Some(theme_name) => {
+                format!("{line} (unchanged)")
+            }
+            (_, Some(line)) => format!("{line} → {}", replace_current_value(line, theme_name)),
+            (true, None) => format!("adds {new_line} to [Theme]"),
+            (false, None) => format!("adds [Theme] section with {new_line}"),
+        }
+    }
+}
+
 /// Loaded SDDM configuration, ready for reading and writing.
 pub struct SddmConfig {
     /// Path we will write changes to.
     pub path: PathBuf,
     /// The `Current=` value found in `[Theme]`, if any.
     pub current_theme: Option<String>,
+    /// All config files that set `[Theme]/Current=` when there is more than
+    /// one; empty otherwise.  A change to `path` may be overridden by another.
+    pub conflict: Vec<PathBuf>,
+    /// The legacy `/etc/sddm.conf` when it sets a theme but `path` is a
+    /// drop-in (`--prefer-dropin`), so its `[Theme]` is left unedited.
+    pub shadowed_legacy: Option<PathBuf>,
+    /// Whether `path` existed when loaded; a write will create it otherwise.
+    pub config_exists: bool,
+    /// Config files the theme could be written to instead of `path`: the
+    /// legacy file when it exists, every drop-in, and `path` itself.  Empty
+    /// when the config was loaded from an explicit path.
+    pub candidates: Vec<PathBuf>,
+    /// The theme SDDM ends up using and the file that sets it, when the
+    /// config was resolved from the usual locations and one sets a theme.
+    effective: Option<(String, PathBuf)>,
+    /// `[Theme]/ThemeDir=`: where SDDM looks for themes instead of
+    /// `/usr/share/sddm/themes`, taken from the last file that sets it.
+    theme_dir: Option<PathBuf>,
+    /// The legacy file and drop-in directory the config was resolved from,
+    /// to recompute `effective` after a write; `None` for an explicit path.
+    locations: Option<(PathBuf, PathBuf)>,
     /// Raw file content (may be empty for a brand-new file).
     raw_content: String,
+    /// Reads and patches `Current` in `raw_content`.
+    format: Arc<dyn ConfigFormat>,
+    /// How to write the file when the current user cannot: the tool found
+    /// by [`Escalation::detect`] unless the caller picks one (`--escalate`).
+    /// `None` when neither sudo nor doas is installed.
+    pub escalation: Option<Escalation>,
 }
 
 impl SddmConfig {
     /// Load config from disk.  Succeeds even when the config file does not
     /// exist yet (returns an empty config targeting `/etc/sddm.conf`).
+    ///
+    /// A non-empty `$THEMEWALKER_CONFIG` is loaded instead, like `load_from`.
     pub fn load() -> Result<Self> {
-        let path = resolve_config_path();
+        Self::load_resolved(false)
+    }
+
+    /// Like `load`, but always targets Themewalker's own drop-in,
+    /// `/etc/sddm.conf.d/zz-themewalker.conf`, so `/etc/sddm.conf` is never
+    /// edited.  Until the drop-in sets a theme, `current_theme` is the one
+    /// SDDM uses from the other files (the last of them to set one).
+    pub fn load_preferring_dropin() -> Result<Self> {
+        Self::load_resolved(true)
+    }
 
-        let raw_content = if path.exists() {
+    fn load_resolved(prefer_dropin: bool) -> Result<Self> {
+        if let Some(path) = std::env::var_os(CONFIG_ENV).filter(|p| !p.is_empty()) {
+            return Self::load_from(Path::new(&path));
+        }
+        load_from_locations(Path::new(SDDM_CONF), Path::new(SDDM_CONF_D), prefer_dropin)
+    }
+
+    /// Load config from an explicit path, bypassing the usual resolution.
+    /// A missing file yields an empty config that will be created on write.
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let path = path.to_path_buf();
+
+        let config_exists = path.exists();
+        let raw_content = if config_exists {
             fs::read_to_string(&path)
                 .with_context(|| format!("Failed to read SDDM config at {}", path.display()))?
         } else {
@@ -46,8 +175,22 @@ impl SddmConfig {
         };
 
         let current_theme = parse_current_theme(&raw_content);
+        let theme_dir = parse_theme_dir(&raw_content);
 
-        Ok(Self { path, current_theme, raw_content })
+        Ok(Self {
+            path,
+            current_theme,
+            conflict: Vec::new(),
+            shadowed_legacy: None,
+            config_exists,
+            candidates: Vec::new(),
+            effective: None,
+            theme_dir,
+            locations: None,
+            raw_content,
+            format: Arc::new(IniFormat),
+            escalation: Escalation::detect(),
+        })
     }
 
     /// Return a minimal in-memory config (no disk I/O), used as a fallback.
@@ -55,15 +198,223 @@ impl SddmConfig {
         Self {
             path: PathBuf::from(SDDM_CONF),
             current_theme: None,
+            conflict: Vec::new(),
+            shadowed_legacy: None,
+            config_exists: false,
+            candidates: Vec::new(),
+            effective: None,
+            theme_dir: None,
+            locations: None,
             raw_content: String::new(),
+            format: Arc::new(IniFormat),
+            escalation: Escalation::detect(),
+        }
+    }
+
+    /// Use `format` instead of [`IniFormat`] to read and patch the theme,
+    /// re-reading `current_theme` with it.  The `[Theme]` key editor and the
+    /// scan of the usual locations for other files setting a theme stay INI.
+    pub fn with_format(mut self, format: impl ConfigFormat + 'static) -> Self {
+        self.current_theme = format.parse_current(&self.raw_content);
+        self.format = Arc::new(format);
+        self
+    }
+
+    /// Load the candidate at `path` to write to instead, keeping the list of
+    /// candidates and conflicting files.
+    pub fn with_target(&self, path: &Path) -> Result<Self> {
+        let mut config = Self::load_from(path)?;
+        config.conflict = self.conflict.clone();
+        config.candidates = self.candidates.clone();
+        config.effective = self.effective.clone();
+        config.theme_dir = self.theme_dir.clone();
+        config.locations = self.locations.clone();
+        config.current_theme = self.format.parse_current(&config.raw_content);
+        config.format = Arc::clone(&self.format);
+        config.escalation = self.escalation;
+        Ok(config)
+    }
+
+    /// The theme directory set by `ThemeDir=` in `[Theme]`, which SDDM
+    /// scans instead of `/usr/share/sddm/themes`; `None` when unset.
+    pub fn theme_dir(&self) -> Option<PathBuf> {
+        self.theme_dir.clone()
+    }
+
+    /// The theme SDDM actually uses and the file that sets it.  SDDM reads
+    /// the legacy file first and then the drop-ins alphabetically, and a
+    /// later `Current=` overrides an earlier one, so this is the last file
+    /// that sets a theme.  It can differ from `current_theme` when
+    /// `conflict` is non-empty.  For an explicit path, or when no file sets
+    /// a theme, this is `current_theme` and `path`.
+    pub fn effective_current(&self) -> (Option<String>, PathBuf) {
+        match &self.effective {
+            Some((theme, path)) => (Some(theme.clone()), path.clone()),
+            None => (self.current_theme.clone(), self.path.clone()),
         }
     }
 
+    /// The config content as read from disk.
+    pub fn raw_content(&self) -> &str {
+        &self.raw_content
+    }
+
+    /// Return the content `write_theme` would write, without touching disk.
+    pub fn preview_theme(&self, theme_name: &str) -> String {
+        self.format.apply(&self.raw_content, theme_name)
+    }
+
+    /// One-line summary of what `write_theme(new_theme)` changes, as the
+    /// config's format describes it (see [`ConfigFormat::describe_change`]).
+    pub fn describe_change(&self, new_theme: &str) -> String {
+        self.format.describe_change(&self.raw_content, new_theme)
+    }
+
+    /// Where the previous config is saved before it is overwritten.
+    pub fn backup_path(&self) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(BACKUP_SUFFIX);
+        PathBuf::from(name)
+    }
+
+    /// Whether `write_theme` will have to escalate to sudo, i.e. the config
+    /// (or its backup) cannot be written by the current process.  Nothing on
+    /// disk is modified by the check.
+    pub fn needs_sudo(&self) -> bool {
+        let backup_needed = self.path.exists() && !self.raw_content.is_empty();
+        !can_write(&self.path) || (backup_needed && !can_write(&self.backup_path()))
+    }
+
     /// Patch the `Current=` key in `[Theme]` and write the file back.
     /// Tries a direct write first; falls back to `sudo tee` on EPERM/EACCES.
-    pub fn write_theme(&self, theme_name: &str) -> Result<()> {
-        let new_content = apply_theme_to_content(&self.raw_content, theme_name);
-        write_to_path(&self.path, &new_content)
+    ///
+    /// When the file already exists with content, that content is first saved
+    /// to [`backup_path`](Self::backup_path) and the backup location is
+    /// returned.  New or empty files are written without a backup.
+    ///
+    /// Fails without touching anything when `theme_name` cannot be stored as
+    /// a config value (see [`check_theme_name`]).
+    pub fn write_theme(&self, theme_name: &str) -> Result<Option<PathBuf>> {
+        check_theme_name(theme_name)?;
+        self.write_content(&self.preview_theme(theme_name))
+    }
+
+    /// The `[Theme]` section's key/value pairs, in file order.
+    pub fn theme_keys(&self) -> Vec<(String, String)> {
+        parse_theme_keys(&self.raw_content)
+    }
+
+    /// Return the content `write_keys` would write, without touching disk.
+    pub fn preview_keys(&self, keys: &HashMap<String, String>) -> String {
+        apply_keys_to_content(&self.raw_content, keys)
+    }
+
+    /// Set every key in `keys` inside `[Theme]` and write the file back,
+    /// backing it up and escalating like `write_theme`.  Fails without
+    /// touching anything when a key or value cannot be stored (see
+    /// [`check_key_value`]).
+    pub fn write_keys(&self, keys: &HashMap<String, String>) -> Result<Option<PathBuf>> {
+        for (key, value) in keys {
+            check_key_value(key, value)?;
+        }
+        self.write_content(&self.preview_keys(keys))
+    }
+
+    /// Like `write_keys`, but also updates this in-memory config to match
+    /// the written file.
+    pub fn apply_keys(&mut self, keys: &HashMap<String, String>) -> Result<Option<PathBuf>> {
+        let backup = self.write_keys(keys)?;
+        self.raw_content = apply_keys_to_content(&self.raw_content, keys);
+        self.current_theme = self.format.parse_current(&self.raw_content);
+        self.config_exists = true;
+        if let Some((main, conf_d)) = &self.locations {
+            self.effective = effective_theme(main, conf_d);
+        }
+        Ok(backup)
+    }
+
+    /// Back up the existing config (when it has content) and replace it
+    /// with `content`, returning the backup location.
+    fn write_content(&self, content: &str) -> Result<Option<PathBuf>> {
+        self.check_writability()?;
+        // One password prompt covers the backup and the config
+        let session = EscalationSession::new(self.escalation);
+        let backup = if self.path.exists() && !self.raw_content.is_empty() {
+            let backup = self.backup_path();
+            write_to_path(&backup, &self.raw_content, &session)
+                .with_context(|| format!("Failed to back up config to {}", backup.display()))?;
+            Some(backup)
+        } else {
+            None
+        };
+
+        write_to_path(&self.path, content, &session)?;
+        Ok(backup)
+    }
+
+    /// Fail with an actionable message when no write to the config can
+    /// succeed, not even through sudo: the file is immutable or append-only
+    /// (`chattr +i` / `+a`), or it lives on a read-only mount.  Checks that
+    /// cannot run (no `lsattr`, no `/proc/mounts`) are skipped.
+    pub fn check_writability(&self) -> Result<()> {
+        let target = fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone());
+        if let Ok(mounts) = fs::read_to_string(PROC_MOUNTS) {
+            if let Some(mount) = read_only_mount(&mounts, &target) {
+                bail!(
+                    "{} is on a read-only filesystem ({}). Remount it read-write first, \
+                     e.g. `sudo mount -o remount,rw {}`",
+                    target.display(),
+                    mount.display(),
+                    mount.display()
+                );
+            }
+        }
+        match file_attributes(&target).as_deref().and_then(blocking_attribute) {
+            Some('i') => bail!(
+                "{0} is immutable (chattr +i). Run `sudo chattr -i {0}` to allow changes",
+                target.display()
+            ),
+            Some(_) => bail!(
+                "{0} is append-only (chattr +a). Run `sudo chattr -a {0}` to allow changes",
+                target.display()
+            ),
+            None => Ok(()),
+        }
+    }
+
+    /// Like `write_theme`, but also updates this in-memory config to match the
+    /// written file, for callers that keep using it afterwards.
+    pub fn apply_theme(&mut self, theme_name: &str) -> Result<Option<PathBuf>> {
+        let backup = self.write_theme(theme_name)?;
+        self.raw_content = self.preview_theme(theme_name);
+        self.current_theme = Some(theme_name.to_string());
+        self.config_exists = true;
+        if let Some((main, conf_d)) = &self.locations {
+            self.effective = effective_theme(main, conf_d);
+        }
+        Ok(backup)
+    }
+
+    /// Read the backup left by the last `write_theme`, returning its content
+    /// and the theme it selects.  Errors when no backup exists.
+    pub fn read_backup(&self) -> Result<(String, Option<String>)> {
+        let backup = self.backup_path();
+        if !backup.exists() {
+            bail!("No backup found at {}", backup.display());
+        }
+        let content = fs::read_to_string(&backup)
+            .with_context(|| format!("Failed to read backup at {}", backup.display()))?;
+        let theme = self.format.parse_current(&content);
+        Ok((content, theme))
+    }
+
+    /// Overwrite the config with its backup, escalating to sudo like
+    /// `write_theme`.  Returns the theme the restored config selects.
+    pub fn restore_backup(&self) -> Result<Option<String>> {
+        let (content, theme) = self.read_backup()?;
+        self.check_writability()?;
+        write_to_path(&self.path, &content, &EscalationSession::new(self.escalation))?;
+        Ok(theme)
     }
 }
 
@@ -71,38 +422,59 @@ impl SddmConfig {
 // Config file resolution
 // ---------------------------------------------------------------------------
 
+/// Load the config from the legacy file `main` and drop-in directory
+/// `conf_d`: the file `resolve_config_path` picks, or the Themewalker drop-in
+/// when `prefer_dropin` is set.
+fn load_from_locations(main: &Path, conf_d: &Path, prefer_dropin: bool) -> Result<SddmConfig> {
+    let setters = files_setting_theme(main, conf_d);
+    let path = if prefer_dropin {
+        conf_d.join(DROPIN_FILE)
+    } else {
+        resolve_config_path(main, conf_d)
+    };
+    let mut config = SddmConfig::load_from(&path)?;
+
+    if prefer_dropin {
+        if config.current_theme.is_none() {
+            config.current_theme = effective_theme(main, conf_d).map(|(theme, _)| theme);
+        }
+        if setters.iter().any(|p| p == main) {
+            config.shadowed_legacy = Some(main.to_path_buf());
+        }
+    }
+    if setters.len() > 1 {
+        config.conflict = setters;
+    }
+    config.candidates = candidate_paths(main, conf_d, &config.path);
+    config.effective = effective_theme(main, conf_d);
+    config.theme_dir = std::iter::once(main.to_path_buf())
+        .chain(drop_in_files(conf_d))
+        .rev()
+        .find_map(|path| parse_theme_dir(&fs::read_to_string(path).ok()?));
+    config.locations = Some((main.to_path_buf(), conf_d.to_path_buf()));
+    Ok(config)
+}
+
+/// The `Current=` value SDDM ends up with and the file it comes from: that
+/// of the last file in `files_setting_theme`, since later files override
+/// earlier ones.
+fn effective_theme(main: &Path, conf_d: &Path) -> Option<(String, PathBuf)> {
+    files_setting_theme(main, conf_d).into_iter().rev().find_map(|path| {
+        let theme = parse_current_theme(&fs::read_to_string(&path).ok()?)?;
+        Some((theme, path))
+    })
+}
+
 /// Walk the known locations and return the path that contains [Theme]/Current=,
 /// or the best default path to create.
-fn resolve_config_path() -> PathBuf {
-    // Prefer an existing file that already holds [Theme]
-    let main = Path::new(SDDM_CONF);
-    if main.exists() {
-        if let Ok(c) = fs::read_to_string(main) {
-            if has_theme_section(&c) {
-                return main.to_path_buf();
-            }
-        }
+fn resolve_config_path(main: &Path, conf_d: &Path) -> PathBuf {
+    // Prefer an existing file that already holds [Theme] / Current=
+    if let Some(path) = files_setting_theme(main, conf_d).into_iter().next() {
+        return path;
     }
 
-    // Check drop-in directory for any file that has [Theme] / Current=
-    let conf_d = Path::new(SDDM_CONF_D);
+    // No existing file with [Theme] – create a new drop-in
     if conf_d.is_dir() {
-        if let Ok(entries) = fs::read_dir(conf_d) {
-            let mut candidates: Vec<PathBuf> = entries
-                .filter_map(|e| e.ok())
-                .map(|e| e.path())
-                .filter(|p| p.extension().map_or(false, |x| x == "conf"))
-                .collect();
-            candidates.sort(); // deterministic order
-            for path in candidates {
-                if let Ok(c) = fs::read_to_string(&path) {
-                    if has_theme_section(&c) {
-                        return path;
-                    }
-                }
-            }
-        }
-        // No existing file with [Theme] – create a new drop-in
         return conf_d.join("theme.conf");
     }
 
@@ -110,13 +482,53 @@ fn resolve_config_path() -> PathBuf {
     main.to_path_buf()
 }
 
+/// Files a theme can be written to: `main` when it exists, the drop-ins in
+/// `conf_d`, and `target` (the resolved path) when it is not one of them yet.
+fn candidate_paths(main: &Path, conf_d: &Path, target: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = std::iter::once(main.to_path_buf())
+        .filter(|p| p.is_file())
+        .chain(drop_in_files(conf_d))
+        .collect();
+    if !paths.iter().any(|p| p == target) {
+        paths.push(target.to_path_buf());
+    }
+    paths
+}
+
+/// Every config file that sets `[Theme]/Current=`: the legacy file first,
+/// then drop-ins in lexical order.
+fn files_setting_theme(main: &Path, conf_d: &Path) -> Vec<PathBuf> {
+    std::iter::once(main.to_path_buf())
+        .chain(drop_in_files(conf_d))
+        .filter(|p| {
+            fs::read_to_string(p)
+                .map(|c| has_theme_section(&c))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// `*.conf` files in the drop-in directory, sorted for deterministic order.
+fn drop_in_files(conf_d: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(conf_d) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|x| x == "conf"))
+        .collect();
+    files.sort();
+    files
+}
+
 fn has_theme_section(content: &str) -> bool {
     let mut in_theme = false;
     for line in content.lines() {
         let t = line.trim();
         if t.starts_with('[') {
             in_theme = t == "[Theme]";
-        } else if in_theme && t.starts_with("Current=") {
+        } else if in_theme && split_current(t).is_some() {
             return true;
         }
     }
@@ -127,8 +539,38 @@ fn has_theme_section(content: &str) -> bool {
 // INI parsing
 // ---------------------------------------------------------------------------
 
-/// Extract the value of `Current=` from the `[Theme]` section.
+/// Whether `content` has a `[Theme]` section, and its first `Current` line
+/// (trimmed), if any.
+fn find_current_line(content: &str) -> (bool, Option<&str>) {
+    let mut in_theme = false;
+    let mut found_section = false;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.starts_with('[') {
+            in_theme = t == "[Theme]";
+            found_section |= in_theme;
+        } else if in_theme && split_current(t).is_some() {
+            return (true, Some(t));
+        }
+    }
+    (found_section, None)
+}
+
+/// Extract the value of `Current=` from the `[Theme]` section.  Spaces
+/// around `=` and double quotes around the value are allowed, as in
+/// `Current = "breeze"`.
 pub fn parse_current_theme(content: &str) -> Option<String> {
+    IniFormat.parse_current(content)
+}
+
+/// Extract `ThemeDir=` from the `[Theme]` section, quoted or not.
+fn parse_theme_dir(content: &str) -> Option<PathBuf> {
+    theme_section_value(content, "ThemeDir").map(PathBuf::from)
+}
+
+/// The first non-empty value of `key` in the `[Theme]` section, with
+/// spaces around `=` and double quotes around the value removed.
+fn theme_section_value(content: &str, key: &str) -> Option<String> {
     let mut in_theme = false;
     for line in content.lines() {
         let t = line.trim();
@@ -136,92 +578,340 @@ pub fn parse_current_theme(content: &str) -> Option<String> {
             in_theme = t == "[Theme]";
             continue;
         }
-        if in_theme {
-            if let Some(val) = t.strip_prefix("Current=") {
-                let v = val.trim().to_string();
-                if !v.is_empty() {
-                    return Some(v);
-                }
-            }
+        if !in_theme {
+            continue;
+        }
+        let value = t
+            .split_once('=')
+            .filter(|(k, _)| k.trim_end() == key)
+            .map(|(_, v)| unquote(v.trim()).0.trim());
+        if let Some(v) = value.filter(|v| !v.is_empty()) {
+            return Some(v.to_string());
         }
     }
     None
 }
 
+/// Split a trimmed `Current = value` line into the text before the value
+/// (the key, `=` and any spaces around it) and the value itself.  `None`
+/// for any other line.
+fn split_current(line: &str) -> Option<(&str, &str)> {
+    split_key(line, "Current")
+}
+
+/// `split_current` for any key.
+fn split_key<'a>(line: &'a str, key: &str) -> Option<(&'a str, &'a str)> {
+    let (name, rest) = line.split_once('=')?;
+    if name.trim_end() != key {
+        return None;
+    }
+    let value_start = line.len() - rest.trim_start().len();
+    Some(line.split_at(value_start))
+}
+
+/// `value` without surrounding double quotes, and whether it had them.
+fn unquote(value: &str) -> (&str, bool) {
+    match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+        Some(inner) => (inner, true),
+        None => (value, false),
+    }
+}
+
+/// The theme a trimmed `Current` line selects, without quotes.
+fn current_value(line: &str) -> Option<&str> {
+    split_current(line).map(|(_, value)| unquote(value).0.trim())
+}
+
+/// A trimmed `Current` line with its value replaced by `theme_name`,
+/// keeping the line's spacing around `=` and its quoting.
+fn replace_current_value(line: &str, theme_name: &str) -> String {
+    replace_key_value(line, "Current", theme_name)
+}
+
+/// `replace_current_value` for any key.
+fn replace_key_value(line: &str, key: &str, value: &str) -> String {
+    match split_key(line, key) {
+        Some((prefix, old)) => {
+            let quote = if unquote(old).1 { "\"" } else { "" };
+            format!("{prefix}{quote}{value}{quote}")
+        }
+        None => format!("{key}={value}"),
+    }
+}
+
+/// Every `key=value` pair in the `[Theme]` section, in file order, with
+/// values unquoted and trimmed as SDDM reads them.  A key set more than
+/// once is listed once, with its first value.  Comments and lines without
+/// `=` are skipped.
+pub fn parse_theme_keys(content: &str) -> Vec<(String, String)> {
+    let mut keys: Vec<(String, String)> = Vec::new();
+    let mut in_theme = false;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.starts_with('[') {
+            in_theme = t == "[Theme]";
+            continue;
+        }
+        if !in_theme || t.starts_with(['#', ';']) {
+            continue;
+        }
+        let Some((key, value)) = t.split_once('=') else {
+            continue;
+        };
+        let key = key.trim_end();
+        if !key.is_empty() && !keys.iter().any(|(k, _)| k == key) {
+            keys.push((key.to_string(), unquote(value.trim()).0.trim().to_string()));
+        }
+    }
+    keys
+}
+
 // ---------------------------------------------------------------------------
 // INI writing
 // ---------------------------------------------------------------------------
 
+/// Check that `theme_name` survives being written as `Current=<name>`.
+///
+/// SDDM reads values raw (no quoting or escapes) and trims surrounding
+/// whitespace, so names with spaces or `=` inside are fine, but a name with
+/// leading/trailing whitespace would be read back as a different theme and
+/// one failing [`is_safe_theme_name`] could inject extra config lines.
+pub(crate) fn check_theme_name(theme_name: &str) -> Result<()> {
+    if theme_name.contains(['\n', '\r']) {
+        bail!("Theme name {theme_name:?} contains a line break");
+    }
+    if !is_safe_theme_name(theme_name) {
+        bail!("Theme name {theme_name:?} contains a control character or a square bracket");
+    }
+    if theme_name.trim().is_empty() {
+        bail!("Theme name {theme_name:?} is empty");
+    }
+    if theme_name.trim() != theme_name {
+        bail!(
+            "Theme name {theme_name:?} has leading or trailing whitespace, \
+             which SDDM strips when reading the config"
+        );
+    }
+    Ok(())
+}
+
+/// Check that `key=value` can be written into `[Theme]` and read back as
+/// the same pair: the key is non-empty, has no `=` and passes
+/// [`is_safe_theme_name`], and the value has no control characters.
+/// `Current` values are held to [`check_theme_name`].
+fn check_key_value(key: &str, value: &str) -> Result<()> {
+    if key.trim().is_empty() || key.trim() != key || key.contains('=') {
+        bail!("{key:?} is not a valid config key");
+    }
+    if !is_safe_theme_name(key) {
+        bail!("Config key {key:?} contains a control character or a square bracket");
+    }
+    if key == "Current" {
+        return check_theme_name(value);
+    }
+    if value.chars().any(char::is_control) {
+        bail!("Value {value:?} for {key} contains a line break or control character");
+    }
+    Ok(())
+}
+
+/// Whether `name` can be written as `Current=<name>` without risk of being
+/// read as more than one value: it has no control characters (line breaks
+/// included) and no `[` or `]` that a lax parser could take for a section
+/// header.  Theme directories failing this are skipped at discovery.
+pub fn is_safe_theme_name(name: &str) -> bool {
+    !name.chars().any(|c| c.is_control() || c == '[' || c == ']')
+}
+
 /// Return a new copy of `content` with `Current=<theme_name>` set inside
-/// `[Theme]`.  Handles four cases:
+/// `[Theme]`.  Handles three cases:
 ///   A. `[Theme]` + `Current=` exist  → replace the value in-place.
-///   B. `[Theme]` exists but no `Current=` → insert before next section.
-///   C. No `[Theme]` at all            → append `[Theme]\nCurrent=…` at EOF.
+///   B. `[Theme]` exists but no `Current=` → insert after the section's last
+///      non-blank line.
+///   C. No `[Theme]` at all            → append `[Theme]\nCurrent=…` at EOF,
+///      after a `# Set by Themewalker on <date>` comment.
+///
+/// `theme_name` is written verbatim, without escaping, so callers should
+/// check it with [`is_safe_theme_name`] first (`SddmConfig::write_theme`
+/// does).  A replaced line keeps its indentation, any spaces around `=` and
+/// double quotes around the value; new lines are written as plain
+/// `Current=<name>`.  Every other line — comments, blank lines, duplicate
+/// keys, trailing whitespace and its original line terminator — is copied
+/// through unchanged.  Inserted lines use the file's dominant line ending,
+/// and the result ends with a newline exactly when `content` does (or is
+/// empty).
 pub fn apply_theme_to_content(content: &str, theme_name: &str) -> String {
-    let new_line = format!("Current={}", theme_name);
+    IniFormat.apply(content, theme_name)
+}
+
+/// `apply_theme_to_content` with the date for the comment on a new section.
+fn apply_theme_on(content: &str, theme_name: &str, date: &str) -> String {
+    apply_keys_on(content, &[("Current", theme_name)], date)
+}
+
+/// Return a new copy of `content` with every key in `keys` set to its value
+/// inside `[Theme]`, patching lines the way `apply_theme_to_content` patches
+/// `Current=`.  Keys missing from the section are inserted in sorted order.
+/// Values are written verbatim; `SddmConfig::write_keys` checks them first.
+pub fn apply_keys_to_content(content: &str, keys: &HashMap<String, String>) -> String {
+    let mut keys: Vec<(&str, &str)> =
+        keys.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    keys.sort_unstable();
+    apply_keys_on(content, &keys, &today())
+}
+
+/// `apply_keys_to_content` with the date for the comment on a new section.
+fn apply_keys_on(content: &str, keys: &[(&str, &str)], date: &str) -> String {
+    let eol = detect_line_ending(content);
+    let new_lines = |found: &[bool]| -> Vec<String> {
+        keys.iter()
+            .zip(found)
+            .filter(|(_, &done)| !done)
+            .map(|((key, value), _)| format!("{key}={value}"))
+            .collect()
+    };
 
     let mut result = String::with_capacity(content.len() + 64);
     let mut in_theme = false;
     let mut found_section = false;
-    let mut found_key = false;
+    let mut found = vec![false; keys.len()];
+    // Blank lines at the tail of [Theme] are held back so an inserted key
+    // lands directly after the section's last entry, not after the gap.
+    let mut pending_blank = String::new();
 
-    for line in content.lines() {
-        let t = line.trim();
+    for raw in content.split_inclusive('\n') {
+        let t = raw.trim();
+
+        if in_theme && t.is_empty() {
+            pending_blank.push_str(raw);
+            continue;
+        }
 
+        let key = keys.iter().position(|(key, _)| split_key(t, key).is_some());
         if t.starts_with('[') {
-            // Leaving a [Theme] section that had no Current= yet → inject key
-            if in_theme && !found_key {
-                result.push_str(&new_line);
-                result.push('\n');
-                found_key = true;
+            // Leaving a [Theme] section without some of the keys → inject them
+            if in_theme {
+                for line in new_lines(&found) {
+                    push_terminated(&mut result, &line, eol);
+                }
+                found.fill(true);
             }
             in_theme = t == "[Theme]";
             if in_theme {
                 found_section = true;
             }
-            result.push_str(line);
-            result.push('\n');
-        } else if in_theme && t.starts_with("Current=") {
-            result.push_str(&new_line);
-            result.push('\n');
-            found_key = true;
+            result.push_str(&pending_blank);
+            result.push_str(raw);
+        } else if let Some(i) = key.filter(|_| in_theme) {
+            // Keep the replaced line's indentation and own terminator (none at EOF)
+            let indent = &raw[..raw.len() - raw.trim_start().len()];
+            let terminator = &raw[raw.trim_end_matches(['\r', '\n']).len()..];
+            let (key, value) = keys[i];
+            result.push_str(&pending_blank);
+            result.push_str(indent);
+            result.push_str(&replace_key_value(t, key, value));
+            result.push_str(terminator);
+            found[i] = true;
         } else {
-            result.push_str(line);
-            result.push('\n');
+            result.push_str(&pending_blank);
+            result.push_str(raw);
         }
+        pending_blank.clear();
     }
 
-    // End-of-file: still inside [Theme] with no Current= written yet
-    if in_theme && !found_key {
-        result.push_str(&new_line);
-        result.push('\n');
-        found_key = true;
+    // End-of-file: still inside [Theme] with some keys not written yet
+    if in_theme {
+        for line in new_lines(&found) {
+            push_terminated(&mut result, &line, eol);
+        }
+        found.fill(true);
     }
+    result.push_str(&pending_blank);
 
     // [Theme] section was never found at all → append it
-    if !found_section || !found_key {
-        if !result.is_empty() && !result.ends_with('\n') {
-            result.push('\n');
+    let missing = new_lines(&found);
+    if !found_section && !missing.is_empty() {
+        if !result.is_empty() {
+            if !result.ends_with('\n') {
+                result.push_str(eol);
+            }
+            result.push_str(eol);
+        }
+        // Only new sections are marked, so repeated edits add no comments
+        result.push_str(&format!("# Set by Themewalker on {date}"));
+        result.push_str(eol);
+        result.push_str("[Theme]");
+        result.push_str(eol);
+        for line in missing {
+            result.push_str(&line);
+            result.push_str(eol);
         }
-        result.push_str("\n[Theme]\n");
-        result.push_str(&new_line);
-        result.push('\n');
     }
 
+    // Lines added at the end of a file that had no final newline leave it
+    // without one too
+    if !content.is_empty() && !content.ends_with('\n') && result.ends_with(eol) {
+        result.truncate(result.len() - eol.len());
+    }
     result
 }
 
+/// Today's date (UTC) as `YYYY-MM-DD`.
+fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    format_date(secs)
+}
+
+/// Format seconds since the Unix epoch as a `YYYY-MM-DD` date (UTC), using
+/// Howard Hinnant's days-to-civil algorithm.
+pub(crate) fn format_date(secs: u64) -> String {
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Return `"\r\n"` when most lines in `content` end in CRLF, else `"\n"`.
+fn detect_line_ending(content: &str) -> &'static str {
+    let crlf = content.matches("\r\n").count();
+    let lf = content.matches('\n').count() - crlf;
+    if crlf > lf {
+        "\r\n"
+    } else {
+        "\n"
+    }
+}
+
+/// Append `line` + `eol`, first terminating the previous line if the input
+/// ended without a newline.
+fn push_terminated(result: &mut String, line: &str, eol: &str) {
+    if !result.is_empty() && !result.ends_with('\n') {
+        result.push_str(eol);
+    }
+    result.push_str(line);
+    result.push_str(eol);
+}
+
 // ---------------------------------------------------------------------------
 // Writing (direct or via sudo)
 // ---------------------------------------------------------------------------
 
-fn write_to_path(path: &Path, content: &str) -> Result<()> {
+fn write_to_path(path: &Path, content: &str, session: &EscalationSession) -> Result<()> {
     // Ensure parent directory exists (e.g. /etc/sddm.conf.d/)
     if let Some(parent) = path.parent() {
         if !parent.exists() {
             // Try to create it with sudo if we can't do it directly
             if fs::create_dir_all(parent).is_err() {
-                sudo_mkdir(parent)?;
+                sudo_mkdir(session, parent)?;
             }
         }
     }
@@ -231,109 +921,1126 @@ fn write_to_path(path: &Path, content: &str) -> Result<()> {
         return Ok(());
     }
 
-    // Escalate to sudo tee
-    sudo_tee(path, content)
+    // Escalate to sudo (or doas) tee
+    sudo_tee(session, path, content)
+}
+
+/// Best-effort check that `path` can be written without sudo.  Direct writes
+/// replace the file through a temp file in the same directory, so this checks
+/// the permission bits of the nearest existing ancestor directory (of the
+/// symlink target, for a symlinked config).
+fn can_write(path: &Path) -> bool {
+    let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    match path.ancestors().skip(1).find(|p| p.exists()) {
+        Some(dir) => dir_writable(dir),
+        None => false,
+    }
+}
+
+/// Compare the directory's owner/group/other write bits with our own uid/gid
+/// (taken from `/proc/self`).  Ignores ACLs and supplementary groups, so this
+/// is a hint — `write_to_path` still falls back to sudo if it is wrong.
+pub(crate) fn dir_writable(dir: &Path) -> bool {
+    let (Ok(meta), Ok(me)) = (fs::metadata(dir), fs::metadata("/proc/self")) else {
+        return false;
+    };
+    if me.uid() == 0 {
+        return true;
+    }
+    let mode = meta.mode();
+    if meta.uid() == me.uid() {
+        mode & 0o200 != 0
+    } else if meta.gid() == me.gid() {
+        mode & 0o020 != 0
+    } else {
+        mode & 0o002 != 0
+    }
 }
 
+/// Replace `path` atomically: write a sibling temp file, give it the existing
+/// file's permissions, then `rename` it over `path`.  SDDM therefore sees
+/// either the old or the new config, never a truncated one.  A symlinked
+/// config is resolved first so the link itself is kept.
 fn try_direct_write(path: &Path, content: &str) -> Result<()> {
-    let mut file = fs::File::create(path)
-        .with_context(|| format!("Cannot open {} for writing", path.display()))?;
+    let target = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let file_name = target
+        .file_name()
+        .with_context(|| format!("{} has no file name", target.display()))?;
+    let tmp = target.with_file_name(format!(
+        ".{}.themewalker-{}.tmp",
+        file_name.to_string_lossy(),
+        std::process::id()
+    ));
+
+    let result = write_and_rename(&tmp, &target, content);
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+fn write_and_rename(tmp: &Path, target: &Path, content: &str) -> Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(tmp)
+        .with_context(|| format!("Cannot create {}", tmp.display()))?;
     file.write_all(content.as_bytes())
-        .with_context(|| format!("Failed to write to {}", path.display()))?;
-    Ok(())
+        .with_context(|| format!("Failed to write to {}", tmp.display()))?;
+
+    if let Ok(meta) = fs::metadata(target) {
+        file.set_permissions(meta.permissions())
+            .with_context(|| format!("Failed to set permissions on {}", tmp.display()))?;
+        // Only succeeds as root; otherwise the file is ours anyway
+        let _ = std::os::unix::fs::fchown(&file, Some(meta.uid()), Some(meta.gid()));
+    }
+    file.sync_all()
+        .with_context(|| format!("Failed to flush {}", tmp.display()))?;
+
+    fs::rename(tmp, target)
+        .with_context(|| format!("Failed to replace {}", target.display()))
 }
 
-/// `echo <content> | sudo tee <path>`
+/// `echo <content> | sudo tee <path>`, or `doas tee` with
+/// [`Escalation::Doas`].
 ///
-/// stdout from tee is suppressed; stderr (sudo password prompt) is inherited
-/// so the user sees it in the terminal after the TUI exits.
-fn sudo_tee(path: &Path, content: &str) -> Result<()> {
-    let path_str = path.to_string_lossy();
-    let mut child = Command::new("sudo")
-        .args(["tee", path_str.as_ref()])
+/// stdout from tee is suppressed; stderr (tee's own errors) is inherited so
+/// the user sees it in the terminal after the TUI exits.  When `session`
+/// checked the password first, a failure here is a write error rather than a
+/// rejected password.
+///
+/// Unlike the direct write this is not atomic: tee truncates the file before
+/// writing, so an interrupted write can leave a partial config behind (the
+/// `.themewalker.bak` backup is the way back).
+fn sudo_tee(session: &EscalationSession, path: &Path, content: &str) -> Result<()> {
+    let (escalation, mut command) = session.command(path, &["tee".as_ref(), path.as_os_str()])?;
+    let tool = escalation.program();
+    let mut child = command
         .stdin(Stdio::piped())
         .stdout(Stdio::null())
         .stderr(Stdio::inherit())
         .spawn()
-        .context("Failed to spawn `sudo tee`. Ensure sudo is installed and configured.")?;
+        .with_context(|| format!("Failed to spawn `{tool} tee`"))?;
 
     // Write config content to tee's stdin
     {
-        let stdin = child.stdin.as_mut().context("Failed to open sudo tee stdin")?;
+        let stdin = child
+            .stdin
+            .as_mut()
+            .with_context(|| format!("Failed to open {tool} tee stdin"))?;
         stdin
             .write_all(content.as_bytes())
-            .context("Failed to write config to sudo tee")?;
+            .with_context(|| format!("Failed to write config to {tool} tee"))?;
     }
 
-    let status = child.wait().context("Failed to wait for `sudo tee`")?;
+    let status = child
+        .wait()
+        .with_context(|| format!("Failed to wait for `{tool} tee`"))?;
     if !status.success() {
-        bail!("`sudo tee {}` exited with status {}", path.display(), status);
+        let prefix = if session.authenticated() { "Authenticated, but " } else { "" };
+        bail!("{prefix}`{tool} tee` could not write {} ({status})", path.display());
     }
     Ok(())
 }
 
-fn sudo_mkdir(dir: &Path) -> Result<()> {
-    let status = Command::new("sudo")
-        .args(["mkdir", "-p", &dir.to_string_lossy()])
-        .status()
-        .context("Failed to run `sudo mkdir`")?;
-    if !status.success() {
-        bail!("`sudo mkdir -p {}` failed", dir.display());
-    }
-    Ok(())
+/// A tool that runs a command as root.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Escalation {
+    Sudo,
+    Doas,
 }
 
-// ---------------------------------------------------------------------------
-// Tests
-// ---------------------------------------------------------------------------
+impl Escalation {
+    /// `sudo` when it is on `PATH`, else `doas`, else `None`.
+    pub fn detect() -> Option<Self> {
+        Self::detect_in(&std::env::var_os("PATH").unwrap_or_default())
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// `detect` for the directories in `path` (`PATH` format).
+    fn detect_in(path: &OsStr) -> Option<Self> {
+        [Self::Sudo, Self::Doas].into_iter().find(|tool| {
+            std::env::split_paths(path).any(|dir| {
+                fs::metadata(dir.join(tool.program()))
+                    .is_ok_and(|m| m.is_file() && m.mode() & 0o111 != 0)
+            })
+        })
+    }
 
-    // --- parse_current_theme ---
+    /// The tool named `name` (`sudo` or `doas`), as given to `--escalate`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "sudo" => Some(Self::Sudo),
+            "doas" => Some(Self::Doas),
+            _ => None,
+        }
+    }
 
-    #[test]
-    fn parses_theme_in_section() {
-        let cfg = "[General]\nFoo=bar\n\n[Theme]\nCurrent=breeze\n";
-        assert_eq!(parse_current_theme(cfg).as_deref(), Some("breeze"));
+    /// The tool's binary.
+    pub fn program(self) -> &'static str {
+        match self {
+            Self::Sudo => "sudo",
+            Self::Doas => "doas",
+        }
     }
 
-    #[test]
-    fn parses_theme_first_section() {
-        let cfg = "[Theme]\nCurrent=maya\n\n[Users]\nMinimumUid=1000\n";
-        assert_eq!(parse_current_theme(cfg).as_deref(), Some("maya"));
+    /// The command line that runs `command` as root.  `--` ends the tool's
+    /// own options, so no argument of `command` is ever read as one.
+    pub fn argv<S: AsRef<OsStr>>(self, command: &[S]) -> Vec<OsString> {
+        std::iter::once(OsStr::new(self.program()))
+            .chain(std::iter::once(OsStr::new("--")))
+            .chain(command.iter().map(AsRef::as_ref))
+            .map(OsStr::to_os_string)
+            .collect()
     }
 
-    #[test]
-    fn returns_none_when_no_theme_section() {
-        let cfg = "[General]\nFoo=bar\n";
-        assert!(parse_current_theme(cfg).is_none());
+    /// The command line that asks for the password without doing anything
+    /// else, `sudo -v`, which also caches the credentials.  `None` for doas,
+    /// which has no such option.
+    pub fn auth_argv(self) -> Option<Vec<OsString>> {
+        match self {
+            Self::Sudo => Some(vec!["sudo".into(), "-v".into()]),
+            Self::Doas => None,
+        }
     }
 
-    #[test]
-    fn returns_none_when_current_missing() {
-        let cfg = "[Theme]\nFontSize=12\n";
-        assert!(parse_current_theme(cfg).is_none());
+    /// The command line that succeeds, without prompting, when `command` may
+    /// run as root right away: `sudo -n -l -- <command>`, which sudoers
+    /// `NOPASSWD` rules and cached credentials satisfy.  `None` for doas.
+    pub fn probe_argv<S: AsRef<OsStr>>(self, command: &[S]) -> Option<Vec<OsString>> {
+        match self {
+            Self::Sudo => Some(
+                ["sudo", "-n", "-l", "--"]
+                    .into_iter()
+                    .map(OsString::from)
+                    .chain(command.iter().map(|a| a.as_ref().to_os_string()))
+                    .collect(),
+            ),
+            Self::Doas => None,
+        }
     }
 
-    #[test]
-    fn ignores_current_outside_theme_section() {
-        let cfg = "[General]\nCurrent=breeze\n[Theme]\n";
-        assert!(parse_current_theme(cfg).is_none());
+    /// `argv(command)` ready to run.
+    pub fn command<S: AsRef<OsStr>>(self, command: &[S]) -> Command {
+        let argv = self.argv(command);
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        cmd
     }
+}
 
-    // --- apply_theme_to_content ---
+/// The escalation tool for one operation (an apply, a restore, a batch of
+/// deletions), which builds its commands.
+///
+/// With sudo, the password is asked for on its own (`sudo -v`) before the
+/// first command that needs one, so a rejected password is reported as such
+/// rather than as a failed write; both make `sudo <cmd>` exit with status 1.
+/// Commands sudoers lets run without a password skip that step.  doas is
+/// never asked in advance: without `persist` it caches nothing, so a
+/// separate `doas true` would only add a prompt, and each command prompts
+/// itself.
+#[derive(Debug)]
+pub struct EscalationSession {
+    escalation: Option<Escalation>,
+    /// `PATH` to look the tool up in instead of the inherited one.
+    search_path: Option<OsString>,
+    authenticated: Cell<bool>,
+}
 
-    #[test]
-    fn replaces_existing_current() {
-        let cfg = "[Theme]\nCurrent=old\n";
-        let out = apply_theme_to_content(cfg, "new");
-        assert!(out.contains("Current=new"));
-        assert!(!out.contains("Current=old"));
+impl EscalationSession {
+    pub fn new(escalation: Option<Escalation>) -> Self {
+        Self { escalation, search_path: None, authenticated: Cell::new(false) }
     }
 
-    #[test]
+    /// Whether the password was checked on its own, so a failing command
+    /// was not rejected for it.
+    pub fn authenticated(&self) -> bool {
+        self.authenticated.get()
+    }
+
+    /// The command running `command` as root, to change `target`,
+    /// authenticating first as described above.  Errors like
+    /// [`required_escalation`] when there is no tool.
+    pub fn command<S: AsRef<OsStr>>(
+        &self,
+        target: &Path,
+        command: &[S],
+    ) -> Result<(Escalation, Command)> {
+        let escalation = required_escalation(self.escalation, target)?;
+        if !self.authenticated.get() && !self.runs_unprompted(escalation, command) {
+            self.authenticate(escalation)?;
+        }
+        Ok((escalation, self.tool_command(escalation.argv(command))))
+    }
+
+    /// Whether `command` can run without a password prompt (see
+    /// `Escalation::probe_argv`).  Always `false` for doas.
+    fn runs_unprompted<S: AsRef<OsStr>>(&self, escalation: Escalation, command: &[S]) -> bool {
+        escalation.probe_argv(command).is_some_and(|argv| {
+            self.tool_command(argv)
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|s| s.success())
+        })
+    }
+
+    /// Prompt for the password (inherited terminal) with
+    /// `Escalation::auth_argv`, if the tool has such a step.
+    fn authenticate(&self, escalation: Escalation) -> Result<()> {
+        let Some(argv) = escalation.auth_argv() else {
+            return Ok(());
+        };
+        let tool = escalation.program();
+        let status = self.tool_command(argv).status().with_context(|| {
+            format!("Failed to run `{tool}`. Ensure {tool} is installed and configured.")
+        })?;
+        if !status.success() {
+            bail!(
+                "{tool} authentication failed or timed out ({status}). \
+                 If no password prompt appeared, run themewalker with {tool} directly."
+            );
+        }
+        self.authenticated.set(true);
+        Ok(())
+    }
+
+    /// `argv` as a command, looked up in `search_path` when set.
+    fn tool_command(&self, argv: Vec<OsString>) -> Command {
+        let mut cmd = Command::new(&argv[0]);
+        cmd.args(&argv[1..]);
+        if let Some(path) = &self.search_path {
+            cmd.env("PATH", path);
+        }
+        cmd
+    }
+}
+
+/// `escalation`, or an error saying `path` needs root when there is no
+/// tool to get it with.
+pub(crate) fn required_escalation(
+    escalation: Option<Escalation>,
+    path: &Path,
+) -> Result<Escalation> {
+    match escalation {
+        Some(escalation) => Ok(escalation),
+        None => bail!(
+            "Changing {} needs root, and neither sudo nor doas is installed. \
+             Run themewalker as root instead.",
+            path.display()
+        ),
+    }
+}
+
+/// The mount point covering `path` in `mounts` (`/proc/mounts` format) when
+/// that mount is read-only.  The longest mount point containing `path` wins,
+/// and among equal ones the last, as it is mounted on top.
+fn read_only_mount(mounts: &str, path: &Path) -> Option<PathBuf> {
+    let mut covering: Option<(PathBuf, bool)> = None;
+    for line in mounts.lines() {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let [_, point, _, options, ..] = fields[..] else {
+            continue;
+        };
+        let point = PathBuf::from(unescape_mount_path(point));
+        let shorter = covering
+            .as_ref()
+            .is_some_and(|(c, _)| c.as_os_str().len() > point.as_os_str().len());
+        if path.starts_with(&point) && !shorter {
+            covering = Some((point, options.split(',').any(|o| o == "ro")));
+        }
+    }
+    covering.filter(|(_, ro)| *ro).map(|(point, _)| point)
+}
+
+/// Decode the octal escapes (`\040` for a space, ...) `/proc/mounts` uses for
+/// whitespace and backslashes in mount points.
+fn unescape_mount_path(escaped: &str) -> String {
+    let mut out = String::with_capacity(escaped.len());
+    let mut rest = escaped;
+    while let Some(i) = rest.find('\\') {
+        out.push_str(&rest[..i]);
+        let code = rest.get(i + 1..i + 4).and_then(|d| u8::from_str_radix(d, 8).ok());
+        match code {
+            Some(byte) => {
+                out.push(char::from(byte));
+                rest = &rest[i + 4..];
+            }
+            None => {
+                out.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The ext2-style attribute letters of `path` as `lsattr -d` prints them
+/// (e.g. `----i---------e-------`), or `None` when they cannot be read
+/// (no `lsattr`, or a filesystem without attributes).
+fn file_attributes(path: &Path) -> Option<String> {
+    let output = Command::new("lsattr")
+        .arg("-d")
+        .arg(path)
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    stdout.split_whitespace().next().map(str::to_string)
+}
+
+/// The attribute among `attrs` that forbids replacing the file: `i`
+/// (immutable) or `a` (append-only).
+fn blocking_attribute(attrs: &str) -> Option<char> {
+    ['i', 'a'].into_iter().find(|&flag| attrs.contains(flag))
+}
+
+fn sudo_mkdir(session: &EscalationSession, dir: &Path) -> Result<()> {
+    let mkdir = ["mkdir".as_ref(), "-p".as_ref(), dir.as_os_str()];
+    let (escalation, mut command) = session.command(dir, &mkdir)?;
+    let tool = escalation.program();
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run `{tool} mkdir`"))?;
+    if !status.success() {
+        bail!("`{tool} mkdir -p {}` failed", dir.display());
+    }
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // --- parse_current_theme ---
+
+    #[test]
+    fn parses_theme_in_section() {
+        let cfg = "[General]\nFoo=bar\n\n[Theme]\nCurrent=breeze\n";
+        assert_eq!(parse_current_theme(cfg).as_deref(), Some("breeze"));
+    }
+
+    #[test]
+    fn parses_theme_first_section() {
+        let cfg = "[Theme]\nCurrent=maya\n\n[Users]\nMinimumUid=1000\n";
+        assert_eq!(parse_current_theme(cfg).as_deref(), Some("maya"));
+    }
+
+    #[test]
+    fn returns_none_when_no_theme_section() {
+        let cfg = "[General]\nFoo=bar\n";
+        assert!(parse_current_theme(cfg).is_none());
+    }
+
+    #[test]
+    fn returns_none_when_current_missing() {
+        let cfg = "[Theme]\nFontSize=12\n";
+        assert!(parse_current_theme(cfg).is_none());
+    }
+
+    #[test]
+    fn ignores_current_outside_theme_section() {
+        let cfg = "[General]\nCurrent=breeze\n[Theme]\n";
+        assert!(parse_current_theme(cfg).is_none());
+    }
+
+    #[test]
+    fn parses_value_with_spaces_and_equals() {
+        let cfg = "[Theme]\nCurrent=My Theme=2\n";
+        assert_eq!(parse_current_theme(cfg).as_deref(), Some("My Theme=2"));
+    }
+
+    #[test]
+    fn parse_trims_trailing_spaces() {
+        let cfg = "[Theme]\nCurrent=breeze  \n";
+        assert_eq!(parse_current_theme(cfg).as_deref(), Some("breeze"));
+    }
+
+    #[test]
+    fn parse_allows_spaces_around_equals_and_quotes() {
+        for line in [
+            "Current = breeze",
+            "Current\t=\tbreeze",
+            "Current=\"breeze\"",
+            "Current = \"breeze\"",
+            "  Current =breeze  ",
+        ] {
+            let cfg = format!("[Theme]\n{line}\n");
+            assert_eq!(parse_current_theme(&cfg).as_deref(), Some("breeze"), "{line:?}");
+            assert!(has_theme_section(&cfg), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn parse_ignores_similar_keys_and_empty_quotes() {
+        assert_eq!(parse_current_theme("[Theme]\nCurrentTheme = breeze\n"), None);
+        assert_eq!(parse_current_theme("[Theme]\nCurrent=\"\"\n"), None);
+        // A lone quote is not a quoted value
+        assert_eq!(parse_current_theme("[Theme]\nCurrent=\"\n").as_deref(), Some("\""));
+    }
+
+    #[test]
+    fn parses_value_that_looks_like_section_header() {
+        let cfg = "[Theme]\nCurrent=[Theme]\n\n[Users]\nMinimumUid=1000\n";
+        assert_eq!(parse_current_theme(cfg).as_deref(), Some("[Theme]"));
+        assert!(has_theme_section(cfg));
+    }
+
+    // --- config file discovery ---
+
+    #[test]
+    fn finds_every_file_setting_theme_in_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[Theme]\nCurrent=breeze\n").unwrap();
+        fs::write(conf_d.join("20-theme.conf"), "[Theme]\nCurrent=maya\n").unwrap();
+        fs::write(conf_d.join("10-general.conf"), "[General]\nNumlock=on\n").unwrap();
+        fs::write(conf_d.join("05-theme.conf"), "[Theme]\nCurrent=aerial\n").unwrap();
+        fs::write(conf_d.join("99-theme.conf.bak"), "[Theme]\nCurrent=old\n").unwrap();
+
+        let files = files_setting_theme(&main, &conf_d);
+        assert_eq!(
+            files,
+            vec![main, conf_d.join("05-theme.conf"), conf_d.join("20-theme.conf")]
+        );
+    }
+
+    #[test]
+    fn last_file_setting_theme_is_effective() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[Theme]\nCurrent=breeze\n").unwrap();
+        fs::write(conf_d.join("20-theme.conf"), "[Theme]\nCurrent=maya\n").unwrap();
+        fs::write(conf_d.join("05-theme.conf"), "[Theme]\nCurrent=aerial\n").unwrap();
+        fs::write(conf_d.join("30-general.conf"), "[General]\nNumlock=on\n").unwrap();
+
+        // The legacy file is edited, but the last drop-in decides
+        let cfg = load_from_locations(&main, &conf_d, false).unwrap();
+        assert_eq!(cfg.path, main);
+        assert_eq!(cfg.current_theme.as_deref(), Some("breeze"));
+        assert_eq!(
+            cfg.effective_current(),
+            (Some("maya".to_string()), conf_d.join("20-theme.conf"))
+        );
+
+        // Writing a file that sorts last makes its value effective...
+        let mut cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        cfg.apply_theme("sugar").unwrap();
+        assert_eq!(
+            cfg.effective_current(),
+            (Some("sugar".to_string()), conf_d.join(DROPIN_FILE))
+        );
+
+        // ...while writing an overridden one does not
+        let mut cfg = cfg.with_target(&main).unwrap();
+        cfg.apply_theme("breeze-dark").unwrap();
+        assert_eq!(cfg.effective_current().0.as_deref(), Some("sugar"));
+    }
+
+    #[test]
+    fn effective_theme_without_any_setting_is_the_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let cfg = load_from_locations(&main, &dir.path().join("sddm.conf.d"), false).unwrap();
+        assert_eq!(cfg.effective_current(), (None, main));
+
+        let path = dir.path().join("explicit.conf");
+        fs::write(&path, "[Theme]\nCurrent=maya\n").unwrap();
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        assert_eq!(cfg.effective_current(), (Some("maya".to_string()), path));
+    }
+
+    #[test]
+    fn parses_custom_theme_dir() {
+        let cfg = "[General]\nThemeDir=/ignored\n\n\
+                   [Theme]\nCurrent=breeze\nThemeDir = \"/srv/sddm/themes\"\n";
+        assert_eq!(parse_theme_dir(cfg), Some(PathBuf::from("/srv/sddm/themes")));
+        assert_eq!(parse_theme_dir("[Theme]\nThemeDir=\n"), None);
+        assert_eq!(parse_theme_dir("[Theme]\nThemeDirs=/x\n"), None);
+    }
+
+    #[test]
+    fn last_file_setting_theme_dir_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[Theme]\nCurrent=breeze\nThemeDir=/opt/themes\n").unwrap();
+        let cfg = load_from_locations(&main, &conf_d, false).unwrap();
+        assert_eq!(cfg.theme_dir(), Some(PathBuf::from("/opt/themes")));
+
+        // A drop-in without Current= still moves the directory
+        fs::write(conf_d.join("10-dirs.conf"), "[Theme]\nThemeDir=/srv/themes\n").unwrap();
+        let cfg = load_from_locations(&main, &conf_d, false).unwrap();
+        assert_eq!(cfg.path, main);
+        assert_eq!(cfg.theme_dir(), Some(PathBuf::from("/srv/themes")));
+
+        let cfg = SddmConfig::load_from(&conf_d.join("10-dirs.conf")).unwrap();
+        assert_eq!(cfg.theme_dir(), Some(PathBuf::from("/srv/themes")));
+        assert_eq!(SddmConfig::empty().theme_dir(), None);
+    }
+
+    #[test]
+    fn missing_locations_yield_no_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = files_setting_theme(&dir.path().join("nope.conf"), &dir.path().join("nope.d"));
+        assert!(files.is_empty());
+    }
+
+    #[test]
+    fn default_load_edits_the_legacy_file_setting_the_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[Theme]\nCurrent=breeze\n").unwrap();
+
+        let cfg = load_from_locations(&main, &conf_d, false).unwrap();
+        assert_eq!(cfg.path, main);
+        assert_eq!(cfg.current_theme.as_deref(), Some("breeze"));
+        assert_eq!(cfg.shadowed_legacy, None);
+    }
+
+    #[test]
+    fn prefer_dropin_targets_own_dropin_and_flags_legacy() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[Theme]\nCurrent=breeze\n").unwrap();
+
+        let cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        assert_eq!(cfg.path, conf_d.join(DROPIN_FILE));
+        assert_eq!(cfg.raw_content(), "");
+        assert_eq!(cfg.current_theme.as_deref(), Some("breeze"));
+        assert_eq!(cfg.shadowed_legacy, Some(main.clone()));
+        assert!(cfg.conflict.is_empty());
+
+        // A drop-in overrides the legacy file, so its theme is the old one
+        fs::write(conf_d.join("50-theme.conf"), "[Theme]\nCurrent=sugar\n").unwrap();
+        let cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        assert_eq!(cfg.current_theme.as_deref(), Some("sugar"));
+        fs::remove_file(conf_d.join("50-theme.conf")).unwrap();
+
+        fs::write(conf_d.join(DROPIN_FILE), "[Theme]\nCurrent=maya\n").unwrap();
+        let cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        assert_eq!(cfg.current_theme.as_deref(), Some("maya"));
+        assert_eq!(cfg.conflict, vec![main, conf_d.join(DROPIN_FILE)]);
+    }
+
+    #[test]
+    fn lists_every_config_file_as_a_candidate() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::create_dir(&conf_d).unwrap();
+        fs::write(&main, "[General]\nNumlock=on\n").unwrap();
+        fs::write(conf_d.join("kde_settings.conf"), "[Theme]\nCurrent=breeze\n").unwrap();
+        fs::write(conf_d.join("hidpi.conf"), "[General]\n").unwrap();
+
+        let cfg = load_from_locations(&main, &conf_d, false).unwrap();
+        assert_eq!(cfg.path, conf_d.join("kde_settings.conf"));
+        let expected = vec![
+            main.clone(),
+            conf_d.join("hidpi.conf"),
+            conf_d.join("kde_settings.conf"),
+        ];
+        assert_eq!(cfg.candidates, expected);
+
+        // A drop-in still to be created is offered too
+        let cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        assert_eq!(cfg.candidates.last(), Some(&conf_d.join(DROPIN_FILE)));
+        assert_eq!(cfg.candidates.len(), 4);
+
+        // Switching target reads that file and keeps the candidates
+        let legacy = cfg.with_target(&main).unwrap();
+        assert_eq!(legacy.path, main);
+        assert_eq!(legacy.current_theme, None);
+        assert_eq!(legacy.raw_content(), "[General]\nNumlock=on\n");
+        assert_eq!(legacy.candidates, cfg.candidates);
+    }
+
+    #[test]
+    fn prefer_dropin_without_legacy_theme_shadows_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let main = dir.path().join("sddm.conf");
+        let conf_d = dir.path().join("sddm.conf.d");
+        fs::write(&main, "[General]\nNumlock=on\n").unwrap();
+
+        let cfg = load_from_locations(&main, &conf_d, true).unwrap();
+        assert_eq!(cfg.path, conf_d.join(DROPIN_FILE));
+        assert_eq!(cfg.current_theme, None);
+        assert_eq!(cfg.shadowed_legacy, None);
+    }
+
+    // --- load_from ---
+
+    #[test]
+    fn load_from_reads_explicit_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("custom.conf");
+        fs::write(&path, "[Theme]\nCurrent=maya\n").unwrap();
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        assert_eq!(cfg.path, path);
+        assert_eq!(cfg.current_theme.as_deref(), Some("maya"));
+        assert!(cfg.config_exists);
+    }
+
+    #[test]
+    fn load_from_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing.conf");
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        assert_eq!(cfg.path, path);
+        assert!(cfg.current_theme.is_none());
+        assert!(cfg.raw_content().is_empty());
+        assert!(!cfg.config_exists);
+    }
+
+    #[test]
+    fn apply_theme_marks_new_config_as_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut cfg = SddmConfig::load_from(&dir.path().join("sddm.conf")).unwrap();
+        assert!(!cfg.config_exists);
+        cfg.apply_theme("maya").unwrap();
+        assert!(cfg.config_exists);
+    }
+
+    #[test]
+    fn config_env_overrides_load_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sandbox.conf");
+        fs::write(&path, "[Theme]\nCurrent=sandboxed\n").unwrap();
+
+        // No other test calls `load`, so setting the variable is race-free
+        std::env::set_var(CONFIG_ENV, &path);
+        let cfg = SddmConfig::load();
+        std::env::remove_var(CONFIG_ENV);
+
+        let cfg = cfg.unwrap();
+        assert_eq!(cfg.path, path);
+        assert_eq!(cfg.current_theme.as_deref(), Some("sandboxed"));
+        assert!(cfg.conflict.is_empty());
+    }
+
+    // --- write_theme ---
+
+    fn config_at(path: PathBuf, raw_content: &str) -> SddmConfig {
+        SddmConfig {
+            path,
+            current_theme: parse_current_theme(raw_content),
+            conflict: Vec::new(),
+            shadowed_legacy: None,
+            config_exists: true,
+            candidates: Vec::new(),
+            effective: None,
+            theme_dir: None,
+            locations: None,
+            raw_content: raw_content.to_string(),
+            format: Arc::new(IniFormat),
+            escalation: Some(Escalation::Sudo),
+        }
+    }
+
+    #[test]
+    fn describe_change_summarises_each_case() {
+        let path = PathBuf::from("/etc/sddm.conf");
+        let replace = config_at(path.clone(), "[Theme]\n  Current=breeze \n");
+        assert_eq!(replace.describe_change("maya"), "Current=breeze → Current=maya");
+        assert_eq!(replace.describe_change("breeze"), "Current=breeze (unchanged)");
+
+        let add_key = config_at(path.clone(), "[Theme]\nFontSize=10\n");
+        assert_eq!(add_key.describe_change("maya"), "adds Current=maya to [Theme]");
+
+        let add_section = config_at(path.clone(), "[General]\nCurrent=breeze\n");
+        assert_eq!(
+            add_section.describe_change("maya"),
+            "adds [Theme] section with Current=maya"
+        );
+        assert_eq!(
+            config_at(path, "").describe_change("maya"),
+            "adds [Theme] section with Current=maya"
+        );
+    }
+
+    #[test]
+    fn backup_path_appends_suffix() {
+        let cfg = config_at(PathBuf::from("/etc/sddm.conf"), "");
+        assert_eq!(cfg.backup_path(), PathBuf::from("/etc/sddm.conf.themewalker.bak"));
+    }
+
+    #[test]
+    fn write_theme_backs_up_existing_content() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        let original = "[Theme]\nCurrent=old\n";
+        fs::write(&path, original).unwrap();
+
+        let cfg = config_at(path.clone(), original);
+        let backup = cfg.write_theme("new").unwrap().expect("backup expected");
+
+        assert_eq!(fs::read_to_string(&backup).unwrap(), original);
+        assert!(fs::read_to_string(&path).unwrap().contains("Current=new"));
+    }
+
+    #[test]
+    fn direct_write_replaces_file_and_keeps_mode() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        try_direct_write(&path, "[Theme]\nCurrent=new\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=new\n");
+        assert_eq!(fs::metadata(&path).unwrap().mode() & 0o777, 0o640);
+        // The temp file was renamed away, not left beside the config
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn direct_write_through_symlink_keeps_link() {
+        let dir = tempfile::tempdir().unwrap();
+        let real = dir.path().join("real.conf");
+        let link = dir.path().join("sddm.conf");
+        fs::write(&real, "[Theme]\n").unwrap();
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        try_direct_write(&link, "[Theme]\nCurrent=new\n").unwrap();
+
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&real).unwrap(), "[Theme]\nCurrent=new\n");
+    }
+
+    #[test]
+    fn needs_sudo_is_false_for_writable_temp_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        assert!(!config_at(path.clone(), "").needs_sudo());
+
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        assert!(!config_at(path, "[Theme]\nCurrent=old\n").needs_sudo());
+    }
+
+    #[test]
+    fn needs_sudo_check_does_not_modify_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\n").unwrap();
+        config_at(path.clone(), "[Theme]\n").needs_sudo();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Theme]\n");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn write_theme_skips_backup_for_new_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = config_at(dir.path().join("sddm.conf"), "");
+        assert!(cfg.write_theme("breeze").unwrap().is_none());
+        assert!(!cfg.backup_path().exists());
+    }
+
+    #[test]
+    fn apply_theme_updates_in_memory_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        let mut cfg = config_at(path.clone(), "[Theme]\nCurrent=old\n");
+
+        cfg.apply_theme("new").unwrap();
+        assert_eq!(cfg.current_theme.as_deref(), Some("new"));
+        assert_eq!(cfg.raw_content(), fs::read_to_string(&path).unwrap());
+    }
+
+    /// A one-line `theme: <name>` format, standing in for a custom one.
+    struct LineFormat;
+
+    impl ConfigFormat for LineFormat {
+        fn parse_current(&self, content: &str) -> Option<String> {
+            content.strip_prefix("theme: ").map(|v| v.trim().to_string())
+        }
+
+        fn apply(&self, _content: &str, theme_name: &str) -> String {
+            format!("theme: {theme_name}\n")
+        }
+    }
+
+    #[test]
+    fn custom_format_reads_and_writes_the_theme() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.yaml");
+        fs::write(&path, "theme: old\n").unwrap();
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        assert_eq!(cfg.current_theme, None);
+
+        let mut cfg = cfg.with_format(LineFormat);
+        assert_eq!(cfg.current_theme.as_deref(), Some("old"));
+        assert_eq!(cfg.describe_change("new"), "old → new");
+        assert_eq!(cfg.describe_change("old"), "old (unchanged)");
+        cfg.apply_theme("new").unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "theme: new\n");
+        assert_eq!(cfg.read_backup().unwrap().1.as_deref(), Some("old"));
+    }
+
+    #[test]
+    fn config_can_move_to_another_thread() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<SddmConfig>();
+    }
+
+    #[test]
+    fn restore_backup_rolls_back_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        let original = "[General]\nNumlock=on\n\n[Theme]\nCurrent=old\n";
+        fs::write(&path, original).unwrap();
+        config_at(path.clone(), original).write_theme("new").unwrap();
+
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        assert_eq!(cfg.read_backup().unwrap().1.as_deref(), Some("old"));
+        assert_eq!(cfg.restore_backup().unwrap().as_deref(), Some("old"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+    }
+
+    #[test]
+    fn restore_backup_without_backup_errors() {
+        let dir = tempfile::tempdir().unwrap();
+        let cfg = config_at(dir.path().join("sddm.conf"), "");
+        let err = cfg.restore_backup().unwrap_err();
+        assert!(err.to_string().contains("No backup found"));
+    }
+
+    // --- check_writability ---
+
+    #[test]
+    fn finds_read_only_mount_covering_path() {
+        let mounts = "proc /proc proc rw,nosuid 0 0\n\
+                      /dev/sda2 / ext4 rw,relatime 0 0\n\
+                      /dev/sda3 /etc ext4 ro,relatime 0 0\n\
+                      /dev/sda4 /etc/sddm.conf.d ext4 rw 0 0\n";
+        assert_eq!(
+            read_only_mount(mounts, Path::new("/etc/sddm.conf")),
+            Some(PathBuf::from("/etc"))
+        );
+        // A writable mount nested inside wins for paths below it
+        assert_eq!(read_only_mount(mounts, Path::new("/etc/sddm.conf.d/x.conf")), None);
+        // "/etcetera" is not inside "/etc"
+        assert_eq!(read_only_mount(mounts, Path::new("/etcetera/sddm.conf")), None);
+        assert_eq!(read_only_mount("", Path::new("/etc/sddm.conf")), None);
+    }
+
+    #[test]
+    fn later_mount_on_same_point_wins() {
+        let mounts = "/dev/sda3 /etc ext4 rw 0 0\noverlay /etc overlay ro 0 0\n";
+        assert!(read_only_mount(mounts, Path::new("/etc/sddm.conf")).is_some());
+        let mounts = "overlay /etc overlay ro 0 0\n/dev/sda3 /etc ext4 rw 0 0\n";
+        assert!(read_only_mount(mounts, Path::new("/etc/sddm.conf")).is_none());
+    }
+
+    #[test]
+    fn unescapes_mount_points() {
+        assert_eq!(unescape_mount_path("/mnt/my\\040disk"), "/mnt/my disk");
+        assert_eq!(unescape_mount_path("/a\\134b"), "/a\\b");
+        assert_eq!(unescape_mount_path("/plain"), "/plain");
+        assert_eq!(unescape_mount_path("/odd\\9"), "/odd\\9");
+    }
+
+    #[test]
+    fn immutable_and_append_only_block_writes() {
+        assert_eq!(blocking_attribute("----i---------e-------"), Some('i'));
+        assert_eq!(blocking_attribute("-----a--------e-------"), Some('a'));
+        assert_eq!(blocking_attribute("--------------e-------"), None);
+    }
+
+    #[test]
+    fn ordinary_file_is_writable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=breeze\n").unwrap();
+        let config = SddmConfig::load_from(&path).unwrap();
+        config.check_writability().unwrap();
+    }
+
+    // --- escalation ---
+
+    #[test]
+    fn escalation_prefers_sudo_then_doas() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = tempfile::tempdir().unwrap();
+        let (sbin, bin) = (dir.path().join("sbin"), dir.path().join("bin"));
+        fs::create_dir_all(&sbin).unwrap();
+        fs::create_dir_all(&bin).unwrap();
+        let path = std::env::join_paths([&sbin, &bin]).unwrap();
+        let install = |tool: &Path, mode: u32| {
+            fs::write(tool, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(tool, fs::Permissions::from_mode(mode)).unwrap();
+        };
+
+        assert_eq!(Escalation::detect_in(&path), None);
+        // Not executable, so not usable
+        install(&sbin.join("sudo"), 0o644);
+        assert_eq!(Escalation::detect_in(&path), None);
+        install(&bin.join("doas"), 0o755);
+        assert_eq!(Escalation::detect_in(&path), Some(Escalation::Doas));
+        install(&sbin.join("sudo"), 0o755);
+        assert_eq!(Escalation::detect_in(&path), Some(Escalation::Sudo));
+    }
+
+    #[test]
+    fn escalation_builds_command_lines_for_each_backend() {
+        let path = Path::new("/etc/sddm.conf.d/zz-themewalker.conf");
+        let tee = ["tee".as_ref(), path.as_os_str()];
+        let strings = |argv: Vec<OsString>| -> Vec<String> {
+            argv.into_iter().map(|a| a.into_string().unwrap()).collect()
+        };
+
+        assert_eq!(
+            strings(Escalation::Sudo.argv(&tee)),
+            ["sudo", "--", "tee", "/etc/sddm.conf.d/zz-themewalker.conf"]
+        );
+        assert_eq!(
+            strings(Escalation::Doas.argv(&tee)),
+            ["doas", "--", "tee", "/etc/sddm.conf.d/zz-themewalker.conf"]
+        );
+        assert_eq!(
+            strings(Escalation::Doas.argv(&["mkdir", "-p", "/etc/sddm.conf.d"])),
+            ["doas", "--", "mkdir", "-p", "/etc/sddm.conf.d"]
+        );
+        assert_eq!(strings(Escalation::Sudo.auth_argv().unwrap()), ["sudo", "-v"]);
+        assert_eq!(Escalation::Doas.auth_argv(), None);
+        assert_eq!(
+            strings(Escalation::Sudo.probe_argv(&tee).unwrap()),
+            ["sudo", "-n", "-l", "--", "tee", "/etc/sddm.conf.d/zz-themewalker.conf"]
+        );
+        assert_eq!(Escalation::Doas.probe_argv(&tee), None);
+        assert_eq!(Escalation::from_name("doas"), Some(Escalation::Doas));
+        assert_eq!(Escalation::from_name("su"), None);
+    }
+
+    #[test]
+    fn writing_without_an_escalation_tool_asks_for_root() {
+        let err = required_escalation(None, Path::new("/etc/sddm.conf")).unwrap_err();
+        assert!(err.to_string().contains("neither sudo nor doas is installed"), "{err}");
+        let tool = required_escalation(Some(Escalation::Doas), Path::new("/etc/sddm.conf"));
+        assert_eq!(tool.unwrap(), Escalation::Doas);
+    }
+
+    /// A session whose tool is a script in a fresh directory that logs its
+    /// arguments, answers `sudo -n` probes with `probe_status` and runs
+    /// whatever follows `--`.  Returns the session, the log and the dir.
+    fn fake_tool_session(
+        escalation: Escalation,
+        probe_status: i32,
+    ) -> (EscalationSession, PathBuf, tempfile::TempDir) {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("calls.log");
+        let script = dir.path().join(escalation.program());
+        fs::write(
+            &script,
+            format!(
+                "#!/bin/sh\necho \"$*\" >> '{}'\n\
+                 case \"$1\" in -n) exit {probe_status} ;; --) shift; exec \"$@\" ;; esac\n",
+                log.display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        let search_path = std::env::join_paths(
+            std::iter::once(dir.path().to_path_buf())
+                .chain(std::env::split_paths(&std::env::var_os("PATH").unwrap_or_default())),
+        )
+        .unwrap();
+        let session = EscalationSession {
+            escalation: Some(escalation),
+            search_path: Some(search_path),
+            authenticated: Cell::new(false),
+        };
+        (session, log, dir)
+    }
+
+    /// Run `true` as root through `session` `times` times and return the
+    /// tool's calls.
+    fn run_escalated(session: &EscalationSession, log: &Path, times: usize) -> Vec<String> {
+        for _ in 0..times {
+            let (_, mut command) = session.command(Path::new("/etc/sddm.conf"), &["true"]).unwrap();
+            assert!(command.status().unwrap().success());
+        }
+        fs::read_to_string(log).unwrap().lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn escalation_session_asks_sudo_for_the_password_once() {
+        let (session, log, _dir) = fake_tool_session(Escalation::Sudo, 1);
+        let calls = run_escalated(&session, &log, 3);
+        assert_eq!(calls, ["-n -l -- true", "-v", "-- true", "-- true", "-- true"]);
+        assert!(session.authenticated());
+        assert!(EscalationSession::new(None).command(&log, &["true"]).is_err());
+    }
+
+    #[test]
+    fn escalation_session_skips_sudo_password_for_nopasswd_commands() {
+        let (session, log, _dir) = fake_tool_session(Escalation::Sudo, 0);
+        let calls = run_escalated(&session, &log, 2);
+        assert_eq!(calls, ["-n -l -- true", "-- true", "-n -l -- true", "-- true"]);
+        assert!(!session.authenticated());
+    }
+
+    #[test]
+    fn escalation_session_runs_doas_only_for_the_commands() {
+        let (session, log, _dir) = fake_tool_session(Escalation::Doas, 1);
+        let calls = run_escalated(&session, &log, 2);
+        assert_eq!(calls, ["-- true", "-- true"]);
+    }
+
+    // --- apply_theme_to_content ---
+
+    #[test]
+    fn replaces_existing_current() {
+        let cfg = "[Theme]\nCurrent=old\n";
+        let out = apply_theme_to_content(cfg, "new");
+        assert!(out.contains("Current=new"));
+        assert!(!out.contains("Current=old"));
+    }
+
+    #[test]
+    fn replacing_keeps_spacing_and_quoting() {
+        for (line, expected) in [
+            ("Current = old", "Current = new"),
+            ("Current=\"old\"", "Current=\"new\""),
+            ("Current = \"old\"", "Current = \"new\""),
+            ("Current\t=  old", "Current\t=  new"),
+            ("  Current=old", "  Current=new"),
+        ] {
+            let out = apply_theme_to_content(&format!("[Theme]\n{line}\n[Users]\n"), "new");
+            assert_eq!(out, format!("[Theme]\n{expected}\n[Users]\n"), "{line:?}");
+        }
+    }
+
+    #[test]
+    fn describe_change_compares_unquoted_values() {
+        let path = PathBuf::from("/etc/sddm.conf");
+        let quoted = config_at(path, "[Theme]\nCurrent = \"breeze\"\n");
+        assert_eq!(quoted.describe_change("breeze"), "Current = \"breeze\" (unchanged)");
+        assert_eq!(
+            quoted.describe_change("maya"),
+            "Current = \"breeze\" → Current = \"maya\""
+        );
+    }
+
+    #[test]
     fn inserts_current_into_existing_theme_section() {
         let cfg = "[Theme]\nFontSize=12\n\n[General]\nFoo=bar\n";
         let out = apply_theme_to_content(cfg, "breeze");
@@ -359,6 +2066,243 @@ mod tests {
         assert!(out.contains("Current=aerial"));
     }
 
+    #[test]
+    fn inserted_key_goes_before_blank_separator() {
+        let cfg = "[Theme]\nFontSize=12\n\n[General]\nFoo=bar\n";
+        let out = apply_theme_to_content(cfg, "breeze");
+        assert_eq!(out, "[Theme]\nFontSize=12\nCurrent=breeze\n\n[General]\nFoo=bar\n");
+    }
+
+    #[test]
+    fn roundtrip_is_byte_exact_apart_from_current() {
+        let cfg = "# SDDM config\n\
+                   [General]\n\
+                   InputMethod=\n\
+                   Numlock=on   # keep numlock\n\
+                   Foo=1\n\
+                   Foo=2\n\
+                   \n\
+                   \n\
+                   [Theme]\n\
+                   # pick one of the installed themes\n\
+                   ; Current=commented-out\n\
+                   Current=old   \n\
+                   FontSize=12  \n\
+                   \n\
+                   [Users]\n\
+                   MinimumUid=1000\n";
+        let out = apply_theme_to_content(cfg, "new");
+        assert_eq!(out, cfg.replace("Current=old   \n", "Current=new\n"));
+    }
+
+    #[test]
+    fn keeps_whether_the_file_ends_with_a_newline() {
+        let cases = [
+            // Replaced in place
+            ("[Theme]\nCurrent=old", "[Theme]\nCurrent=new"),
+            // Inserted at the end of [Theme]
+            ("[Theme]\nFontSize=12", "[Theme]\nFontSize=12\nCurrent=new"),
+            ("[Theme]\r\nFontSize=12", "[Theme]\r\nFontSize=12\r\nCurrent=new"),
+            // Appended as a new section
+            (
+                "[General]\nNumlock=on",
+                "[General]\nNumlock=on\n\n# Set by Themewalker on 2024-05-01\n[Theme]\nCurrent=new",
+            ),
+        ];
+        for (cfg, expected) in cases {
+            assert_eq!(apply_theme_on(cfg, "new", "2024-05-01"), expected, "{cfg:?}");
+            let terminated = format!("{cfg}{}", detect_line_ending(cfg));
+            let expected = format!("{expected}{}", detect_line_ending(cfg));
+            assert_eq!(apply_theme_on(&terminated, "new", "2024-05-01"), expected);
+        }
+    }
+
+    #[test]
+    fn roundtrip_preserves_crlf_terminators() {
+        let cfg = "[General]\r\nNumlock=on\r\n\r\n[Theme]\r\nCurrent=old\r\n\r\n[Users]\r\nMinimumUid=1000\r\n";
+        let out = apply_theme_to_content(cfg, "new");
+        assert_eq!(out, cfg.replace("Current=old", "Current=new"));
+    }
+
+    #[test]
+    fn crlf_config_keeps_crlf() {
+        let out = apply_theme_to_content("[Theme]\r\nCurrent=old\r\n", "new");
+        assert_eq!(out, "[Theme]\r\nCurrent=new\r\n");
+    }
+
+    #[test]
+    fn inserted_lines_use_dominant_line_ending() {
+        let out = apply_theme_on("[General]\r\nNumlock=on\r\n", "new", "2024-05-01");
+        assert_eq!(
+            out,
+            "[General]\r\nNumlock=on\r\n\r\n\
+             # Set by Themewalker on 2024-05-01\r\n[Theme]\r\nCurrent=new\r\n"
+        );
+
+        let out = apply_theme_to_content("[Theme]\r\nFontSize=12\r\n\r\n[Users]\r\n", "new");
+        assert_eq!(out, "[Theme]\r\nFontSize=12\r\nCurrent=new\r\n\r\n[Users]\r\n");
+    }
+
+    #[test]
+    fn new_section_is_marked_with_date() {
+        let out = apply_theme_on("", "aerial", "2024-05-01");
+        assert_eq!(out, "# Set by Themewalker on 2024-05-01\n[Theme]\nCurrent=aerial\n");
+
+        // Patching an existing section adds no comment
+        let out = apply_theme_on("[Theme]\nFontSize=12\n", "aerial", "2024-05-01");
+        assert!(!out.contains('#'));
+    }
+
+    #[test]
+    fn theme_keys_are_listed_in_file_order() {
+        let content = "[General]\nNumlock=on\n[Theme]\n# CursorTheme=old\nCurrent = \"breeze\"\n\
+                       CursorTheme=Adwaita\nFont=Noto Sans,10\nCurrent=ignored\n";
+        assert_eq!(
+            parse_theme_keys(content),
+            [
+                ("Current".to_string(), "breeze".to_string()),
+                ("CursorTheme".to_string(), "Adwaita".to_string()),
+                ("Font".to_string(), "Noto Sans,10".to_string()),
+            ]
+        );
+        assert!(parse_theme_keys("[General]\nNumlock=on\n").is_empty());
+    }
+
+    #[test]
+    fn several_keys_are_patched_in_place_or_inserted() {
+        let content = "[Theme]\nCurrent=old\n  Font = \"Sans\"\n\n[Users]\nMaximumUid=60000\n";
+        let keys = [("Font", "Noto Sans"), ("Current", "breeze"), ("EnableAvatars", "false")];
+        let out = apply_keys_on(content, &keys, "2024-05-01");
+        assert_eq!(
+            out,
+            "[Theme]\nCurrent=breeze\n  Font = \"Noto Sans\"\nEnableAvatars=false\n\n\
+             [Users]\nMaximumUid=60000\n"
+        );
+
+        // No [Theme] section: every key goes into a new one, in sorted order
+        let keys = HashMap::from([
+            ("Font".to_string(), "Sans".to_string()),
+            ("CursorTheme".to_string(), "Adwaita".to_string()),
+        ]);
+        let out = apply_keys_to_content("[General]\nNumlock=on\n", &keys);
+        assert!(out.starts_with("[General]\nNumlock=on\n\n# Set by Themewalker on "));
+        assert!(out.ends_with("[Theme]\nCursorTheme=Adwaita\nFont=Sans\n"));
+
+        // Nothing to set leaves the content alone
+        assert_eq!(apply_keys_to_content(content, &HashMap::new()), content);
+    }
+
+    #[test]
+    fn apply_keys_writes_and_refuses_unsafe_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        let mut cfg = config_at(path.clone(), "[Theme]\nCurrent=old\n");
+
+        for (key, value) in [("Font", "Sans\n[Autologin]"), ("Bad=Key", "x"), ("Current", "a]b")] {
+            let keys = HashMap::from([(key.to_string(), value.to_string())]);
+            assert!(cfg.write_keys(&keys).is_err());
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=old\n");
+
+        let keys = HashMap::from([
+            ("Current".to_string(), "breeze".to_string()),
+            ("CursorTheme".to_string(), "Adwaita".to_string()),
+        ]);
+        let backup = cfg.apply_keys(&keys).unwrap();
+        assert_eq!(backup, Some(cfg.backup_path()));
+        let written = "[Theme]\nCurrent=breeze\nCursorTheme=Adwaita\n";
+        assert_eq!(fs::read_to_string(&path).unwrap(), written);
+        assert_eq!(cfg.raw_content(), written);
+        assert_eq!(cfg.current_theme.as_deref(), Some("breeze"));
+    }
+
+    #[test]
+    fn new_config_gets_comment_once_across_edits() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("zz-themewalker.conf");
+        let mut cfg = SddmConfig::load_from(&path).unwrap();
+        cfg.apply_theme("maya").unwrap();
+        let cfg = SddmConfig::load_from(&path).unwrap();
+        cfg.write_theme("breeze").unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert_eq!(content.matches("# Set by Themewalker on ").count(), 1);
+        assert!(content.ends_with("[Theme]\nCurrent=breeze\n"));
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951_782_400), "2000-02-29");
+        assert_eq!(format_date(1_709_251_199), "2024-02-29");
+        assert_eq!(format_date(1_735_689_600), "2025-01-01");
+    }
+
+    #[test]
+    fn detects_line_endings() {
+        assert_eq!(detect_line_ending("a\r\nb\r\nc\n"), "\r\n");
+        assert_eq!(detect_line_ending("a\nb\nc\r\n"), "\n");
+        assert_eq!(detect_line_ending(""), "\n");
+    }
+
+    #[test]
+    fn writes_special_names_verbatim_and_round_trips() {
+        for name in ["My Theme", "a=b", "[Theme]", "sugar candy (dark)"] {
+            let out = apply_theme_to_content("[Theme]\nCurrent=old\n", name);
+            assert_eq!(out, format!("[Theme]\nCurrent={name}\n"));
+            assert_eq!(parse_current_theme(&out).as_deref(), Some(name));
+
+            let out = apply_theme_to_content("[General]\nNumlock=on\n", name);
+            assert_eq!(parse_current_theme(&out).as_deref(), Some(name));
+        }
+    }
+
+    #[test]
+    fn bracket_name_is_replaced_not_treated_as_section() {
+        let cfg = "[Theme]\nCurrent=[Theme]\n";
+        let out = apply_theme_to_content(cfg, "breeze");
+        assert_eq!(out, "[Theme]\nCurrent=breeze\n");
+    }
+
+    #[test]
+    fn check_theme_name_rejects_unwritable_names() {
+        assert!(check_theme_name("My Theme").is_ok());
+        assert!(check_theme_name("a=b").is_ok());
+        assert!(check_theme_name("[Theme]").is_err());
+        assert!(check_theme_name("tab\tname").is_err());
+        assert!(check_theme_name("breeze ").is_err());
+        assert!(check_theme_name(" breeze").is_err());
+        assert!(check_theme_name("bad\nname").is_err());
+        assert!(check_theme_name("").is_err());
+    }
+
+    #[test]
+    fn write_theme_refuses_injected_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        let cfg = config_at(path.clone(), "[Theme]\nCurrent=old\n");
+
+        for name in ["evil\nRootLogin=true", "evil\r\n[Autologin]\nUser=root", "a]\u{1b}[b"] {
+            assert!(!is_safe_theme_name(name));
+            assert!(cfg.write_theme(name).is_err());
+        }
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=old\n");
+    }
+
+    #[test]
+    fn write_theme_refuses_trailing_space_without_touching_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sddm.conf");
+        fs::write(&path, "[Theme]\nCurrent=old\n").unwrap();
+        let cfg = config_at(path.clone(), "[Theme]\nCurrent=old\n");
+
+        assert!(cfg.write_theme("breeze ").is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[Theme]\nCurrent=old\n");
+        assert!(!cfg.backup_path().exists());
+    }
+
     #[test]
     fn roundtrip_preserves_other_sections() {
         let cfg = "[General]\nNumlock=on\n\n[Theme]\nCurrent=breeze\n\n[Users]\nMinimumUid=1000\n";
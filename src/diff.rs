@@ -0,0 +1,161 @@
+//! Minimal line-based unified diff, used by `--dry-run` to show exactly which
+//! config lines would change.
+//!
+//! The diff is computed from a longest-common-subsequence table, which is
+//! quadratic in the number of lines but more than fast enough for config
+//! files.
+
+/// Lines of unchanged context printed around each change.
+const CONTEXT: usize = 3;
+
+/// One step of the edit script turning `old` into `new`.
+enum Op<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+/// Render a unified diff between `old` and `new`.  Returns an empty string
+/// when the two inputs have identical lines.
+pub fn unified_diff(old: &str, new: &str, old_label: &str, new_label: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = diff_lines(&old_lines, &new_lines);
+
+    // Group changed ops (plus surrounding context) into hunk ranges [start, end)
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for (i, op) in ops.iter().enumerate() {
+        if matches!(op, Op::Equal(_)) {
+            continue;
+        }
+        let start = i.saturating_sub(CONTEXT);
+        let end = (i + 1 + CONTEXT).min(ops.len());
+        match ranges.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    if ranges.is_empty() {
+        return String::new();
+    }
+
+    // (old line, new line) reached before each op, 0-based
+    let mut positions = Vec::with_capacity(ops.len() + 1);
+    let (mut o, mut n) = (0, 0);
+    for op in &ops {
+        positions.push((o, n));
+        match op {
+            Op::Equal(_) => {
+                o += 1;
+                n += 1;
+            }
+            Op::Delete(_) => o += 1,
+            Op::Insert(_) => n += 1,
+        }
+    }
+    positions.push((o, n));
+
+    let mut out = format!("--- {old_label}\n+++ {new_label}\n");
+    for (start, end) in ranges {
+        let (o0, n0) = positions[start];
+        let (o1, n1) = positions[end];
+        out.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(o0, o1 - o0),
+            hunk_range(n0, n1 - n0)
+        ));
+        for op in &ops[start..end] {
+            let (prefix, line) = match op {
+                Op::Equal(l) => (' ', l),
+                Op::Delete(l) => ('-', l),
+                Op::Insert(l) => ('+', l),
+            };
+            out.push(prefix);
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    out
+}
+
+/// Build the edit script from an LCS table over whole lines.
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Op<'a>> {
+    let (n, m) = (old.len(), new.len());
+
+    // lcs[i][j] = length of the LCS of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut ops = Vec::with_capacity(n + m);
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[i] == new[j] {
+            ops.push(Op::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            ops.push(Op::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(Op::Insert(new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|&l| Op::Delete(l)));
+    ops.extend(new[j..].iter().map(|&l| Op::Insert(l)));
+    ops
+}
+
+/// Format a hunk range in unified-diff notation (1-based start; an empty
+/// range points at the line before it).
+fn hunk_range(start: usize, len: usize) -> String {
+    match len {
+        0 => format!("{},0", start),
+        1 => format!("{}", start + 1),
+        _ => format!("{},{}", start + 1, len),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn identical_inputs_produce_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "old", "new"), "");
+    }
+
+    #[test]
+    fn replaced_line_has_context() {
+        let out = unified_diff("a\nb\nc\n", "a\nB\nc\n", "old", "new");
+        assert_eq!(out, "--- old\n+++ new\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n");
+    }
+
+    #[test]
+    fn appended_lines_into_empty_file() {
+        let out = unified_diff("", "[Theme]\nCurrent=breeze\n", "old", "new");
+        assert_eq!(out, "--- old\n+++ new\n@@ -0,0 +1,2 @@\n+[Theme]\n+Current=breeze\n");
+    }
+
+    #[test]
+    fn distant_changes_become_separate_hunks() {
+        let old = "1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n";
+        let new = "one\n2\n3\n4\n5\n6\n7\n8\n9\nten\n";
+        let out = unified_diff(old, new, "old", "new");
+        assert_eq!(out.matches("@@ -").count(), 2);
+        assert!(out.contains("@@ -1,4 +1,4 @@\n-1\n+one\n"));
+        assert!(out.contains("@@ -7,4 +7,4 @@\n 7\n 8\n 9\n-10\n+ten\n"));
+    }
+}
@@ -0,0 +1,132 @@
+//! Which display manager the system starts, so Themewalker can warn when
+//! SDDM is not it and a theme change would have no visible effect.
+//!
+//! systemd enables a display manager by linking
+//! `/etc/systemd/system/display-manager.service` to its unit (e.g.
+//! `/usr/lib/systemd/system/gdm.service`).  Only that link is read: no
+//! process is started, so detection cannot stall startup.
+//!
+//! Restarting SDDM (`--restart`) ends every session it started, so this
+//! module also tells whether Themewalker itself runs in a graphical session.
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, ExitStatus};
+
+use anyhow::{Context, Result};
+
+use crate::config::Escalation;
+
+/// Alias systemd links to the enabled display manager's unit.
+const DISPLAY_MANAGER_UNIT: &str = "/etc/systemd/system/display-manager.service";
+
+/// Name of SDDM's unit, without the `.service` suffix.
+pub const SDDM: &str = "sddm";
+
+/// `$XDG_SESSION_TYPE` values of sessions whose display server was started
+/// by the display manager.
+const GRAPHICAL_SESSION_TYPES: [&str; 3] = ["x11", "wayland", "mir"];
+
+/// The enabled display manager's unit name without `.service` (e.g. `sddm`,
+/// `gdm`, `lightdm`), or `None` when it cannot be told (no systemd, or no
+/// display manager enabled).
+pub fn detect_display_manager() -> Option<String> {
+    detect_in(Path::new(DISPLAY_MANAGER_UNIT))
+}
+
+/// `detect_display_manager` reading the alias at `unit`.
+fn detect_in(unit: &Path) -> Option<String> {
+    let target = fs::read_link(unit).ok()?;
+    let name = target.file_name()?.to_str()?;
+    let name = name.strip_suffix(".service").unwrap_or(name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+/// Type of the login session Themewalker runs in (`x11`, `wayland`, `tty`,
+/// ...), from `$XDG_SESSION_TYPE` or else `loginctl`.  `None` when neither
+/// can tell.
+pub fn session_type() -> Option<String> {
+    if let Some(kind) = std::env::var("XDG_SESSION_TYPE").ok().filter(|t| !t.is_empty()) {
+        return Some(kind);
+    }
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+    let output = Command::new("loginctl")
+        .args(["show-session", &session, "--property=Type", "--value"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let kind = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!kind.is_empty()).then_some(kind)
+}
+
+/// Whether a session of type `kind` would end if the display manager
+/// restarted.
+pub fn is_graphical(kind: &str) -> bool {
+    GRAPHICAL_SESSION_TYPES.contains(&kind)
+}
+
+/// The unit to restart so SDDM reloads its theme, given the enabled display
+/// manager: the `display-manager` alias when that is SDDM, `sddm` itself when
+/// it cannot be told, and `None` when another display manager is enabled
+/// (restarting it would not show the theme).
+pub fn restart_unit(enabled: Option<&str>) -> Option<&'static str> {
+    match enabled {
+        Some(SDDM) => Some("display-manager"),
+        None => Some(SDDM),
+        Some(_) => None,
+    }
+}
+
+/// Run `sudo systemctl restart <unit>` (or `doas`, per `escalation`), with
+/// the terminal attached for the password prompt.
+pub fn restart(unit: &str, escalation: Escalation) -> Result<ExitStatus> {
+    let tool = escalation.program();
+    escalation
+        .command(&["systemctl", "restart", unit])
+        .status()
+        .with_context(|| format!("Failed to run `{tool} systemctl restart {unit}`"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn reads_unit_name_from_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        let unit = dir.path().join("display-manager.service");
+        symlink("/usr/lib/systemd/system/gdm.service", &unit).unwrap();
+        assert_eq!(detect_in(&unit).as_deref(), Some("gdm"));
+
+        let unit = dir.path().join("relative.service");
+        symlink("../sddm.service", &unit).unwrap();
+        assert_eq!(detect_in(&unit).as_deref(), Some(SDDM));
+    }
+
+    #[test]
+    fn unknown_without_alias() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(detect_in(&dir.path().join("display-manager.service")), None);
+
+        // A plain file is not an alias
+        let unit = dir.path().join("plain.service");
+        fs::write(&unit, "").unwrap();
+        assert_eq!(detect_in(&unit), None);
+    }
+
+    #[test]
+    fn graphical_session_types() {
+        assert!(is_graphical("x11"));
+        assert!(is_graphical("wayland"));
+        assert!(!is_graphical("tty"));
+        assert!(!is_graphical("unspecified"));
+    }
+
+    #[test]
+    fn restarts_only_sddm() {
+        assert_eq!(restart_unit(Some(SDDM)), Some("display-manager"));
+        assert_eq!(restart_unit(None), Some(SDDM));
+        assert_eq!(restart_unit(Some("gdm")), None);
+    }
+}
@@ -0,0 +1,126 @@
+//! Favorite themes, persisted between sessions.
+//!
+//! The state file is `$XDG_CONFIG_HOME/themewalker/favorites.toml` (falling
+//! back to `~/.config`) and holds a single array:
+//!
+//! ```toml
+//! favorites = ["breeze", "sugar-candy"]
+//! ```
+//!
+//! Only that shape is read and written, with `user_config`'s parser, so the
+//! module builds without the `toml` crate the `tui` feature brings in.
+
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+use crate::user_config::parse_string_array;
+
+/// File name inside the themewalker config directory.
+const FAVORITES_FILE: &str = "favorites.toml";
+
+/// Themewalker's per-user config directory (`$XDG_CONFIG_HOME/themewalker`,
+/// falling back to `~/.config/themewalker`), or `None` when neither
+/// variable is set.  Also holds the hooks.
+pub fn config_dir() -> Option<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config_home.join("themewalker"))
+}
+
+/// Where favorites are stored, or `None` when there is no config directory.
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(FAVORITES_FILE))
+}
+
+/// Read favorites from `path`.  A missing or malformed file yields no
+/// favorites rather than an error.
+pub fn load(path: &Path) -> HashSet<String> {
+    fs::read_to_string(path)
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+/// Write `favorites` to `path`, creating its directory if needed.
+pub fn save(path: &Path, favorites: &HashSet<String>) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    fs::write(path, render(favorites))
+        .with_context(|| format!("Failed to write favorites to {}", path.display()))
+}
+
+/// Extract the strings of the `favorites = [...]` array.
+fn parse(content: &str) -> HashSet<String> {
+    parse_string_array(content, "favorites").into_iter().collect()
+}
+
+/// Render `favorites` as the state file, sorted for stable output.
+fn render(favorites: &HashSet<String>) -> String {
+    let mut names: Vec<&String> = favorites.iter().collect();
+    names.sort();
+    let quoted: Vec<String> = names
+        .iter()
+        .map(|n| format!("\"{}\"", n.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("favorites = [{}]\n", quoted.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn parses_single_and_multi_line_arrays() {
+        assert_eq!(parse("favorites = [\"a\", \"b\"]\n"), set(&["a", "b"]));
+        assert_eq!(
+            parse("# pinned themes\nfavorites = [\n  \"a\", # first\n  \"b c\",\n]\n"),
+            set(&["a", "b c"])
+        );
+    }
+
+    #[test]
+    fn parse_handles_escapes_and_garbage() {
+        assert_eq!(
+            parse(r#"favorites = ["say \"hi\"", "back\\slash"]"#),
+            set(&["say \"hi\"", "back\\slash"])
+        );
+        assert!(parse("").is_empty());
+        assert!(parse("other = 1\n").is_empty());
+        assert!(parse("favorites = oops\n").is_empty());
+    }
+
+    #[test]
+    fn render_round_trips() {
+        let favorites = set(&["sugar-candy", "breeze", "My \"Theme\""]);
+        let rendered = render(&favorites);
+        assert_eq!(
+            rendered,
+            "favorites = [\"My \\\"Theme\\\"\", \"breeze\", \"sugar-candy\"]\n"
+        );
+        assert_eq!(parse(&rendered), favorites);
+    }
+
+    #[test]
+    fn save_creates_missing_config_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config/themewalker").join(FAVORITES_FILE);
+        save(&path, &set(&["breeze"])).unwrap();
+        assert_eq!(load(&path), set(&["breeze"]));
+    }
+
+    #[test]
+    fn load_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(load(&dir.path().join(FAVORITES_FILE)).is_empty());
+    }
+}
@@ -0,0 +1,116 @@
+//! A log of applied themes, for working out after the fact what changed and
+//! when.
+//!
+//! The log is `$XDG_STATE_HOME/themewalker/history.log` (falling back to
+//! `~/.local/state`).  Each successful apply appends one line:
+//!
+//! ```text
+//! 2024-05-01 18:30:05 UTC  breeze -> sugar-candy  /etc/sddm.conf
+//! ```
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::config::format_date;
+
+/// File name inside the themewalker state directory.
+const HISTORY_FILE: &str = "history.log";
+
+/// Shown for the old theme when the config did not set one.
+const NO_THEME: &str = "(none)";
+
+/// Where the history is kept (`$XDG_STATE_HOME/themewalker/history.log`,
+/// falling back to `~/.local/state/themewalker/history.log`), or `None`
+/// when neither variable is set.
+pub fn default_path() -> Option<PathBuf> {
+    let state_home = std::env::var_os("XDG_STATE_HOME")
+        .filter(|d| !d.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".local/state")))?;
+    Some(state_home.join("themewalker").join(HISTORY_FILE))
+}
+
+/// Append a line to the log at `log` recording that `config` was switched
+/// from `old` to `new`, creating the log's directory if needed.
+pub fn log_apply(log: &Path, old: Option<&str>, new: &str, config: &Path) -> Result<()> {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    append(log, &entry(secs, old, new, config))
+}
+
+/// The log's content, or `None` when nothing has been applied yet.
+pub fn read(log: &Path) -> Result<Option<String>> {
+    match fs::read_to_string(log) {
+        Ok(content) => Ok(Some(content)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e).with_context(|| format!("Failed to read {}", log.display())),
+    }
+}
+
+/// Append `line` to `log`, creating its directory if needed.
+fn append(log: &Path, line: &str) -> Result<()> {
+    if let Some(dir) = log.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(log)
+        .and_then(|mut f| f.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write history to {}", log.display()))
+}
+
+/// One log line for a switch at `secs` since the Unix epoch.
+fn entry(secs: u64, old: Option<&str>, new: &str, config: &Path) -> String {
+    let time = secs % 86_400;
+    format!(
+        "{} {:02}:{:02}:{:02} UTC  {} -> {new}  {}\n",
+        format_date(secs),
+        time / 3600,
+        time / 60 % 60,
+        time % 60,
+        old.unwrap_or(NO_THEME),
+        config.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_entries() {
+        let config = Path::new("/etc/sddm.conf");
+        assert_eq!(
+            entry(1_714_588_205, Some("breeze"), "sugar-candy", config),
+            "2024-05-01 18:30:05 UTC  breeze -> sugar-candy  /etc/sddm.conf\n"
+        );
+        assert_eq!(
+            entry(0, None, "maya", config),
+            "1970-01-01 00:00:00 UTC  (none) -> maya  /etc/sddm.conf\n"
+        );
+    }
+
+    #[test]
+    fn log_apply_creates_dir_and_appends() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("state/themewalker").join(HISTORY_FILE);
+        assert!(read(&log).unwrap().is_none());
+
+        let config = Path::new("/etc/sddm.conf");
+        log_apply(&log, None, "breeze", config).unwrap();
+        log_apply(&log, Some("breeze"), "maya", config).unwrap();
+
+        let content = read(&log).unwrap().unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].ends_with("UTC  (none) -> breeze  /etc/sddm.conf"));
+        assert!(lines[1].ends_with("UTC  breeze -> maya  /etc/sddm.conf"));
+    }
+}
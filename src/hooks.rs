@@ -0,0 +1,73 @@
+//! User hooks run after a theme is applied (`--run-hooks`).
+//!
+//! The only hook is `post-apply.sh` in Themewalker's config directory.  It is
+//! run with the new theme name as `$1` and the terminal's stdio, after the
+//! config has been written.
+
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, ExitStatus};
+
+use anyhow::{Context, Result};
+
+use crate::favorites;
+
+/// File name of the post-apply hook inside the config directory.
+const POST_APPLY_HOOK: &str = "post-apply.sh";
+
+/// Where the post-apply hook is looked for, or `None` when neither
+/// `$XDG_CONFIG_HOME` nor `$HOME` is set.
+pub fn post_apply_path() -> Option<PathBuf> {
+    favorites::config_dir().map(|dir| dir.join(POST_APPLY_HOOK))
+}
+
+/// Whether `path` is a regular file with any execute bit set.
+pub fn is_executable(path: &Path) -> bool {
+    path.metadata()
+        .is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+/// Run the hook at `path` with `theme` as its only argument and wait for it.
+/// Fails only when the hook cannot be started.
+pub fn run(path: &Path, theme: &str) -> Result<ExitStatus> {
+    Command::new(path)
+        .arg(theme)
+        .status()
+        .with_context(|| format!("Failed to run {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_hook(dir: &Path, script: &str, mode: u32) -> PathBuf {
+        let path = dir.join(POST_APPLY_HOOK);
+        fs::write(&path, script).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        path
+    }
+
+    #[test]
+    fn only_executable_files_count() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(!is_executable(&dir.path().join(POST_APPLY_HOOK)));
+        assert!(!is_executable(dir.path()));
+        let hook = write_hook(dir.path(), "#!/bin/sh\n", 0o644);
+        assert!(!is_executable(&hook));
+        fs::set_permissions(&hook, fs::Permissions::from_mode(0o755)).unwrap();
+        assert!(is_executable(&hook));
+    }
+
+    #[test]
+    fn run_passes_theme_and_reports_exit_status() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out");
+        let script = format!("#!/bin/sh\necho \"$1\" > '{}'\nexit 3\n", out.display());
+        let hook = write_hook(dir.path(), &script, 0o755);
+
+        let status = run(&hook, "sugar-candy").unwrap();
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(fs::read_to_string(out).unwrap(), "sugar-candy\n");
+    }
+}
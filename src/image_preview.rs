@@ -0,0 +1,119 @@
+//! Inline screenshot previews for terminals with an image protocol (Kitty,
+//! iTerm2, sixel), drawn with `ratatui-image` when the `images` feature is
+//! enabled.
+//!
+//! Support is guessed from the environment first, so terminals that clearly
+//! cannot show images are never sent the capability query.  Without the
+//! feature, or on other terminals, `detect` returns `None` and the preview
+//! pane only shows the screenshot path.
+
+use std::path::Path;
+#[cfg(feature = "images")]
+use std::path::PathBuf;
+
+use ratatui::layout::Rect;
+use ratatui::Frame;
+
+/// `$TERM_PROGRAM` values of terminals known to support an image protocol.
+const IMAGE_TERM_PROGRAMS: &[&str] = &["iTerm.app", "WezTerm", "ghostty"];
+/// Substrings of `$TERM` naming terminals known to support an image protocol.
+const IMAGE_TERMS: &[&str] = &["kitty", "ghostty", "wezterm", "foot", "mlterm"];
+
+/// Whether the environment suggests an image-capable terminal.  `var` looks
+/// up an environment variable.
+pub fn terminal_supports_images(var: impl Fn(&str) -> Option<String>) -> bool {
+    let set = |name: &str| var(name).is_some_and(|v| !v.is_empty());
+    set("KITTY_WINDOW_ID")
+        || set("KONSOLE_VERSION")
+        || var("TERM_PROGRAM").is_some_and(|p| IMAGE_TERM_PROGRAMS.contains(&p.as_str()))
+        || var("TERM").is_some_and(|t| IMAGE_TERMS.iter().any(|name| t.contains(name)))
+}
+
+/// Draws theme screenshots into the preview pane.
+pub struct ImagePreview {
+    #[cfg(feature = "images")]
+    picker: ratatui_image::picker::Picker,
+    /// The last screenshot drawn, decoded and scaled for the terminal;
+    /// `None` when it could not be decoded.  Kept so an image is only
+    /// decoded once per selection.
+    #[cfg(feature = "images")]
+    cached: Option<(PathBuf, Option<ratatui_image::protocol::StatefulProtocol>)>,
+}
+
+impl ImagePreview {
+    /// Set up image previews when the terminal supports them.  Queries the
+    /// terminal, so call it after entering raw mode and before reading
+    /// events.
+    #[cfg(feature = "images")]
+    pub fn detect() -> Option<Self> {
+        use ratatui_image::picker::{Picker, ProtocolType};
+
+        if !terminal_supports_images(|name| std::env::var(name).ok()) {
+            return None;
+        }
+        let picker = Picker::from_query_stdio().ok()?;
+        // Half-block "images" are too coarse to be worth the space
+        if picker.protocol_type() == ProtocolType::Halfblocks {
+            return None;
+        }
+        Some(Self {
+            picker,
+            cached: None,
+        })
+    }
+
+    /// Always `None`: this build has no image support.
+    #[cfg(not(feature = "images"))]
+    pub fn detect() -> Option<Self> {
+        None
+    }
+
+    /// Draw the image at `path` scaled into `area`.  Draws nothing when the
+    /// file cannot be read or decoded.
+    #[cfg(feature = "images")]
+    pub fn render(&mut self, frame: &mut Frame, path: &Path, area: Rect) {
+        if self.cached.as_ref().map(|(p, _)| p.as_path()) != Some(path) {
+            let protocol = image::ImageReader::open(path)
+                .ok()
+                .and_then(|reader| reader.with_guessed_format().ok())
+                .and_then(|reader| reader.decode().ok())
+                .map(|img| self.picker.new_resize_protocol(img));
+            self.cached = Some((path.to_path_buf(), protocol));
+        }
+        if let Some((_, Some(protocol))) = self.cached.as_mut() {
+            frame.render_stateful_widget(ratatui_image::StatefulImage::default(), area, protocol);
+        }
+    }
+
+    /// Never called: `detect` returns `None` in this build.
+    #[cfg(not(feature = "images"))]
+    pub fn render(&mut self, _frame: &mut Frame, _path: &Path, _area: Rect) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn supports(vars: &[(&str, &str)]) -> bool {
+        let env: HashMap<&str, &str> = vars.iter().copied().collect();
+        terminal_supports_images(|name| env.get(name).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn recognises_image_capable_terminals() {
+        assert!(supports(&[("KITTY_WINDOW_ID", "1")]));
+        assert!(supports(&[("TERM", "xterm-kitty")]));
+        assert!(supports(&[("TERM_PROGRAM", "iTerm.app"), ("TERM", "xterm-256color")]));
+        assert!(supports(&[("TERM", "foot")]));
+        assert!(supports(&[("KONSOLE_VERSION", "230804")]));
+    }
+
+    #[test]
+    fn plain_terminals_get_no_images() {
+        assert!(!supports(&[]));
+        assert!(!supports(&[("TERM", "xterm-256color")]));
+        assert!(!supports(&[("TERM", "linux"), ("KITTY_WINDOW_ID", "")]));
+        assert!(!supports(&[("TERM_PROGRAM", "Apple_Terminal")]));
+    }
+}
@@ -0,0 +1,261 @@
+//! Installing a downloaded theme (`i` in the TUI): copy a theme directory,
+//! or unpack a `.tar.gz` / `.tgz` archive, into the user themes directory.
+//!
+//! The source must look like an SDDM theme, i.e. contain a
+//! `metadata.desktop` or a `Main.qml`.  An archive may hold the theme at its
+//! top level or, as most downloads do, inside a single directory; the theme
+//! is then named after that directory (or after the archive).
+//!
+//! Unpacking archives needs the `tar` feature; without it, importing an
+//! archive fails with an explanatory error, like the clipboard does.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::is_safe_theme_name;
+
+/// Files of which at least one marks a directory as a theme.
+const THEME_MARKERS: [&str; 2] = ["metadata.desktop", "Main.qml"];
+
+/// Archive extensions `import` unpacks.
+const ARCHIVE_SUFFIXES: [&str; 2] = [".tar.gz", ".tgz"];
+
+/// Install the theme at `source` (a directory or an archive) under `root`
+/// and return its new directory.  `root` is created if needed; an existing
+/// theme of the same name is never overwritten.
+pub fn import(source: &Path, root: &Path) -> Result<PathBuf> {
+    if source.is_dir() {
+        if !is_theme_dir(source) {
+            bail!("{} has no metadata.desktop or Main.qml", source.display());
+        }
+        let name = source
+            .canonicalize()
+            .ok()
+            .and_then(|dir| dir.file_name().map(|n| n.to_string_lossy().into_owned()))
+            .with_context(|| format!("{} has no directory name", source.display()))?;
+        let dest = destination(root, &name)?;
+        create_root(root)?;
+        let copied = copy_dir(source, &dest);
+        if copied.is_err() {
+            let _ = fs::remove_dir_all(&dest);
+        }
+        return copied.map(|()| dest);
+    }
+
+    let file_name = source
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let Some(stem) = ARCHIVE_SUFFIXES
+        .iter()
+        .find_map(|suffix| file_name.strip_suffix(suffix))
+    else {
+        if !source.exists() {
+            bail!("{} does not exist", source.display());
+        }
+        bail!("{} is neither a directory nor a .tar.gz archive", source.display());
+    };
+
+    // Unpacked next to the themes so the final move is a rename
+    create_root(root)?;
+    let staging = root.join(format!(".themewalker-import-{}", std::process::id()));
+    let result = unpack(source, &staging).and_then(|()| {
+        let (theme, name) = find_theme(&staging, stem)?;
+        let dest = destination(root, &name)?;
+        fs::rename(&theme, &dest)
+            .with_context(|| format!("Failed to move the theme to {}", dest.display()))?;
+        Ok(dest)
+    });
+    let _ = fs::remove_dir_all(&staging);
+    result
+}
+
+/// Create the themes directory (and its parents) if it is missing.
+fn create_root(root: &Path) -> Result<()> {
+    fs::create_dir_all(root).with_context(|| format!("Failed to create {}", root.display()))
+}
+
+/// Whether `dir` holds one of `THEME_MARKERS`.
+fn is_theme_dir(dir: &Path) -> bool {
+    THEME_MARKERS.iter().any(|marker| dir.join(marker).is_file())
+}
+
+/// `root/name`, after checking the name can be stored in the config and is
+/// not taken.
+fn destination(root: &Path, name: &str) -> Result<PathBuf> {
+    if !is_safe_theme_name(name) || name.starts_with('.') {
+        bail!("{name:?} cannot be used as a theme name");
+    }
+    let dest = root.join(name);
+    if dest.exists() {
+        bail!("{} already exists; not overwriting it", dest.display());
+    }
+    Ok(dest)
+}
+
+/// The theme inside an unpacked archive and its name: the archive's top
+/// level (named `stem`, the archive's name) or its only directory.
+fn find_theme(unpacked: &Path, stem: &str) -> Result<(PathBuf, String)> {
+    if is_theme_dir(unpacked) {
+        return Ok((unpacked.to_path_buf(), stem.to_string()));
+    }
+    let entries: Vec<PathBuf> = fs::read_dir(unpacked)
+        .with_context(|| format!("Failed to read {}", unpacked.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    match entries.as_slice() {
+        [dir] if dir.is_dir() && is_theme_dir(dir) => {
+            let name = dir.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            Ok((dir.clone(), name))
+        }
+        _ => bail!("The archive does not contain a theme (no metadata.desktop or Main.qml)"),
+    }
+}
+
+/// Copy `src` to the new directory `dest`, recreating symlinks as links.
+fn copy_dir(src: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+    for entry in fs::read_dir(src).with_context(|| format!("Failed to read {}", src.display()))? {
+        let entry = entry?;
+        let (from, to) = (entry.path(), dest.join(entry.file_name()));
+        let kind = entry.file_type()?;
+        if kind.is_symlink() {
+            std::os::unix::fs::symlink(fs::read_link(&from)?, &to)
+                .with_context(|| format!("Failed to link {}", to.display()))?;
+        } else if kind.is_dir() {
+            copy_dir(&from, &to)?;
+        } else {
+            fs::copy(&from, &to).with_context(|| format!("Failed to copy {}", from.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpack the gzipped tarball `archive` into the new directory `dest`.
+/// Entries that would land outside `dest` (absolute paths, `..`) are
+/// skipped by `tar`.
+#[cfg(feature = "tar")]
+fn unpack(archive: &Path, dest: &Path) -> Result<()> {
+    let file =
+        fs::File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+    fs::create_dir(dest).with_context(|| format!("Failed to create {}", dest.display()))?;
+    tar::Archive::new(flate2::read::GzDecoder::new(file))
+        .unpack(dest)
+        .with_context(|| format!("Failed to unpack {}", archive.display()))
+}
+
+/// Always fails: this build cannot unpack archives.
+#[cfg(not(feature = "tar"))]
+fn unpack(_archive: &Path, _dest: &Path) -> Result<()> {
+    bail!("importing archives is not built in (enable the `tar` feature)")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_theme_dir(dir: &Path) {
+        fs::create_dir_all(dir.join("faces")).unwrap();
+        fs::write(dir.join("metadata.desktop"), "[SddmGreeterTheme]\nName=Maya\n").unwrap();
+        fs::write(dir.join("faces/user.png"), "png").unwrap();
+    }
+
+    #[test]
+    fn copies_a_theme_directory() {
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("download/maya");
+        make_theme_dir(&source);
+        let root = tmp.path().join("themes");
+
+        let dest = import(&source, &root).unwrap();
+        assert_eq!(dest, root.join("maya"));
+        assert_eq!(fs::read_to_string(dest.join("faces/user.png")).unwrap(), "png");
+        // The original stays, and a second import does not overwrite
+        assert!(source.join("metadata.desktop").exists());
+        let err = import(&source, &root).unwrap_err();
+        assert!(err.to_string().contains("already exists"), "{err}");
+    }
+
+    #[test]
+    fn rejects_what_is_not_a_theme() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path().join("themes");
+        let plain = tmp.path().join("photos");
+        fs::create_dir_all(&plain).unwrap();
+        fs::write(plain.join("a.png"), "png").unwrap();
+
+        let err = import(&plain, &root).unwrap_err();
+        assert!(err.to_string().contains("no metadata.desktop or Main.qml"), "{err}");
+        let err = import(&plain.join("a.png"), &root).unwrap_err();
+        assert!(err.to_string().contains("neither a directory"), "{err}");
+        assert!(import(&tmp.path().join("missing.zip"), &root).is_err());
+        assert!(!root.join("photos").exists());
+    }
+
+    #[cfg(feature = "tar")]
+    #[test]
+    fn imports_a_tarball_wrapped_in_a_directory_or_flat() {
+        use flate2::{write::GzEncoder, Compression};
+
+        let tmp = tempfile::tempdir().unwrap();
+        let source = tmp.path().join("download/maya");
+        make_theme_dir(&source);
+        let root = tmp.path().join("themes");
+        // Pack `source` under `prefix` in the archive
+        let pack = |archive: &Path, prefix: &str| {
+            let file = fs::File::create(archive).unwrap();
+            let mut builder = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+            builder.append_dir_all(prefix, &source).unwrap();
+            builder.into_inner().unwrap().finish().unwrap();
+        };
+
+        let wrapped = tmp.path().join("sugar-candy-v2.tar.gz");
+        pack(&wrapped, "sugar-candy");
+        let dest = import(&wrapped, &root).unwrap();
+        assert_eq!(dest, root.join("sugar-candy"));
+        assert_eq!(fs::read_to_string(dest.join("faces/user.png")).unwrap(), "png");
+
+        let flat = tmp.path().join("maya.tgz");
+        pack(&flat, ".");
+        let dest = import(&flat, &root).unwrap();
+        assert_eq!(dest, root.join("maya"));
+        assert!(dest.join("metadata.desktop").is_file());
+
+        // No staging directory is left behind
+        let mut names: Vec<_> =
+            fs::read_dir(&root).unwrap().map(|e| e.unwrap().file_name()).collect();
+        names.sort();
+        assert_eq!(names, ["maya", "sugar-candy"]);
+    }
+
+    #[cfg(not(feature = "tar"))]
+    #[test]
+    fn archives_need_the_tar_feature() {
+        let tmp = tempfile::tempdir().unwrap();
+        let archive = tmp.path().join("maya.tar.gz");
+        fs::write(&archive, "").unwrap();
+        let err = import(&archive, &tmp.path().join("themes")).unwrap_err();
+        assert!(err.to_string().contains("enable the `tar` feature"), "{err}");
+    }
+
+    #[test]
+    fn finds_the_theme_in_an_unpacked_archive() {
+        let tmp = tempfile::tempdir().unwrap();
+        // Wrapped in one directory, as most downloads are
+        let wrapped = tmp.path().join("wrapped");
+        make_theme_dir(&wrapped.join("sugar-candy"));
+        let (dir, name) = find_theme(&wrapped, "sugar-candy-v2").unwrap();
+        assert_eq!((dir, name.as_str()), (wrapped.join("sugar-candy"), "sugar-candy"));
+
+        // At the top level, named after the archive
+        let flat = tmp.path().join("flat");
+        make_theme_dir(&flat);
+        assert_eq!(find_theme(&flat, "maya").unwrap().1, "maya");
+
+        let empty = tmp.path().join("empty");
+        fs::create_dir_all(empty.join("docs")).unwrap();
+        assert!(find_theme(&empty, "docs").is_err());
+    }
+}
@@ -0,0 +1,142 @@
+//! JSON output for `--list --format json` and `--validate --format json`.
+//!
+//! The output is small and flat, so it is written by hand instead of pulling
+//! in a serialization framework.
+
+use crate::theme::SddmTheme;
+
+/// Render `themes` as a JSON array of objects with `name`, `path`,
+/// `description`, `author` and `active` (whether the theme equals `current`).
+/// Missing metadata fields are `null`.
+pub fn theme_list(themes: &[SddmTheme], current: Option<&str>) -> String {
+    let mut out = String::from("[");
+    for (i, theme) in themes.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        out.push_str("\n  {");
+        out.push_str(&format!("\"name\": {}, ", string(&theme.name)));
+        out.push_str(&format!(
+            "\"path\": {}, ",
+            string(&theme.path.to_string_lossy())
+        ));
+        out.push_str(&format!(
+            "\"description\": {}, ",
+            optional(theme.description())
+        ));
+        out.push_str(&format!("\"author\": {}, ", optional(theme.author())));
+        out.push_str(&format!("\"active\": {}", current.is_some_and(|c| theme.answers_to(c))));
+        out.push('}');
+    }
+    if !themes.is_empty() {
+        out.push('\n');
+    }
+    out.push_str("]\n");
+    out
+}
+
+/// Render the `--validate` result for `theme` as a JSON object with `name`,
+/// `path`, `valid` (whether `problems` is empty) and the `problems` found.
+pub fn validation_report(theme: &SddmTheme, problems: &[String]) -> String {
+    let problems: Vec<String> = problems.iter().map(|p| string(p)).collect();
+    format!(
+        "{{\"name\": {}, \"path\": {}, \"valid\": {}, \"problems\": [{}]}}\n",
+        string(&theme.name),
+        string(&theme.path.to_string_lossy()),
+        problems.is_empty(),
+        problems.join(", ")
+    )
+}
+
+/// `s` as a JSON string literal, or `null`.
+fn optional(s: Option<&str>) -> String {
+    s.map(string).unwrap_or_else(|| "null".to_string())
+}
+
+/// `s` as a quoted JSON string literal with the required escapes.
+fn string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::ThemeSource;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    fn make_theme(name: &str) -> SddmTheme {
+        SddmTheme {
+            name: name.to_string(),
+            pretty_name: None,
+            path: PathBuf::from(format!("/usr/share/sddm/themes/{name}")),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            screenshot: None,
+            background_size: None,
+            source: ThemeSource::System,
+            modified: None,
+            configurable: false,
+            colors: Vec::new(),
+            metadata_loaded: true,
+            shadowed: false,
+            aliases: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn empty_list() {
+        assert_eq!(theme_list(&[], None), "[]\n");
+    }
+
+    #[test]
+    fn lists_fields_and_active_flag() {
+        let mut breeze = make_theme("breeze");
+        breeze.metadata.insert("Description".to_string(), "KDE Breeze".to_string());
+        breeze.metadata.insert("Author".to_string(), "KDE".to_string());
+        let out = theme_list(&[breeze, make_theme("maya")], Some("maya"));
+        assert_eq!(
+            out,
+            "[\n  {\"name\": \"breeze\", \"path\": \"/usr/share/sddm/themes/breeze\", \
+             \"description\": \"KDE Breeze\", \"author\": \"KDE\", \"active\": false},\n  \
+             {\"name\": \"maya\", \"path\": \"/usr/share/sddm/themes/maya\", \
+             \"description\": null, \"author\": null, \"active\": true}\n]\n"
+        );
+    }
+
+    #[test]
+    fn reports_validation_problems() {
+        let theme = make_theme("maya");
+        assert_eq!(
+            validation_report(&theme, &[]),
+            "{\"name\": \"maya\", \"path\": \"/usr/share/sddm/themes/maya\", \
+             \"valid\": true, \"problems\": []}\n"
+        );
+        let problems = ["no Main.qml".to_string(), "background bg.png is missing".to_string()];
+        assert!(validation_report(&theme, &problems).ends_with(
+            "\"valid\": false, \"problems\": [\"no Main.qml\", \"background bg.png is missing\"]}\n"
+        ));
+    }
+
+    #[test]
+    fn escapes_strings() {
+        assert_eq!(string("a \"b\" \\ c"), r#""a \"b\" \\ c""#);
+        assert_eq!(string("line\nbreak\ttab"), r#""line\nbreak\ttab""#);
+        assert_eq!(string("\u{1}"), r#""\u0001""#);
+        assert_eq!(string("Grüße"), "\"Grüße\"");
+    }
+}
@@ -0,0 +1,58 @@
+//! Themewalker as a library: SDDM theme discovery and config patching, for
+//! tools that want the same logic without the TUI.
+//!
+//! ```no_run
+//! use themewalker::{apply_theme_to_content, discover_themes, parse_current_theme};
+//!
+//! for mut theme in discover_themes()? {
+//!     // Discovery only lists directories; read metadata.desktop on demand
+//!     theme.load_metadata();
+//!     println!("{} ({})", theme.display_name(), theme.path.display());
+//! }
+//!
+//! let content = std::fs::read_to_string("/etc/sddm.conf")?;
+//! println!("active: {:?}", parse_current_theme(&content));
+//! print!("{}", apply_theme_to_content(&content, "breeze"));
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Both functions use [`IniFormat`].  A config in another syntax can be read
+//! and written by implementing [`ConfigFormat`] and passing it to
+//! [`SddmConfig::with_format`].
+//!
+//! The interactive interface (`app`, `ui`, `palette`, `prefs`, `width`,
+//! `image_preview`) is only built with the `tui` feature, which is on by
+//! default.  Depend on the crate with `default-features = false` to leave out
+//! ratatui and crossterm.
+
+pub mod clipboard;
+pub mod config;
+pub mod diff;
+pub mod display_manager;
+pub mod favorites;
+pub mod history;
+pub mod hooks;
+pub mod import;
+pub mod json;
+pub mod scaffold;
+pub mod theme;
+pub mod user_config;
+
+#[cfg(feature = "tui")]
+pub mod app;
+#[cfg(feature = "tui")]
+pub mod image_preview;
+#[cfg(feature = "tui")]
+pub mod palette;
+#[cfg(feature = "tui")]
+pub mod prefs;
+#[cfg(feature = "tui")]
+pub mod ui;
+#[cfg(feature = "tui")]
+pub mod width;
+
+pub use config::{
+    apply_keys_to_content, apply_theme_to_content, is_safe_theme_name, parse_current_theme,
+    parse_theme_keys, ConfigFormat, IniFormat, SddmConfig,
+};
+pub use theme::{discover_themes, SddmTheme};
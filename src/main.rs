@@ -2,79 +2,433 @@
 //!
 //! # Execution flow
 //!
-//! 1. Load SDDM config (best-effort; falls back to empty state).
-//! 2. Discover installed themes under `/usr/share/sddm/themes/`.
+//! 0. Parse command-line flags (`--help`, `--version`, `--dry-run`, `--set`,
+//!    `--list`, `--format`,
+//!    `--config`, `--restore`, `--prefer-dropin`, `--run-hooks`, `--new`,
+//!    `--confirm-timeout`, `--history`, `--restart`, `--force-restart`,
+//!    `--no-altscreen`, `--validate`, `--confirm-quit`).
+//!    `--new` scaffolds a theme, `--history` prints the apply log and
+//!    `--validate` checks a theme directory; all three exit right away.
+//! 1. Load SDDM config (best-effort; falls back to empty state), from the
+//!    `--config` path when given, or Themewalker's own drop-in with
+//!    `--prefer-dropin`.
+//! 2. Discover installed themes under `/usr/share/sddm/themes/` (or the
+//!    config's `ThemeDir=`), the user's theme directory and any
+//!    `theme_dirs` from config.toml (directory names only; metadata is
+//!    loaded on a background thread once the TUI is up, and drained by the
+//!    event loop on every tick).  `--list`, `--set` and `--restore` are
+//!    handled here and exit without entering the TUI.
 //! 3. Install a panic hook that restores the terminal before printing.
-//! 4. Enter alternate-screen raw mode and run the ratatui event loop.
+//! 4. Enter alternate-screen raw mode (with mouse capture) and run the
+//!    ratatui event loop.  `--no-altscreen` draws on the normal screen
+//!    instead, leaving the last frame visible after exit.
 //! 5. On exit, restore the terminal unconditionally.
-//! 6. If the user confirmed a theme, write it to the config file
-//!    (using `sudo tee` when the current process lacks write permission).
-
-mod app;
-mod config;
-mod theme;
-mod ui;
+//!    A confirmed theme is written right away, inside the TUI, when no sudo
+//!    is needed (and none of `--dry-run`, `--run-hooks` or `--restart` is
+//!    set).
+//! 6. If the user confirmed a theme that needs sudo, back up the existing
+//!    config and write the theme to it (using `sudo tee` when the current
+//!    process lacks write permission).  With `--dry-run`, print a diff of the change instead.
+//!    Record the change in the history log.
+//!    With `--run-hooks`, then run the user's post-apply hook.
+//!    With `--restart` (or `r` in the "Applied!" popup), then restart SDDM,
+//!    unless that would end the user's own graphical session and
+//!    `--force-restart` was not given.
+//!    If the user asked to open a theme directory, hand it to `xdg-open`.
+//!    If the user asked to restore the backup, copy it over the config.
+//!    If the user confirmed deleting themes, remove their directories (with
+//!    `sudo rm -rf` for system themes).
+//!    If the user saved edited `[Theme]` keys that could not be written
+//!    inside the TUI, write them the same way (or print the diff).
+//! 7. Exit with a status scripts can test: 0 on success, `EXIT_NOT_APPLIED`
+//!    when the TUI was quit without applying anything, `EXIT_FAILURE` when
+//!    a write failed and `EXIT_DISCOVERY_FAILED` when no theme directory
+//!    could be scanned.
 
+use std::collections::HashMap;
 use std::io::{self, Stdout};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::Receiver;
+use std::time::{Duration, Instant};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use crossterm::{
-    event::{self, Event, KeyEventKind},
+    cursor::Show,
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
 
-use app::{App, ExitAction};
-use config::SddmConfig;
-use theme::discover_themes;
+use themewalker::app::{App, ExitAction, NO_WRAP_ENV};
+use themewalker::config::{Escalation, EscalationSession};
+use themewalker::image_preview::ImagePreview;
+use themewalker::palette::Palette;
+use themewalker::theme::{
+    discover_themes_in, non_directory_roots_in, remove_theme, spawn_metadata_loader, theme_roots,
+    unreadable_dirs_in, unsafe_theme_dirs_in, user_themes_dir, ThemeSource,
+};
+use themewalker::width::pad_right;
+use themewalker::display_manager::{self, detect_display_manager};
+use themewalker::{diff, favorites, history, hooks, json, prefs, scaffold, ui, user_config};
+use themewalker::{SddmConfig, SddmTheme};
+
+// ---------------------------------------------------------------------------
+// Command-line arguments
+// ---------------------------------------------------------------------------
+
+/// Output format of `--list` and `--validate`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum ListFormat {
+    /// One theme name per line, or a readable validation report.
+    #[default]
+    Plain,
+    /// JSON array with each theme's metadata and whether it is active, or
+    /// a JSON validation report.
+    Json,
+}
+
+/// Option summary printed by `--help`, ahead of the keybindings.
+const USAGE: &str = "\
+Browse installed SDDM themes and set the active one.
+
+Usage: themewalker [OPTIONS]
+
+Options:
+  --set <THEME>            Apply a theme without starting the TUI
+  --list                   Print installed theme names and exit
+  --format <plain|json>    Output format of --list and --validate
+  --config <PATH>          Read and write this config file
+  --prefer-dropin          Write to /etc/sddm.conf.d/zz-themewalker.conf
+  --dry-run                Print the config diff instead of writing it
+  --restore                Restore the config from its backup and exit
+  --run-hooks              Run ~/.config/themewalker/post-apply.sh after applying
+  --restart                Restart SDDM after applying
+  --force-restart          Restart SDDM even from a graphical session
+  --confirm-timeout <SECS> Cancel the confirmation dialog after SECS idle seconds
+  --confirm-quit           Ask before quitting with an unapplied theme highlighted
+  --history                Print the log of applied themes and exit
+  --new <NAME>             Scaffold a new theme in ~/.local/share/sddm/themes
+  --validate <PATH>        Check the theme in PATH and exit (1 if it has problems)
+  --no-altscreen           Draw the TUI on the normal screen
+  --table                  Start with the theme list drawn as a table
+  --escalate <sudo|doas>   Get root with this tool (default: sudo, else doas)
+  -h, --help               Print this help and exit
+  -V, --version            Print the version and exit
+";
+
+/// Exit status when a write (or any other requested action) failed.
+const EXIT_FAILURE: i32 = 1;
+/// Exit status when the theme directories could not be scanned.
+const EXIT_DISCOVERY_FAILED: i32 = 2;
+/// Exit status when the TUI was quit without applying a theme.
+const EXIT_NOT_APPLIED: i32 = 10;
+
+/// Parsed command-line flags.
+#[derive(Debug, Default)]
+struct Args {
+    /// Print the config diff instead of writing it.
+    dry_run: bool,
+    /// Apply this theme without starting the TUI.
+    set: Option<String>,
+    /// Print installed theme names and exit.
+    list: bool,
+    /// How `--list` prints themes and `--validate` its report.
+    format: ListFormat,
+    /// Use this config file instead of resolving one under /etc.
+    config: Option<PathBuf>,
+    /// Restore the config from its `.themewalker.bak` backup and exit.
+    restore: bool,
+    /// Write to `/etc/sddm.conf.d/zz-themewalker.conf` even when another
+    /// config file already sets the theme.
+    prefer_dropin: bool,
+    /// Run `~/.config/themewalker/post-apply.sh` after applying a theme.
+    run_hooks: bool,
+    /// Scaffold a new theme with this name in the user themes directory.
+    new: Option<String>,
+    /// Cancel the confirmation dialog after this long without a key press.
+    confirm_timeout: Option<Duration>,
+    /// Ask before quitting while a theme other than the active one is
+    /// highlighted.
+    confirm_quit: bool,
+    /// Print the log of applied themes and exit.
+    history: bool,
+    /// Restart SDDM after applying a theme.
+    restart: bool,
+    /// Restart SDDM even from inside a graphical session it would end.
+    force_restart: bool,
+    /// Draw the TUI on the normal screen instead of the alternate one.
+    no_altscreen: bool,
+    /// Get root with this tool instead of the detected one.
+    escalate: Option<Escalation>,
+    /// Start with the theme list drawn as a table.
+    table: bool,
+    /// Check the theme directory at this path and exit.
+    validate: Option<PathBuf>,
+}
+
+/// What the command line asks for.
+enum Invocation {
+    /// Print usage and keybindings (`--help`).
+    Help,
+    /// Print the version (`--version`).
+    Version,
+    /// Run with these flags.
+    Run(Args),
+}
+
+impl Args {
+    /// Parse the command line.  `--help` and `--version` win over any
+    /// flags after them, so they work even on an otherwise bad command line.
+    fn parse() -> Result<Invocation> {
+        let mut args = Args::default();
+        let mut argv = std::env::args().skip(1);
+        while let Some(arg) = argv.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Invocation::Help),
+                "-V" | "--version" => return Ok(Invocation::Version),
+                "--dry-run" => args.dry_run = true,
+                "--list" => args.list = true,
+                "--restore" => args.restore = true,
+                "--prefer-dropin" => args.prefer_dropin = true,
+                "--run-hooks" => args.run_hooks = true,
+                "--history" => args.history = true,
+                "--restart" => args.restart = true,
+                "--no-altscreen" => args.no_altscreen = true,
+                "--table" => args.table = true,
+                "--confirm-quit" => args.confirm_quit = true,
+                "--force-restart" => {
+                    args.restart = true;
+                    args.force_restart = true;
+                }
+                "--set" => {
+                    args.set = Some(argv.next().context("--set requires a theme name")?);
+                }
+                "--confirm-timeout" => {
+                    let secs = argv.next().context("--confirm-timeout requires seconds")?;
+                    let secs: u64 = match secs.parse() {
+                        Ok(secs) if secs > 0 => secs,
+                        _ => bail!("--confirm-timeout expects a positive number of seconds"),
+                    };
+                    args.confirm_timeout = Some(Duration::from_secs(secs));
+                }
+                "--new" => {
+                    args.new = Some(argv.next().context("--new requires a theme name")?);
+                }
+                "--format" => {
+                    args.format = match argv.next().as_deref() {
+                        Some("plain") => ListFormat::Plain,
+                        Some("json") => ListFormat::Json,
+                        Some(other) => bail!("Unknown --format '{other}' (expected plain or json)"),
+                        None => bail!("--format requires plain or json"),
+                    };
+                }
+                "--validate" => {
+                    let path = argv.next().context("--validate requires a theme directory")?;
+                    args.validate = Some(PathBuf::from(path));
+                }
+                "--config" => {
+                    let path = argv.next().context("--config requires a path")?;
+                    args.config = Some(PathBuf::from(path));
+                }
+                "--escalate" => {
+                    let name = argv.next().context("--escalate requires sudo or doas")?;
+                    match Escalation::from_name(&name) {
+                        Some(escalation) => args.escalate = Some(escalation),
+                        None => bail!("Unknown --escalate '{name}' (expected sudo or doas)"),
+                    }
+                }
+                other => bail!("Unknown argument: {other} (see --help)"),
+            }
+        }
+        if args.format != ListFormat::Plain && !args.list && args.validate.is_none() {
+            bail!("--format only applies to --list and --validate");
+        }
+        if args.prefer_dropin && args.config.is_some() {
+            bail!("--prefer-dropin cannot be combined with --config");
+        }
+        Ok(Invocation::Run(args))
+    }
+
+    /// The tool that gets root: `--escalate`, else whichever is installed.
+    fn escalation(&self) -> Option<Escalation> {
+        self.escalate.or_else(Escalation::detect)
+    }
+}
+
+/// `USAGE` followed by every TUI keybinding.
+fn help_text() -> String {
+    let mut text = USAGE.to_string();
+    for (section, keys) in ui::HELP_SECTIONS {
+        text.push_str(&format!("\n{section} keys:\n"));
+        for (key, action) in keys.iter() {
+            text.push_str(&format!("  {} {action}\n", pad_right(key, 24)));
+        }
+    }
+    text
+}
 
 // ---------------------------------------------------------------------------
 // Main
 // ---------------------------------------------------------------------------
 
 fn main() -> Result<()> {
+    // Before anything touches the terminal
+    let args = match Args::parse()? {
+        Invocation::Help => {
+            print!("{}", help_text());
+            return Ok(());
+        }
+        Invocation::Version => {
+            println!("themewalker {}", env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Invocation::Run(args) => args,
+    };
+
+    if let Some(name) = args.new.as_deref() {
+        new_theme(name);
+        return Ok(());
+    }
+    if args.history {
+        print_history();
+        return Ok(());
+    }
+    if let Some(path) = args.validate.as_deref() {
+        validate_theme(path, args.format);
+        return Ok(());
+    }
+
     // ------------------------------------------------------------------
     // 1. Load config (non-fatal: fall back to empty)
     // ------------------------------------------------------------------
-    let config = match SddmConfig::load() {
+    let config = match load_config(&args) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Warning: could not read SDDM config ({e}); starting with empty state.");
-            SddmConfig::empty()
+            let mut config = SddmConfig::empty();
+            config.escalation = args.escalation();
+            config
         }
     };
 
     // ------------------------------------------------------------------
     // 2. Discover themes
     // ------------------------------------------------------------------
-    let themes = discover_themes().context("Failed to scan theme directory")?;
+    let roots = configured_theme_roots(&config);
+    let mut themes = match discover_themes_in(&roots) {
+        Ok(themes) => themes,
+        Err(e) => {
+            eprintln!("Error: failed to scan theme directory ({e}).");
+            std::process::exit(EXIT_DISCOVERY_FAILED);
+        }
+    };
+    let unsafe_dirs = unsafe_theme_dirs_in(&roots);
+    for dir in &unsafe_dirs {
+        eprintln!(
+            "Warning: skipping {dir:?}: its name contains a control character or square bracket."
+        );
+    }
+
+    // ------------------------------------------------------------------
+    // 2b. Non-interactive modes (never touch the terminal)
+    // ------------------------------------------------------------------
+    if args.list {
+        // Only the copies SDDM can load
+        themes.retain(|t| !t.shadowed);
+        match args.format {
+            ListFormat::Plain => {
+                for theme in &themes {
+                    println!("{}", theme.name);
+                }
+            }
+            ListFormat::Json => {
+                themes.iter_mut().for_each(SddmTheme::load_metadata);
+                let (active, _) = config.effective_current();
+                print!("{}", json::theme_list(&themes, active.as_deref()));
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(name) = args.set.as_deref() {
+        if !themes.iter().any(|t| t.answers_to(name)) {
+            eprintln!("Error: theme '{name}' is not installed (see `themewalker --list`).");
+            std::process::exit(EXIT_FAILURE);
+        }
+        apply_theme(&config, name, &args);
+        return Ok(());
+    }
+
+    if args.restore {
+        restore_backup(&config, args.dry_run);
+        return Ok(());
+    }
 
     // ------------------------------------------------------------------
     // 3. Build app state
     // ------------------------------------------------------------------
+    let mut metadata = spawn_metadata_loader(&themes);
     let mut app = App::new(themes, config);
+    // Hooks and restarts use the terminal, so with --run-hooks or --restart
+    // every apply happens after the TUI has exited
+    app.apply_in_tui = !args.dry_run && !args.run_hooks && !args.restart;
+    app.confirm_timeout = args.confirm_timeout;
+    app.confirm_quit = args.confirm_quit;
+    if let Some(path) = prefs::default_path() {
+        app.set_prefs(prefs::load(&path), Some(path));
+    }
+    // Override the saved settings for this session
+    if std::env::var_os(NO_WRAP_ENV).filter(|v| !v.is_empty()).is_some() {
+        app.override_wrap_navigation(false);
+    }
+    if args.table {
+        app.override_table(true);
+    }
+    app.report_scanned_roots(&roots);
+    app.report_unreadable_dirs(&unreadable_dirs_in(&roots));
+    app.report_non_directory_roots(&non_directory_roots_in(&roots));
+    app.report_unsafe_theme_dirs(&unsafe_dirs);
+    if let Some(path) = favorites::default_path() {
+        app.set_favorites(favorites::load(&path), Some(path));
+    }
+    app.history_path = history::default_path();
+    app.import_dir = user_themes_dir();
+    app.display_manager = detect_display_manager();
 
     // ------------------------------------------------------------------
     // 4. Panic hook – restore terminal so the panic message is readable
     // ------------------------------------------------------------------
+    let alt_screen = !args.no_altscreen;
     let original_hook = std::panic::take_hook();
     std::panic::set_hook(Box::new(move |info| {
-        let _ = restore_terminal_raw();
+        let _ = restore_terminal_raw(alt_screen);
         original_hook(info);
     }));
 
     // ------------------------------------------------------------------
     // 5. Enter the TUI
     // ------------------------------------------------------------------
-    let mut terminal = enter_terminal()?;
-    let result = run_event_loop(&mut terminal, &mut app);
+    let mut terminal = enter_terminal(alt_screen)?;
+    // Queries the terminal, so only once raw mode is on
+    app.image_preview = ImagePreview::detect();
+    let colors = user_config::default_path()
+        .map(|path| user_config::load(&path).colors)
+        .unwrap_or_default();
+    let palette = Palette::from_config(&colors);
+    let result = run_event_loop(
+        &mut terminal,
+        &mut app,
+        &palette,
+        &mut metadata,
+        &args,
+    );
 
     // ------------------------------------------------------------------
     // 6. Restore terminal (always – even on error)
     // ------------------------------------------------------------------
-    let restore_err = restore_terminal(&mut terminal);
+    let restore_err = restore_terminal(&mut terminal, alt_screen);
 
     // Propagate event-loop error before restore error
     let action = result?;
@@ -84,49 +438,436 @@ fn main() -> Result<()> {
     // 7. Apply selected theme (post-TUI, in normal terminal mode)
     // ------------------------------------------------------------------
     match action {
+        ExitAction::Quit if !app.applied => std::process::exit(EXIT_NOT_APPLIED),
         ExitAction::Quit => {}
-        ExitAction::ApplyTheme(ref name) => {
-            println!("Applying theme '{name}'…");
-            println!("Config path: {}", app.config.path.display());
-            match app.config.write_theme(name) {
-                Ok(()) => {
-                    println!("Done.  Restart SDDM (or log out) for the change to take effect.");
-                }
-                Err(e) => {
-                    eprintln!("Error: {e}");
-                    std::process::exit(1);
-                }
+        ExitAction::ApplyTheme(ref name) => apply_theme(&app.config, name, &args),
+        ExitAction::OpenDir(ref dir) => open_dir(dir),
+        ExitAction::RestoreBackup => restore_backup(&app.config, args.dry_run),
+        ExitAction::RestartDisplayManager => restart_sddm(&args),
+        ExitAction::DeleteThemes(ref themes) => delete_themes(themes, &args),
+        ExitAction::WriteThemeKeys(ref keys) => write_theme_keys(&app.config, keys, &args),
+    }
+
+    Ok(())
+}
+
+/// The theme roots to scan: the defaults, with the system directory moved
+/// by the SDDM config's `ThemeDir=`, plus the `theme_dirs` of Themewalker's
+/// config.toml, re-read on every call so a reload picks up edits.
+fn configured_theme_roots(config: &SddmConfig) -> Vec<(PathBuf, ThemeSource)> {
+    let extra = user_config::default_path()
+        .map(|path| user_config::load(&path).theme_dirs)
+        .unwrap_or_default();
+    theme_roots(config.theme_dir().as_deref(), &extra)
+}
+
+/// Load the config from `--config`, Themewalker's drop-in
+/// (`--prefer-dropin`) or the usual locations.
+fn load_config(args: &Args) -> Result<SddmConfig> {
+    let mut config = match args.config.as_deref() {
+        Some(path) => SddmConfig::load_from(path),
+        None if args.prefer_dropin => SddmConfig::load_preferring_dropin(),
+        None => SddmConfig::load(),
+    }?;
+    config.escalation = args.escalation();
+    Ok(config)
+}
+
+// ---------------------------------------------------------------------------
+// Applying a theme
+// ---------------------------------------------------------------------------
+
+/// Write `name` to the config, or print the would-be diff under `--dry-run`.
+/// With `--run-hooks`, the post-apply hook runs after a successful write,
+/// and with `--restart` SDDM is then restarted.  Exits the process with
+/// status 1 when the write fails.
+fn apply_theme(config: &SddmConfig, name: &str, args: &Args) {
+    if args.dry_run {
+        print_dry_run(
+            config,
+            &config.preview_theme(name),
+            &format!("with '{name}'"),
+            &format!("Applying '{name}'"),
+        );
+        return;
+    }
+
+    println!("Applying theme '{name}'…");
+    println!("Config path: {}", config.path.display());
+    announce_sudo(config);
+    match config.write_theme(name) {
+        Ok(backup) => {
+            if let Some(backup) = backup {
+                println!("Previous config backed up to {}", backup.display());
+            }
+            if args.restart {
+                println!("Done.");
+            } else {
+                println!("Done.  Restart SDDM (or log out) for the change to take effect.");
+            }
+            record_history(config, name);
+            if args.run_hooks {
+                run_post_apply_hook(name);
+            }
+            if args.restart {
+                restart_sddm(args);
             }
         }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(EXIT_FAILURE);
+        }
     }
+}
 
-    Ok(())
+/// Write `[Theme]` keys edited in the TUI (`e`), or print the diff under
+/// `--dry-run`, like `apply_theme`.  Exits the process with status 1 when
+/// the write fails.
+fn write_theme_keys(config: &SddmConfig, keys: &HashMap<String, String>, args: &Args) {
+    if args.dry_run {
+        print_dry_run(
+            config,
+            &config.preview_keys(keys),
+            "with edited [Theme] keys",
+            "Saving the [Theme] keys",
+        );
+        return;
+    }
+
+    println!("Saving {} [Theme] key(s)…", keys.len());
+    println!("Config path: {}", config.path.display());
+    announce_sudo(config);
+    match config.write_keys(keys) {
+        Ok(backup) => {
+            if let Some(backup) = backup {
+                println!("Previous config backed up to {}", backup.display());
+            }
+            println!("Done.  Restart SDDM (or log out) for the change to take effect.");
+            if let Some(name) = keys.get("Current") {
+                record_history(config, name);
+            }
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// Append the switch to `name` to the history log.  A failure is only a
+/// warning: the theme has already been applied.
+fn record_history(config: &SddmConfig, name: &str) {
+    let Some(log) = history::default_path() else {
+        return;
+    };
+    let old = config.current_theme.as_deref();
+    if let Err(e) = history::log_apply(&log, old, name, &config.path) {
+        eprintln!("Warning: could not record history: {e:#}");
+    }
+}
+
+/// Print the history log for `--history`.
+fn print_history() {
+    let Some(log) = history::default_path() else {
+        eprintln!("Error: $HOME is not set, so there is no history log.");
+        std::process::exit(EXIT_FAILURE);
+    };
+    match history::read(&log) {
+        Ok(Some(content)) => print!("{content}"),
+        Ok(None) => println!("No themes applied yet ({} does not exist).", log.display()),
+        Err(e) => {
+            eprintln!("Error: {e:#}");
+            std::process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// Run the post-apply hook, if there is an executable one, and report how
+/// it went.  A failing hook does not change the exit status: the theme has
+/// already been applied.
+fn run_post_apply_hook(name: &str) {
+    let Some(hook) = hooks::post_apply_path() else {
+        println!("No config directory ($HOME is unset); no post-apply hook to run.");
+        return;
+    };
+    if !hooks::is_executable(&hook) {
+        println!("No executable post-apply hook at {}; skipped.", hook.display());
+        return;
+    }
+    println!("Running post-apply hook {}…", hook.display());
+    match hooks::run(&hook, name) {
+        Ok(status) => match status.code() {
+            Some(code) => println!("Post-apply hook exited with code {code}."),
+            None => println!("Post-apply hook was terminated by a signal."),
+        },
+        Err(e) => eprintln!("Error: {e:#}"),
+    }
+}
+
+/// Restart SDDM so a new theme shows.  Restarting ends every session SDDM
+/// started, so inside a graphical session (or when the session type cannot
+/// be told) this refuses unless `--force-restart` is given.  Like a failing
+/// hook, a refused or failed restart does not change the exit status: the
+/// theme has already been applied.
+fn restart_sddm(args: &Args) {
+    let force = args.force_restart;
+    let enabled = detect_display_manager();
+    let Some(unit) = display_manager::restart_unit(enabled.as_deref()) else {
+        let dm = enabled.as_deref().unwrap_or_default();
+        eprintln!("Not restarting: {dm} is the enabled display manager, not SDDM.");
+        return;
+    };
+    let session = display_manager::session_type();
+    let text_session = session
+        .as_deref()
+        .is_some_and(|kind| !display_manager::is_graphical(kind));
+    if !text_session && !force {
+        match session.as_deref() {
+            Some(kind) => eprintln!("Not restarting: this is a graphical ({kind}) session."),
+            None => eprintln!("Not restarting: cannot tell whether this is a graphical session."),
+        }
+        eprintln!("Restarting SDDM would log you out and close every open application.");
+        eprintln!("Run with --force-restart to restart anyway, or log out to see the theme.");
+        return;
+    }
+
+    println!("Warning: restarting SDDM ends every session it started.");
+    if !text_session {
+        println!("Warning: --force-restart given; this will log you out now.");
+    }
+    let Some(escalation) = args.escalation() else {
+        eprintln!("Not restarting: neither sudo nor doas is installed.");
+        eprintln!("Run `systemctl restart {unit}` as root to see the theme.");
+        return;
+    };
+    let tool = escalation.program();
+    println!("Restarting SDDM ({tool} systemctl restart {unit})…");
+    match display_manager::restart(unit, escalation) {
+        Ok(status) if status.success() => println!("SDDM restarted."),
+        Ok(status) => match status.code() {
+            Some(code) => eprintln!("Error: systemctl exited with code {code}."),
+            None => eprintln!("Error: systemctl was terminated by a signal."),
+        },
+        Err(e) => eprintln!("Error: {e:#}"),
+    }
+}
+
+/// Scaffold the theme `name` in the user themes directory and print where.
+/// Exits the process with status 1 when it cannot be created.
+fn new_theme(name: &str) {
+    let Some(root) = user_themes_dir() else {
+        eprintln!("Error: $HOME is not set, so there is no user themes directory.");
+        std::process::exit(EXIT_FAILURE);
+    };
+    let author = std::env::var("USER").ok();
+    match scaffold::create(&root, name, author.as_deref()) {
+        Ok(dir) => {
+            println!("Created theme '{name}' at {}", dir.display());
+            println!("Edit metadata.desktop and Main.qml, then select it in themewalker.");
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// Check the theme directory `path` the way the confirmation dialog does
+/// and print what is wrong with it.  Exits the process with status 1 when
+/// the theme has problems or `path` is not a theme directory.
+fn validate_theme(path: &Path, format: ListFormat) {
+    // Canonical, so `.` and trailing slashes still give the theme a name
+    let theme = path
+        .canonicalize()
+        .ok()
+        .and_then(|dir| SddmTheme::from_dir(dir, ThemeSource::User));
+    let Some(theme) = theme else {
+        eprintln!("Error: {} is not a theme directory.", path.display());
+        std::process::exit(EXIT_FAILURE);
+    };
+    let problems = theme.validate().err().unwrap_or_default();
+    match format {
+        ListFormat::Plain if problems.is_empty() => {
+            println!("{}: OK", theme.path.display());
+        }
+        ListFormat::Plain => {
+            println!("{}: {} problem(s)", theme.path.display(), problems.len());
+            for problem in &problems {
+                println!("  - {problem}");
+            }
+        }
+        ListFormat::Json => print!("{}", json::validation_report(&theme, &problems)),
+    }
+    if !problems.is_empty() {
+        std::process::exit(EXIT_FAILURE);
+    }
+}
+
+/// Copy the config backup back over the config, or print the would-be diff
+/// under `--dry-run`.  Exits the process with status 1 when there is no
+/// backup or the write fails.
+fn restore_backup(config: &SddmConfig, dry_run: bool) {
+    if dry_run {
+        match config.read_backup() {
+            Ok((content, _)) => {
+                print_dry_run(config, &content, "from backup", "Restoring the backup")
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                std::process::exit(EXIT_FAILURE);
+            }
+        }
+        return;
+    }
+
+    println!("Restoring {} from backup…", config.path.display());
+    announce_sudo(config);
+    match config.restore_backup() {
+        Ok(theme) => {
+            match theme {
+                Some(theme) => println!("Restored theme '{theme}'."),
+                None => println!("Restored config (it does not set a theme)."),
+            }
+            println!("Done.  Restart SDDM (or log out) for the change to take effect.");
+        }
+        Err(e) => {
+            eprintln!("Error: {e}");
+            std::process::exit(EXIT_FAILURE);
+        }
+    }
+}
+
+/// Delete the directories of `themes`, or just name them under
+/// `--dry-run`.  A failure is reported and the rest are still deleted.
+fn delete_themes(themes: &[SddmTheme], args: &Args) {
+    if args.dry_run {
+        for theme in themes {
+            println!("Dry run: would delete {}.", theme.path.display());
+        }
+        return;
+    }
+
+    let escalation = args.escalation();
+    let needs_root = themes.iter().any(SddmTheme::removal_needs_root);
+    if let Some(escalation) = escalation.filter(|_| needs_root) {
+        let tool = escalation.program();
+        println!("Waiting for {tool} authentication… (enter your password if prompted)");
+    }
+    // One password prompt covers every deletion
+    let session = EscalationSession::new(escalation);
+    let mut failed = false;
+    for theme in themes {
+        println!("Deleting theme '{}' ({})…", theme.name, theme.path.display());
+        if let Err(e) = remove_theme(theme, &session) {
+            eprintln!("Error: {e}");
+            failed = true;
+        }
+    }
+    if failed {
+        std::process::exit(EXIT_FAILURE);
+    }
+    println!("Deleted.");
+}
+
+/// Tell the user a sudo (or doas) password prompt may follow, so a slow
+/// prompt is not mistaken for a hang.  Without either tool the write fails
+/// with its own message instead.
+fn announce_sudo(config: &SddmConfig) {
+    if let Some(escalation) = config.escalation.filter(|_| config.needs_sudo()) {
+        let tool = escalation.program();
+        println!("Waiting for {tool} authentication… (enter your password if prompted)");
+    }
+}
+
+/// Print the diff between the current config and `new_content` for
+/// `--dry-run`.  `label` tags the new side of the diff; `change` describes
+/// the action in the summary line.
+fn print_dry_run(config: &SddmConfig, new_content: &str, label: &str, change: &str) {
+    let path = config.path.display().to_string();
+    let diff = diff::unified_diff(
+        config.raw_content(),
+        new_content,
+        &format!("{path} (current)"),
+        &format!("{path} ({label})"),
+    );
+    if diff.is_empty() {
+        println!("Dry run: {change} would not change {path}.");
+    } else {
+        println!("Dry run: no changes written.  {change} would change:\n");
+        print!("{diff}");
+    }
+}
+
+/// Open `dir` with the desktop's default file manager.  `xdg-open` is
+/// spawned detached so themewalker can exit immediately.
+fn open_dir(dir: &Path) {
+    println!("Opening {}…", dir.display());
+    let spawned = Command::new("xdg-open")
+        .arg(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    if let Err(e) = spawned {
+        eprintln!("Error: could not run `xdg-open` ({e}).");
+        std::process::exit(EXIT_FAILURE);
+    }
 }
 
 // ---------------------------------------------------------------------------
 // Terminal setup / teardown
 // ---------------------------------------------------------------------------
 
-fn enter_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
+/// Switch to raw mode with mouse capture, on the alternate screen unless
+/// `alt_screen` is false (`--no-altscreen`).
+fn enter_terminal(alt_screen: bool) -> Result<Terminal<CrosstermBackend<Stdout>>> {
     enable_raw_mode().context("Failed to enable raw mode")?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    if alt_screen {
+        execute!(stdout, EnterAlternateScreen).context("Failed to enter alternate screen")?;
+    }
+    execute!(stdout, EnableMouseCapture).context("Failed to enable mouse capture")?;
     let backend = CrosstermBackend::new(stdout);
-    Terminal::new(backend).context("Failed to create ratatui terminal")
+    let mut terminal = Terminal::new(backend).context("Failed to create ratatui terminal")?;
+    if !alt_screen {
+        // The first draw skips blank cells, which would let the shell's
+        // output show through
+        terminal.clear().context("Failed to clear the screen")?;
+    }
+    Ok(terminal)
 }
 
-fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+fn restore_terminal(
+    terminal: &mut Terminal<CrosstermBackend<Stdout>>,
+    alt_screen: bool,
+) -> Result<()> {
     disable_raw_mode().context("Failed to disable raw mode")?;
-    execute!(terminal.backend_mut(), LeaveAlternateScreen)
-        .context("Failed to leave alternate screen")?;
+    execute!(terminal.backend_mut(), DisableMouseCapture)
+        .context("Failed to disable mouse capture")?;
+    if alt_screen {
+        execute!(terminal.backend_mut(), LeaveAlternateScreen)
+            .context("Failed to leave alternate screen")?;
+    } else {
+        // Keep the last frame and continue below it
+        let height = terminal.size().context("Failed to read terminal size")?.height;
+        terminal
+            .set_cursor_position((0, height.saturating_sub(1)))
+            .context("Failed to move cursor")?;
+        println!();
+    }
     terminal.show_cursor().context("Failed to show cursor")?;
     Ok(())
 }
 
 /// Used only by the panic hook (no terminal handle available there).
-fn restore_terminal_raw() -> io::Result<()> {
+fn restore_terminal_raw(alt_screen: bool) -> io::Result<()> {
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen)?;
+    execute!(io::stdout(), DisableMouseCapture, Show)?;
+    if alt_screen {
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+    } else {
+        // The panic message starts on a fresh line instead of inside the frame
+        println!();
+    }
     Ok(())
 }
 
@@ -134,13 +875,25 @@ fn restore_terminal_raw() -> io::Result<()> {
 // Event loop
 // ---------------------------------------------------------------------------
 
-/// Render frames and dispatch key events until the user picks an action.
+/// Render frames and dispatch key and mouse events until the user picks an
+/// action.  Themes arriving on `metadata` are merged into the app before each
+/// frame; a requested reload re-scans themes and re-reads the config as
+/// `load_config` does.
 fn run_event_loop(
     terminal: &mut Terminal<CrosstermBackend<Stdout>>,
     app: &mut App,
+    palette: &Palette,
+    metadata: &mut Receiver<SddmTheme>,
+    args: &Args,
 ) -> Result<ExitAction> {
     loop {
-        terminal.draw(|f| ui::draw(f, app))?;
+        if app.reload_requested {
+            app.reload_requested = false;
+            reload(app, metadata, args);
+        }
+        app.apply_metadata(metadata.try_iter());
+        app.expire_confirmation(Instant::now());
+        terminal.draw(|f| ui::draw(f, app, palette))?;
 
         // Poll with a short timeout so we can keep re-drawing on resize, etc.
         if event::poll(Duration::from_millis(200))? {
@@ -151,7 +904,13 @@ fn run_event_loop(
                 if key.kind != KeyEventKind::Press {
                     continue;
                 }
-                if let Some(action) = app.handle_key(key.code) {
+                if let Some(action) = app.handle_key(key) {
+                    return Ok(action);
+                }
+            }
+
+            if let Event::Mouse(mouse) = ev {
+                if let Some(action) = app.handle_mouse(mouse) {
                     return Ok(action);
                 }
             }
@@ -163,3 +922,25 @@ fn run_event_loop(
         }
     }
 }
+
+/// Re-scan themes and re-read the config into `app`, restarting the
+/// background metadata loader for the new theme list.  Failures are shown
+/// in the status bar and leave `app` unchanged.
+fn reload(app: &mut App, metadata: &mut Receiver<SddmTheme>, args: &Args) {
+    let loaded = load_config(args).and_then(|config| {
+        let roots = configured_theme_roots(&config);
+        let themes = discover_themes_in(&roots).context("Failed to scan theme directory")?;
+        Ok((themes, config, roots))
+    });
+    match loaded {
+        Ok((themes, config, roots)) => {
+            *metadata = spawn_metadata_loader(&themes);
+            app.reload(themes, config);
+            app.report_scanned_roots(&roots);
+            app.report_unreadable_dirs(&unreadable_dirs_in(&roots));
+            app.report_non_directory_roots(&non_directory_roots_in(&roots));
+            app.report_unsafe_theme_dirs(&unsafe_theme_dirs_in(&roots));
+        }
+        Err(e) => app.status = Some(format!("Reload failed: {e}")),
+    }
+}
@@ -0,0 +1,203 @@
+//! Colour palette for the TUI.
+//!
+//! The palette is chosen once at startup and passed into every draw function.
+//! When the `NO_COLOR` environment variable is set (see <https://no-color.org>)
+//! a monochrome palette is used instead, which distinguishes the highlight and
+//! active states with reverse video, bold and underline.  Otherwise the
+//! `[colors]` table of config.toml can override a few of the colours.
+
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::user_config::ColorOverrides;
+
+/// Colour names accepted in config.toml, as ratatui spells them once
+/// lowercased and stripped of `-`, `_` and spaces.
+const COLOR_NAMES: [(&str, Color); 19] = [
+    ("reset", Color::Reset),
+    ("black", Color::Black),
+    ("red", Color::Red),
+    ("green", Color::Green),
+    ("yellow", Color::Yellow),
+    ("blue", Color::Blue),
+    ("magenta", Color::Magenta),
+    ("cyan", Color::Cyan),
+    ("gray", Color::Gray),
+    ("grey", Color::Gray),
+    ("darkgray", Color::DarkGray),
+    ("darkgrey", Color::DarkGray),
+    ("lightred", Color::LightRed),
+    ("lightgreen", Color::LightGreen),
+    ("lightyellow", Color::LightYellow),
+    ("lightblue", Color::LightBlue),
+    ("lightmagenta", Color::LightMagenta),
+    ("lightcyan", Color::LightCyan),
+    ("white", Color::White),
+];
+
+/// Base styles used by the draw functions.  Call sites may add modifiers
+/// (e.g. `BOLD`) on top.
+#[derive(Debug, Clone, Copy)]
+pub struct Palette {
+    /// Selected row in the theme list.
+    pub highlight: Style,
+    /// `[active]` badge and the current theme in the header.
+    pub active: Style,
+    /// Header title.
+    pub title: Style,
+    /// Key hints and field labels.
+    pub key: Style,
+    /// Confirmation popup border and title.
+    pub popup_border: Style,
+    /// Theme name in the confirmation popup; "writing directly" note.
+    pub confirm: Style,
+    /// Errors and warnings.
+    pub warning: Style,
+    /// Advice that is not an error (e.g. an oversized background image).
+    pub caution: Style,
+    /// Secondary text (config path, tags, hints).
+    pub dim: Style,
+    /// Whether a theme's own colours are drawn as swatches; the monochrome
+    /// palette lists their hex values only.
+    pub swatches: bool,
+    /// Whether theme names in the list get a colour derived from the name.
+    pub name_colours: bool,
+}
+
+impl Palette {
+    /// The default colour palette.
+    pub fn colour() -> Self {
+        Self {
+            highlight: Style::default().bg(Color::Blue).fg(Color::White),
+            active: Style::default().fg(Color::Green),
+            title: Style::default().fg(Color::Cyan),
+            key: Style::default().fg(Color::Yellow),
+            popup_border: Style::default().fg(Color::LightYellow),
+            confirm: Style::default().fg(Color::LightGreen),
+            warning: Style::default().fg(Color::Red),
+            caution: Style::default().fg(Color::Yellow),
+            dim: Style::default().fg(Color::DarkGray),
+            swatches: true,
+            name_colours: true,
+        }
+    }
+
+    /// Colour-free palette relying only on text modifiers.
+    pub fn monochrome() -> Self {
+        Self {
+            highlight: Style::default().add_modifier(Modifier::REVERSED),
+            active: Style::default().add_modifier(Modifier::UNDERLINED),
+            title: Style::default(),
+            key: Style::default().add_modifier(Modifier::BOLD),
+            popup_border: Style::default(),
+            confirm: Style::default().add_modifier(Modifier::UNDERLINED),
+            warning: Style::default().add_modifier(Modifier::UNDERLINED),
+            caution: Style::default().add_modifier(Modifier::ITALIC),
+            dim: Style::default().add_modifier(Modifier::DIM),
+            swatches: false,
+            name_colours: false,
+        }
+    }
+
+    /// Monochrome when `NO_COLOR` is set to a non-empty value, colour otherwise.
+    pub fn from_env() -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(v) if !v.is_empty() => Self::monochrome(),
+            _ => Self::colour(),
+        }
+    }
+
+    /// `from_env` with the `[colors]` overrides of config.toml applied to the
+    /// colour palette.  `NO_COLOR` still wins over them.
+    pub fn from_config(overrides: &ColorOverrides) -> Self {
+        match std::env::var_os("NO_COLOR") {
+            Some(v) if !v.is_empty() => Self::monochrome(),
+            _ => Self::colour().with_overrides(overrides),
+        }
+    }
+
+    /// This palette with each override that parses (see [`parse_color`])
+    /// replacing the colour it names; the rest are left as they are.
+    pub fn with_overrides(mut self, overrides: &ColorOverrides) -> Self {
+        let color = |value: &Option<String>| value.as_deref().and_then(parse_color);
+        if let Some(c) = color(&overrides.highlight_bg) {
+            self.highlight = self.highlight.bg(c);
+        }
+        if let Some(c) = color(&overrides.highlight_fg) {
+            self.highlight = self.highlight.fg(c);
+        }
+        if let Some(c) = color(&overrides.active) {
+            self.active = self.active.fg(c);
+        }
+        if let Some(c) = color(&overrides.popup_border) {
+            self.popup_border = self.popup_border.fg(c);
+        }
+        self
+    }
+}
+
+/// Parse a colour as written in config.toml: a name such as `blue` or
+/// `light-cyan`, `#rrggbb` or `#rgb`, or `indexed:N` for entry `N` of the
+/// terminal's 256-colour palette.  `None` for anything else.
+pub fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if let Some(index) = value.strip_prefix("indexed:") {
+        return index.trim().parse().ok().map(Color::Indexed);
+    }
+    if let Some(hex) = value.strip_prefix('#') {
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let byte = |s: &str| u8::from_str_radix(s, 16).ok();
+        return match hex.len() {
+            3 => {
+                let digit = |i: usize| byte(&hex[i..i + 1]).map(|d| d * 17);
+                Some(Color::Rgb(digit(0)?, digit(1)?, digit(2)?))
+            }
+            6 => Some(Color::Rgb(byte(&hex[0..2])?, byte(&hex[2..4])?, byte(&hex[4..6])?)),
+            _ => None,
+        };
+    }
+    let name: String = value
+        .chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .collect::<String>()
+        .to_lowercase();
+    COLOR_NAMES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, color)| *color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_names_hex_and_indexed() {
+        assert_eq!(parse_color("blue"), Some(Color::Blue));
+        assert_eq!(parse_color("Light-Cyan"), Some(Color::LightCyan));
+        assert_eq!(parse_color("dark_grey"), Some(Color::DarkGray));
+        assert_eq!(parse_color("#5f87af"), Some(Color::Rgb(0x5f, 0x87, 0xaf)));
+        assert_eq!(parse_color("#fff"), Some(Color::Rgb(255, 255, 255)));
+        assert_eq!(parse_color("indexed:33"), Some(Color::Indexed(33)));
+        for bad in ["", "bleu", "#12345", "#gggggg", "indexed:256", "indexed:"] {
+            assert_eq!(parse_color(bad), None, "{bad:?}");
+        }
+    }
+
+    #[test]
+    fn overrides_replace_only_what_parses() {
+        let overrides = ColorOverrides {
+            highlight_bg: Some("#5f87af".to_string()),
+            highlight_fg: Some("not a colour".to_string()),
+            active: Some("indexed:33".to_string()),
+            popup_border: None,
+        };
+        let default = Palette::colour();
+        let pal = Palette::colour().with_overrides(&overrides);
+        assert_eq!(pal.highlight, default.highlight.bg(Color::Rgb(0x5f, 0x87, 0xaf)));
+        assert_eq!(pal.active, Style::default().fg(Color::Indexed(33)));
+        assert_eq!(pal.popup_border, default.popup_border);
+        assert_eq!(pal.dim, default.dim);
+    }
+}
@@ -0,0 +1,151 @@
+//! Display settings remembered between sessions.
+//!
+//! The file is `$XDG_CONFIG_HOME/themewalker/prefs.toml` (falling back to
+//! `~/.config`), next to the favorites, and is rewritten whenever one of the
+//! settings changes:
+//!
+//! ```toml
+//! sort = "author"
+//! dense = true
+//! wrap = false
+//! table = false
+//! tag = "Plasma"
+//! incomplete = false
+//! ```
+//!
+//! Missing keys keep their defaults and unknown ones are ignored, but a file
+//! that is not valid TOML, or has a value of the wrong type, is ignored as a
+//! whole.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::app::SortKey;
+use crate::favorites::config_dir;
+
+/// File name inside the themewalker config directory.
+const PREFS_FILE: &str = "prefs.toml";
+
+/// Settings restored at startup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Prefs {
+    /// Order of the theme list (`s`).
+    #[serde(rename = "sort")]
+    pub sort_key: SortKey,
+    /// List theme names only (`d`).
+    pub dense: bool,
+    /// Wrap around at the list ends (`w`).
+    #[serde(rename = "wrap")]
+    pub wrap_navigation: bool,
+    /// Draw the list as a Name | Author | Description table (`Tab`).
+    pub table: bool,
+    /// Show only themes with this tag (`t`).
+    #[serde(rename = "tag", skip_serializing_if = "Option::is_none")]
+    pub tag_filter: Option<String>,
+    /// Show only themes with incomplete metadata (`m`).
+    #[serde(rename = "incomplete")]
+    pub incomplete_only: bool,
+}
+
+impl Default for Prefs {
+    fn default() -> Self {
+        Self {
+            sort_key: SortKey::Name,
+            dense: false,
+            wrap_navigation: true,
+            table: false,
+            tag_filter: None,
+            incomplete_only: false,
+        }
+    }
+}
+
+/// Where the settings are stored, or `None` when there is no config
+/// directory.
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(PREFS_FILE))
+}
+
+/// Read the settings from `path`.  A missing or malformed file yields the
+/// defaults rather than an error.
+pub fn load(path: &Path) -> Prefs {
+    fs::read_to_string(path)
+        .map(|content| parse(&content))
+        .unwrap_or_default()
+}
+
+/// Write `prefs` to `path`, creating its directory if needed.
+pub fn save(path: &Path, prefs: &Prefs) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let content = toml::to_string(prefs).context("Failed to serialize preferences")?;
+    fs::write(path, content)
+        .with_context(|| format!("Failed to write preferences to {}", path.display()))
+}
+
+/// Read the settings, falling back to the defaults when `content` does not
+/// parse.
+fn parse(content: &str) -> Prefs {
+    toml::from_str(content).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_every_key() {
+        let prefs = parse(
+            "# themewalker\nsort = \"modified\"\ndense = true # small screen\nwrap=false\n\
+             table = true\ntag = \"Plasma\"\nincomplete = true\n",
+        );
+        assert_eq!(
+            prefs,
+            Prefs {
+                sort_key: SortKey::Modified,
+                dense: true,
+                wrap_navigation: false,
+                table: true,
+                tag_filter: Some("Plasma".to_string()),
+                incomplete_only: true,
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_files_keep_defaults() {
+        assert_eq!(parse(""), Prefs::default());
+        assert_eq!(parse("sort = \"size\"\ndense = true\n"), Prefs::default());
+        assert_eq!(parse("sort = author\ndense = true\n"), Prefs::default());
+        // Missing keys keep their defaults; unknown ones are ignored
+        let prefs = parse("dense = true\ncolumns = 3\n");
+        assert!(prefs.dense);
+        assert!(prefs.wrap_navigation);
+    }
+
+    #[test]
+    fn save_round_trips_and_creates_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("themewalker").join(PREFS_FILE);
+        assert_eq!(load(&path), Prefs::default());
+
+        let prefs = Prefs {
+            sort_key: SortKey::Author,
+            dense: true,
+            wrap_navigation: false,
+            table: true,
+            tag_filter: Some("Qt6".to_string()),
+            incomplete_only: false,
+        };
+        save(&path, &prefs).unwrap();
+        assert_eq!(load(&path), prefs);
+        save(&path, &Prefs::default()).unwrap();
+        assert_eq!(load(&path), Prefs::default());
+    }
+}
@@ -0,0 +1,107 @@
+//! Theme scaffolding for `--new`: a starter theme directory that Themewalker
+//! (and SDDM) recognise, for theme authors to fill in.
+//!
+//! The generated `metadata.desktop` sets the keys `SddmTheme` is read from
+//! (`Name`, `Description`, `Author`, `MainScript`), plus `ConfigFile` for
+//! the generated `theme.conf`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+
+use crate::config::check_theme_name;
+
+/// QML entry point written by the scaffold.
+const MAIN_SCRIPT: &str = "Main.qml";
+/// Options file written by the scaffold.
+const CONFIG_FILE: &str = "theme.conf";
+
+/// Create the theme `name` under `root` and return its directory.  Refuses
+/// to touch an existing directory; `root` is created if needed.  `name`
+/// must be writable as `Current=` (see `check_theme_name`) and a single
+/// path component that discovery does not skip as hidden.
+pub fn create(root: &Path, name: &str, author: Option<&str>) -> Result<PathBuf> {
+    check_theme_name(name)?;
+    if name.contains('/') || name.starts_with('.') {
+        bail!("Theme name {name:?} must not contain '/' or start with '.'");
+    }
+    let dir = root.join(name);
+    if dir.exists() {
+        bail!("{} already exists; not overwriting it", dir.display());
+    }
+    fs::create_dir_all(root).with_context(|| format!("Failed to create {}", root.display()))?;
+    // create_dir (not _all) fails if the directory appeared in the meantime
+    fs::create_dir(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let files = [
+        ("metadata.desktop", metadata(name, author)),
+        (MAIN_SCRIPT, String::new()),
+        (CONFIG_FILE, "[General]\n".to_string()),
+    ];
+    for (file, content) in files {
+        let path = dir.join(file);
+        fs::write(&path, content)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(dir)
+}
+
+/// Starter `metadata.desktop` content.
+fn metadata(name: &str, author: Option<&str>) -> String {
+    format!(
+        "[SddmGreeterTheme]\n\
+         Name={name}\n\
+         Description=A new SDDM theme\n\
+         Author={author}\n\
+         Type=sddm-theme\n\
+         Version=0.1\n\
+         MainScript={MAIN_SCRIPT}\n\
+         ConfigFile={CONFIG_FILE}\n\
+         Theme-Id={name}\n\
+         Theme-API=2.0\n",
+        author = author.unwrap_or_default(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::{SddmTheme, ThemeSource};
+
+    #[test]
+    fn creates_a_theme_themewalker_can_read() {
+        let root = tempfile::tempdir().unwrap();
+        let themes = root.path().join("sddm/themes");
+        let dir = create(&themes, "my-theme", Some("alice")).unwrap();
+        assert_eq!(dir, themes.join("my-theme"));
+
+        let theme = SddmTheme::from_dir(dir, ThemeSource::User).unwrap();
+        assert_eq!(theme.pretty_name.as_deref(), Some("my-theme"));
+        assert_eq!(theme.description(), Some("A new SDDM theme"));
+        assert_eq!(theme.author(), Some("alice"));
+        assert!(theme.configurable);
+        assert_eq!(theme.validate(), Ok(()));
+    }
+
+    #[test]
+    fn refuses_to_overwrite_existing_directory() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("taken")).unwrap();
+        fs::write(root.path().join("taken/Main.qml"), "keep me").unwrap();
+
+        let err = create(root.path(), "taken", None).unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(fs::read_to_string(root.path().join("taken/Main.qml")).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn rejects_names_that_are_not_a_single_component() {
+        let root = tempfile::tempdir().unwrap();
+        let names = ["", ".", "..", ".hidden", "a/b", " padded", "two\nlines", "a[b]", "bell\u{7}"];
+        for name in names {
+            assert!(create(root.path(), name, None).is_err(), "{name:?} was accepted");
+        }
+        assert_eq!(fs::read_dir(root.path()).unwrap().count(), 0);
+    }
+}
@@ -1,90 +1,809 @@
-//! Theme discovery: scans /usr/share/sddm/themes/ for installed SDDM themes
-//! and reads per-theme metadata from metadata.desktop files.
+//! Theme discovery: scans /usr/share/sddm/themes/ (and the per-user
+//! ~/.local/share/sddm/themes/) for installed SDDM themes and reads per-theme
+//! metadata from metadata.desktop files.
+//!
+//! Discovery only lists directory names so the UI can appear immediately;
+//! metadata is read afterwards, either in place with `load_metadata` or on a
+//! background thread with `spawn_metadata_loader`.
 
+use std::collections::HashMap;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::SystemTime;
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
+
+use crate::config::{dir_writable, is_safe_theme_name, EscalationSession};
 
 pub const THEMES_DIR: &str = "/usr/share/sddm/themes";
 
+/// Section of `metadata.desktop` that SDDM reads.
+const METADATA_SECTION: &str = "SddmGreeterTheme";
+
+/// Background images larger than this (5 MiB) are flagged as slowing the
+/// greeter down.
+pub const LARGE_ASSET_BYTES: u64 = 5 * 1024 * 1024;
+
+/// metadata.desktop keys whose `;`-separated values become a theme's tags.
+const TAG_KEYS: [&str; 2] = ["Categories", "Keywords"];
+
+/// QML entry point SDDM loads when `MainScript=` is not set.
+const DEFAULT_MAIN_SCRIPT: &str = "Main.qml";
+
+/// Options file that configurable themes ship, and the per-install override
+/// SDDM reads on top of it.
+pub const THEME_CONF: &str = "theme.conf";
+const THEME_CONF_USER: &str = "theme.conf.user";
+
+/// Per-user themes directory, relative to `$HOME`.
+pub const USER_THEMES_SUBDIR: &str = ".local/share/sddm/themes";
+
+/// Environment variable naming a single themes directory to scan instead of
+/// the default roots (e.g. a sandbox tree for testing).
+pub const THEMES_DIR_ENV: &str = "THEMEWALKER_THEMES_DIR";
+
+/// Which theme root a theme was discovered in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeSource {
+    /// System-wide install under `/usr/share/sddm/themes`, or another root
+    /// the user cannot write to.
+    System,
+    /// Per-user install under `~/.local/share/sddm/themes`, or another root
+    /// the user can write to.
+    User,
+}
+
+impl ThemeSource {
+    /// The source of the extra or sandbox root `dir`: `User` when the
+    /// current user can write to it, else `System`.
+    pub fn of_dir(dir: &Path) -> Self {
+        if dir_writable(dir) {
+            ThemeSource::User
+        } else {
+            ThemeSource::System
+        }
+    }
+
+    /// Short tag shown next to the theme in the list.
+    pub fn tag(self) -> &'static str {
+        match self {
+            ThemeSource::System => "system",
+            ThemeSource::User => "user",
+        }
+    }
+}
+
+/// Which field of a theme a search query matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryMatch {
+    /// The directory name or the pretty name.
+    Name,
+    Description,
+    Author,
+}
+
+impl QueryMatch {
+    /// Tag shown next to a search result that did not match by name.
+    pub fn tag(self) -> &'static str {
+        match self {
+            QueryMatch::Name => "",
+            QueryMatch::Description => " (in description)",
+            QueryMatch::Author => " (by author)",
+        }
+    }
+}
+
 /// A discovered SDDM theme.
 #[derive(Debug, Clone)]
 pub struct SddmTheme {
     /// Directory name – this is the identifier SDDM uses in its config.
     pub name: String,
-    /// Full path to the theme directory (available for callers that need it).
-    #[allow(dead_code)]
+    /// Human-readable `Name=` from metadata.desktop (if present).  Display only;
+    /// never written to the config.
+    pub pretty_name: Option<String>,
+    /// Full path to the theme directory.
     pub path: PathBuf,
-    /// Human-readable description from metadata.desktop (if present).
-    pub description: Option<String>,
-    /// Author field from metadata.desktop (if present).
-    pub author: Option<String>,
+    /// Every `Key=Value` pair under `[SddmGreeterTheme]` in metadata.desktop
+    /// (`Name`, `Type`, `License`, `Website`, `Theme-API`, ...).  Translated
+    /// keys such as `Name[de]` are kept verbatim; the plain `Name` and
+    /// `Description` hold the best match for the user's locale.
+    pub metadata: HashMap<String, String>,
+    /// Values of `Categories=` and `Keywords=` in metadata.desktop, as
+    /// declared and each listed once.
+    pub tags: Vec<String>,
+    /// Preview image shipped by the theme, resolved against the theme directory.
+    pub screenshot: Option<PathBuf>,
+    /// Size in bytes of the background image metadata.desktop names (the
+    /// screenshot when it names none), if that file exists.
+    pub background_size: Option<u64>,
+    /// Root the theme was found under.
+    pub source: ThemeSource,
+    /// Last modification time of the theme directory (if readable).
+    pub modified: Option<SystemTime>,
+    /// Whether the theme ships a `theme.conf` (or `theme.conf.user`) with
+    /// options that can be adjusted.
+    pub configurable: bool,
+    /// Colours `theme.conf` sets, as read by `read_theme_colors`.
+    pub colors: Vec<(String, [u8; 3])>,
+    /// Whether `load_metadata` has run.  Until then every field read from
+    /// disk is empty.
+    pub metadata_loaded: bool,
+    /// Whether an earlier theme root has a theme of the same name, which is
+    /// the one SDDM loads for it.
+    pub shadowed: bool,
+    /// Names of symlinks in the theme roots that resolve to this theme (e.g.
+    /// a distro's `default`), which the config may name instead.
+    pub aliases: Vec<String>,
 }
 
 impl SddmTheme {
-    /// Try to build an `SddmTheme` from a directory path.
+    /// Try to build an `SddmTheme` from a directory path, reading its metadata.
     /// Returns `None` when the path is not a directory or has no valid name.
-    pub fn from_dir(path: PathBuf) -> Option<Self> {
+    pub fn from_dir(path: PathBuf, source: ThemeSource) -> Option<Self> {
+        let mut theme = Self::from_dir_unloaded(path, source)?;
+        theme.load_metadata();
+        Some(theme)
+    }
+
+    /// Like `from_dir`, but only records the name and path; call
+    /// `load_metadata` to fill in the rest.  A directory whose name fails
+    /// [`is_safe_theme_name`] is not a theme, since applying it could
+    /// corrupt the config.
+    pub fn from_dir_unloaded(path: PathBuf, source: ThemeSource) -> Option<Self> {
         if !path.is_dir() {
             return None;
         }
         let name = path.file_name()?.to_string_lossy().into_owned();
-        let (description, author) = parse_metadata(&path.join("metadata.desktop"));
-        Some(Self { name, path, description, author })
+        if !is_safe_theme_name(&name) {
+            return None;
+        }
+        Some(Self {
+            name,
+            pretty_name: None,
+            path,
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            screenshot: None,
+            background_size: None,
+            source,
+            modified: None,
+            configurable: false,
+            colors: Vec::new(),
+            metadata_loaded: false,
+            shadowed: false,
+            aliases: Vec::new(),
+        })
+    }
+
+    /// Read the display fields from `metadata.desktop` and check for a
+    /// `theme.conf`.
+    pub fn load_metadata(&mut self) {
+        let meta = parse_metadata(&self.path.join("metadata.desktop"));
+        self.pretty_name = meta.pretty_name;
+        self.metadata = meta.entries;
+        self.tags = parse_tags(&self.metadata);
+        self.background_size = meta
+            .background
+            .as_deref()
+            .or(meta.screenshot.as_deref())
+            .and_then(|b| fs::metadata(self.path.join(b)).ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len());
+        self.screenshot = meta.screenshot.map(|s| self.path.join(s));
+        self.modified = fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        self.configurable = [THEME_CONF, THEME_CONF_USER]
+            .iter()
+            .any(|f| self.path.join(f).is_file());
+        self.colors = read_theme_colors(&self.path.join(THEME_CONF));
+        self.metadata_loaded = true;
+    }
+
+    /// Check the theme on disk for problems that typically leave SDDM on a
+    /// broken greeter: a missing or unreadable `metadata.desktop`, a missing
+    /// QML entry point (`MainScript=`, default `Main.qml`), or a preview or
+    /// background image that the metadata names but the theme does not ship.
+    ///
+    /// Reads the disk afresh rather than trusting the loaded fields, so it is
+    /// meant to be called once per confirmation, not on every frame.
+    pub fn validate(&self) -> Result<(), Vec<String>> {
+        let metadata_path = self.path.join("metadata.desktop");
+        if !metadata_path.is_file() {
+            return Err(vec!["no metadata.desktop".to_string()]);
+        }
+        let content = match read_desktop_file(&metadata_path) {
+            Ok(c) => c,
+            Err(e) => return Err(vec![format!("metadata.desktop is unreadable ({e})")]),
+        };
+        let meta = parse_metadata_content(&content, None);
+
+        let mut problems = Vec::new();
+        let main_script = meta.main_script.as_deref().unwrap_or(DEFAULT_MAIN_SCRIPT);
+        if !self.path.join(main_script).is_file() {
+            problems.push(format!("no {main_script}"));
+        }
+        if let Some(background) = meta.background.as_deref() {
+            if !self.path.join(background).is_file() {
+                problems.push(format!("background {background} is missing"));
+            }
+        }
+        if let Some(screenshot) = meta.screenshot.as_deref() {
+            if meta.background.as_deref() != Some(screenshot)
+                && !self.path.join(screenshot).is_file()
+            {
+                problems.push(format!("preview image {screenshot} is missing"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+
+    /// Case-insensitive substring match against the name, pretty name,
+    /// description and author.  `query_lower` must already be lowercased.
+    pub fn matches_query(&self, query_lower: &str) -> bool {
+        self.query_match(query_lower).is_some()
+    }
+
+    /// The first field `query_lower` matches, in the order name (or pretty
+    /// name), description, author.
+    pub fn query_match(&self, query_lower: &str) -> Option<QueryMatch> {
+        let contains = |field: Option<&str>| {
+            field.is_some_and(|f| f.to_lowercase().contains(query_lower))
+        };
+        if contains(Some(&self.name)) || contains(self.pretty_name.as_deref()) {
+            Some(QueryMatch::Name)
+        } else if contains(self.description()) {
+            Some(QueryMatch::Description)
+        } else if contains(self.author()) {
+            Some(QueryMatch::Author)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the background image is over `LARGE_ASSET_BYTES`.
+    pub fn has_large_background(&self) -> bool {
+        self.background_size.is_some_and(|size| size > LARGE_ASSET_BYTES)
+    }
+
+    /// Whether the theme declares `tag`, ignoring case.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        let tag = tag.to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase() == tag)
+    }
+
+    /// Whether loaded metadata lacks a description or an author.  Themes
+    /// whose metadata has not been loaded yet do not count.
+    pub fn metadata_incomplete(&self) -> bool {
+        self.metadata_loaded && (self.description().is_none() || self.author().is_none())
+    }
+
+    /// `Description=` from metadata.desktop, translated when possible.
+    pub fn description(&self) -> Option<&str> {
+        self.metadata.get("Description").map(String::as_str)
+    }
+
+    /// `Author=` from metadata.desktop.
+    pub fn author(&self) -> Option<&str> {
+        self.metadata.get("Author").map(String::as_str)
+    }
+
+    /// Whether deleting the theme needs root, i.e. the directory holding it
+    /// is not writable by the current user, whatever its `source`.
+    pub fn removal_needs_root(&self) -> bool {
+        !self.path.parent().is_some_and(dir_writable)
+    }
+
+    /// Whether the config's `Current=` value `name` selects this theme: its
+    /// directory name or one of its `aliases`.
+    pub fn answers_to(&self, name: &str) -> bool {
+        self.name == name || self.aliases.iter().any(|a| a == name)
+    }
+
+    /// Name to show in the UI: the metadata `Name=` when present, otherwise
+    /// the directory name.
+    pub fn display_name(&self) -> &str {
+        self.pretty_name.as_deref().unwrap_or(&self.name)
     }
 
     /// One-line summary for display: "name — description" when a description exists.
     pub fn display_label(&self) -> String {
-        match &self.description {
-            Some(d) if !d.is_empty() => format!("{} — {}", self.name, d),
-            _ => self.name.clone(),
+        match self.description() {
+            Some(d) if !d.is_empty() => format!("{} — {}", self.display_name(), d),
+            _ => self.display_name().to_string(),
         }
     }
 }
 
-/// Parse `Description=` and `Author=` from a `.desktop` file.
-fn parse_metadata(path: &Path) -> (Option<String>, Option<String>) {
-    let content = match fs::read_to_string(path) {
-        Ok(c) => c,
-        Err(_) => return (None, None),
-    };
-    let mut description = None;
-    let mut author = None;
+/// Fields read from a theme's `metadata.desktop`.
+#[derive(Debug, Default)]
+struct Metadata {
+    /// All keys of the `[SddmGreeterTheme]` section; see `SddmTheme::metadata`.
+    entries: HashMap<String, String>,
+    pretty_name: Option<String>,
+    /// Raw `Screenshot=` value (falls back to `Background=`), relative to the theme dir.
+    screenshot: Option<String>,
+    /// Raw `Background=` value, relative to the theme dir.
+    background: Option<String>,
+    /// `MainScript=` value, relative to the theme dir.
+    main_script: Option<String>,
+}
+
+/// Tags from the `TAG_KEYS` of `metadata`.  Lists are `;`-separated as in
+/// the desktop entry spec, though `,` is accepted too; repeats that differ
+/// only in case are dropped.
+fn parse_tags(metadata: &HashMap<String, String>) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    let values = TAG_KEYS.iter().filter_map(|key| metadata.get(*key));
+    for tag in values.flat_map(|v| v.split([';', ','])) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t.to_lowercase() == tag.to_lowercase()) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+/// Parse `metadata.desktop` using the user's locale for translated fields.
+fn parse_metadata(path: &Path) -> Metadata {
+    parse_metadata_localized(path, current_locale().as_deref())
+}
+
+/// Parse the `[SddmGreeterTheme]` keys of a `.desktop` file, picking out
+/// `Name=` and the preview image.  Keys before any section header count too;
+/// other sections are skipped.
+///
+/// `Name` and `Description` honour translated keys such as `Name[de]=`: an
+/// exact `lang_COUNTRY` match wins over a language-only match, which wins over
+/// the unqualified key.  Translations for other locales are only kept under
+/// their own keys.
+fn parse_metadata_localized(path: &Path, locale: Option<&str>) -> Metadata {
+    match read_desktop_file(path) {
+        Ok(content) => parse_metadata_content(&content, locale),
+        Err(_) => Metadata::default(),
+    }
+}
+
+/// Read a `.desktop` file leniently: a leading UTF-8 byte order mark is
+/// dropped and invalid UTF-8 (e.g. Latin-1 text) is decoded lossily, so the
+/// remaining keys can still be parsed.
+fn read_desktop_file(path: &Path) -> std::io::Result<String> {
+    let bytes = fs::read(path)?;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+/// `parse_metadata_localized` on already-read file content.
+fn parse_metadata_content(content: &str, locale: Option<&str>) -> Metadata {
+    let candidates = locale.map(locale_candidates).unwrap_or_default();
+
+    let mut meta = Metadata::default();
+    // Rank of the key each localized field was taken from (lower is better)
+    let mut name_rank = usize::MAX;
+    let mut description_rank = usize::MAX;
+    let mut in_section = true;
+
     for line in content.lines() {
-        let t = line.trim();
-        if description.is_none() {
-            if let Some(v) = t.strip_prefix("Description=") {
-                description = Some(v.to_string());
+        let line = line.trim();
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = section == METADATA_SECTION;
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        let (base, key_locale) = split_locale_key(key);
+
+        // Translations are kept under their own keys; the plain localized
+        // keys are filled in below with the best match for the locale
+        if key_locale.is_some() {
+            meta.entries.insert(key.to_string(), value.to_string());
+        } else if !matches!(base, "Name" | "Description") {
+            meta.entries
+                .entry(key.to_string())
+                .or_insert_with(|| value.to_string());
+        }
+
+        let rank = match key_locale {
+            None => candidates.len(),
+            Some(l) => match candidates.iter().position(|c| c == l) {
+                Some(r) => r,
+                None => continue, // translation for another locale
+            },
+        };
+
+        match base {
+            "Name" if rank < name_rank && !value.is_empty() => {
+                meta.pretty_name = Some(value.to_string());
+                meta.entries.insert(base.to_string(), value.to_string());
+                name_rank = rank;
+            }
+            "Description" if rank < description_rank => {
+                meta.entries.insert(base.to_string(), value.to_string());
+                description_rank = rank;
             }
+            _ if key_locale.is_some() => {}
+            // SDDM's own themes spell it `Screenshot`; some third-party ones use `ScreenShot`
+            "Screenshot" | "ScreenShot" if meta.screenshot.is_none() && !value.is_empty() => {
+                meta.screenshot = Some(value.to_string());
+            }
+            "Background" if meta.background.is_none() && !value.is_empty() => {
+                meta.background = Some(value.to_string());
+            }
+            "MainScript" if meta.main_script.is_none() && !value.is_empty() => {
+                meta.main_script = Some(value.to_string());
+            }
+            _ => {}
+        }
+    }
+    if meta.screenshot.is_none() {
+        meta.screenshot = meta.background.clone();
+    }
+    meta
+}
+
+/// The locale used for translated metadata: `$LC_ALL`, then `$LC_MESSAGES`,
+/// then `$LANG`.
+fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok().filter(|s| !s.is_empty()))
+}
+
+/// Translation keys to look for, best first: `de_DE.UTF-8` → `["de_DE", "de"]`.
+fn locale_candidates(locale: &str) -> Vec<String> {
+    let base = locale.split(['.', '@']).next().unwrap_or("");
+    if base.is_empty() || base == "C" || base == "POSIX" {
+        return Vec::new();
+    }
+    let mut out = vec![base.to_string()];
+    if let Some((lang, _)) = base.split_once('_') {
+        out.push(lang.to_string());
+    }
+    out
+}
+
+/// Split `Name[de]` into `("Name", Some("de"))`; plain keys have no locale.
+fn split_locale_key(key: &str) -> (&str, Option<&str>) {
+    match key.strip_suffix(']').and_then(|k| k.split_once('[')) {
+        Some((base, locale)) => (base, Some(locale)),
+        None => (key, None),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// theme.conf colours
+// ---------------------------------------------------------------------------
+
+/// Colour names accepted besides hex values, with their SVG/Qt RGB values.
+const COLOR_NAMES: &[(&str, [u8; 3])] = &[
+    ("black", [0, 0, 0]),
+    ("white", [255, 255, 255]),
+    ("gray", [128, 128, 128]),
+    ("grey", [128, 128, 128]),
+    ("red", [255, 0, 0]),
+    ("green", [0, 128, 0]),
+    ("blue", [0, 0, 255]),
+    ("yellow", [255, 255, 0]),
+    ("cyan", [0, 255, 255]),
+    ("magenta", [255, 0, 255]),
+    ("orange", [255, 165, 0]),
+    ("purple", [128, 0, 128]),
+];
+
+/// Colours a theme's options file sets in `[General]`, as RGB, in file
+/// order.  Keys ending in `color` or `colour` (any case, e.g. `MainColor`,
+/// `AccentColor`) count, as does a bare `background` when it holds a colour
+/// rather than an image path.  Values that are not colours are skipped, and
+/// a missing or unreadable file yields nothing.
+pub fn read_theme_colors(path: &Path) -> Vec<(String, [u8; 3])> {
+    fs::read_to_string(path)
+        .map(|content| theme_color_values(&content))
+        .unwrap_or_default()
+}
+
+/// `read_theme_colors` on already-read content.
+fn theme_color_values(content: &str) -> Vec<(String, [u8; 3])> {
+    let mut colors: Vec<(String, [u8; 3])> = Vec::new();
+    let mut in_general = false;
+    for line in content.lines() {
+        let t = line.trim();
+        if t.starts_with('[') {
+            in_general = t == "[General]";
+            continue;
         }
-        if author.is_none() {
-            if let Some(v) = t.strip_prefix("Author=") {
-                author = Some(v.to_string());
+        let Some((key, value)) = t.split_once('=').filter(|_| in_general) else {
+            continue;
+        };
+        let key = key.trim();
+        let lower = key.to_lowercase();
+        let is_color_key =
+            lower.ends_with("color") || lower.ends_with("colour") || lower == "background";
+        if !is_color_key || colors.iter().any(|(k, _)| k == key) {
+            continue;
+        }
+        if let Some(rgb) = parse_color(value) {
+            colors.push((key.to_string(), rgb));
+        }
+    }
+    colors
+}
+
+/// Parse a Qt colour string: `#rgb`, `#rrggbb`, `#aarrggbb` (alpha is
+/// ignored) or one of `COLOR_NAMES`, optionally in double quotes.
+fn parse_color(value: &str) -> Option<[u8; 3]> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+        .trim();
+    let Some(hex) = value.strip_prefix('#') else {
+        let lower = value.to_lowercase();
+        return COLOR_NAMES
+            .iter()
+            .find(|(name, _)| *name == lower)
+            .map(|(_, rgb)| *rgb);
+    };
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    match hex.len() {
+        3 => {
+            let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+            Some([digit(0)?, digit(1)?, digit(2)?])
+        }
+        6 => Some([byte(0)?, byte(2)?, byte(4)?]),
+        8 => Some([byte(2)?, byte(4)?, byte(6)?]),
+        _ => None,
+    }
+}
+
+/// The default theme roots in precedence order: the per-user directory
+/// (when `$HOME` is set) followed by the system directory.  A non-empty
+/// `$THEMEWALKER_THEMES_DIR` replaces both; a sandbox the user owns is a
+/// user root, so nothing in it is deleted through sudo.
+pub fn default_theme_roots() -> Vec<(PathBuf, ThemeSource)> {
+    if let Some(dir) = std::env::var_os(THEMES_DIR_ENV).filter(|d| !d.is_empty()) {
+        let dir = PathBuf::from(dir);
+        let source = ThemeSource::of_dir(&dir);
+        return vec![(dir, source)];
+    }
+    let mut roots = Vec::new();
+    if let Some(dir) = user_themes_dir() {
+        roots.push((dir, ThemeSource::User));
+    }
+    roots.push((PathBuf::from(THEMES_DIR), ThemeSource::System));
+    roots
+}
+
+/// `default_theme_roots` followed by `extra` (e.g. the `theme_dirs` of
+/// Themewalker's config.toml), which lose to them when theme names clash.
+/// `system_dir` (SDDM's `ThemeDir=`) takes the place of
+/// `/usr/share/sddm/themes`.  `$THEMEWALKER_THEMES_DIR` still replaces
+/// every root.
+pub fn theme_roots(system_dir: Option<&Path>, extra: &[PathBuf]) -> Vec<(PathBuf, ThemeSource)> {
+    let roots = default_theme_roots();
+    if std::env::var_os(THEMES_DIR_ENV).filter(|d| !d.is_empty()).is_some() {
+        return roots;
+    }
+    append_roots(replace_system_root(roots, system_dir), extra)
+}
+
+/// Point the system root of `roots` at `dir`, when given.
+fn replace_system_root(
+    mut roots: Vec<(PathBuf, ThemeSource)>,
+    dir: Option<&Path>,
+) -> Vec<(PathBuf, ThemeSource)> {
+    if let Some(dir) = dir {
+        for (root, source) in &mut roots {
+            if *source == ThemeSource::System {
+                *root = dir.to_path_buf();
             }
         }
-        if description.is_some() && author.is_some() {
-            break;
+    }
+    roots
+}
+
+/// Add each of `extra` to `roots`, skipping ones already listed.  Each is a
+/// user root only when it is writable (see `ThemeSource::of_dir`).
+fn append_roots(
+    mut roots: Vec<(PathBuf, ThemeSource)>,
+    extra: &[PathBuf],
+) -> Vec<(PathBuf, ThemeSource)> {
+    for dir in extra {
+        if !roots.iter().any(|(root, _)| root == dir) {
+            roots.push((dir.clone(), ThemeSource::of_dir(dir)));
         }
     }
-    (description, author)
+    roots
 }
 
-/// Scan `THEMES_DIR` and return all installed themes, sorted alphabetically.
+/// The per-user theme directory, `~/.local/share/sddm/themes`, or `None`
+/// when `$HOME` is unset.
+pub fn user_themes_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(USER_THEMES_SUBDIR))
+}
+
+/// Scan the default theme roots and return all installed themes, without
+/// their metadata.
 pub fn discover_themes() -> Result<Vec<SddmTheme>> {
-    let dir = Path::new(THEMES_DIR);
-    if !dir.exists() {
-        return Ok(Vec::new());
+    discover_themes_in(&default_theme_roots())
+}
+
+/// Scan each root and return the merged themes, sorted alphabetically.
+/// Only directory names are read; metadata is left unloaded.
+///
+/// Roots are given in precedence order: when two roots contain a theme with
+/// the same directory name, the one from the earlier root is what SDDM
+/// loads, and the later one is kept with `shadowed` set, sorted right after
+/// it.  Roots that do not exist, are not directories or cannot be read for
+/// lack of permission are skipped (see `non_directory_roots_in` and
+/// `unreadable_theme_dirs`); other read errors are returned.
+///
+/// Symlinked themes (e.g. a distro's `default` pointing at a real theme) are
+/// listed once: when several entries resolve to the same directory, the real
+/// directory is kept and the links' names are recorded in its `aliases`.  A
+/// link whose target is not listed itself keeps the link's name.
+pub fn discover_themes_in(roots: &[(PathBuf, ThemeSource)]) -> Result<Vec<SddmTheme>> {
+    let mut themes: Vec<SddmTheme> = Vec::new();
+    // Canonical path of each entry in `themes`, for spotting symlinked duplicates
+    let mut canonical: Vec<Option<PathBuf>> = Vec::new();
+
+    for (dir, source) in roots {
+        // Missing roots, and ones that are files (see `non_directory_roots_in`)
+        if !dir.is_dir() {
+            continue;
+        }
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for mut theme in entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            // Hidden entries, such as the staging directory an interrupted
+            // import leaves behind, are not themes
+            .filter(|p| !p.file_name().is_some_and(|n| n.to_string_lossy().starts_with('.')))
+            .filter_map(|p| SddmTheme::from_dir_unloaded(p, *source))
+        {
+            let resolved = fs::canonicalize(&theme.path).ok();
+            let duplicate = resolved
+                .as_ref()
+                .and_then(|r| canonical.iter().position(|c| c.as_ref() == Some(r)));
+            if let Some(winner) = themes.iter().position(|t| t.name == theme.name) {
+                // The same directory reached through two roots is listed once
+                if duplicate != Some(winner) {
+                    theme.shadowed = true;
+                    themes.push(theme);
+                    canonical.push(resolved);
+                }
+                continue;
+            }
+            match duplicate {
+                Some(i) => {
+                    if is_symlink(&themes[i].path) && !is_symlink(&theme.path) {
+                        let link = std::mem::replace(&mut themes[i], theme);
+                        themes[i].aliases = link.aliases;
+                        themes[i].aliases.push(link.name);
+                    } else {
+                        themes[i].aliases.push(theme.name);
+                    }
+                }
+                None => {
+                    themes.push(theme);
+                    canonical.push(resolved);
+                }
+            }
+        }
+    }
+
+    themes.sort_by(|a, b| a.name.cmp(&b.name).then(a.shadowed.cmp(&b.shadowed)));
+    for theme in &mut themes {
+        theme.aliases.sort();
     }
+    Ok(themes)
+}
+
+/// Whether `path` itself is a symbolic link (not following it).
+fn is_symlink(path: &Path) -> bool {
+    fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink())
+}
+
+/// Default theme roots that exist but cannot be listed because permission
+/// is denied, so callers can explain an empty or partial theme list.
+pub fn unreadable_theme_dirs() -> Vec<PathBuf> {
+    unreadable_dirs_in(&default_theme_roots())
+}
+
+/// The roots in `roots` whose listing fails with a permission error.
+pub fn unreadable_dirs_in(roots: &[(PathBuf, ThemeSource)]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .filter(|(dir, _)| {
+            matches!(fs::read_dir(dir), Err(e) if e.kind() == io::ErrorKind::PermissionDenied)
+        })
+        .map(|(dir, _)| dir.clone())
+        .collect()
+}
+
+/// The roots in `roots` that exist but are not directories (e.g. a broken
+/// install left a file at `/usr/share/sddm/themes`).
+pub fn non_directory_roots_in(roots: &[(PathBuf, ThemeSource)]) -> Vec<PathBuf> {
+    roots
+        .iter()
+        .filter(|(dir, _)| dir.exists() && !dir.is_dir())
+        .map(|(dir, _)| dir.clone())
+        .collect()
+}
 
-    let mut themes: Vec<SddmTheme> = fs::read_dir(dir)?
-        .filter_map(|e| e.ok())
-        .map(|e| e.path())
-        .filter_map(SddmTheme::from_dir)
+/// Directories in `roots` skipped by discovery because their names fail
+/// [`is_safe_theme_name`], so callers can warn about them.
+pub fn unsafe_theme_dirs_in(roots: &[(PathBuf, ThemeSource)]) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = roots
+        .iter()
+        .filter_map(|(dir, _)| fs::read_dir(dir).ok())
+        .flat_map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()))
+        .filter(|path| {
+            path.is_dir()
+                && path
+                    .file_name()
+                    .is_some_and(|n| !is_safe_theme_name(&n.to_string_lossy()))
+        })
         .collect();
+    dirs.sort();
+    dirs
+}
 
-    themes.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(themes)
+/// Load the metadata of `themes` on a background thread.  Each theme is sent
+/// over the returned channel as soon as it is loaded; the thread stops early
+/// once the receiver is dropped.
+pub fn spawn_metadata_loader(themes: &[SddmTheme]) -> Receiver<SddmTheme> {
+    let (tx, rx) = mpsc::channel();
+    let mut pending = themes.to_vec();
+    thread::spawn(move || {
+        for mut theme in pending.drain(..) {
+            theme.load_metadata();
+            if tx.send(theme).is_err() {
+                break;
+            }
+        }
+    });
+    rx
+}
+
+/// Delete `theme`'s directory.  It is removed directly when its root is
+/// writable; otherwise (see `SddmTheme::removal_needs_root`) it goes through
+/// `sudo rm -rf` (or `doas`, per `session`), prompting for the password on
+/// the inherited terminal.  A symlinked theme loses only the link.
+pub fn remove_theme(theme: &SddmTheme, session: &EscalationSession) -> Result<()> {
+    if !theme.removal_needs_root() {
+        return fs::remove_dir_all(&theme.path)
+            .with_context(|| format!("Failed to remove {}", theme.path.display()));
+    }
+    let rm = ["rm".as_ref(), "-rf".as_ref(), "--".as_ref(), theme.path.as_os_str()];
+    let (escalation, mut command) = session.command(&theme.path, &rm)?;
+    let tool = escalation.program();
+    let status = command
+        .status()
+        .with_context(|| format!("Failed to run `{tool} rm`"))?;
+    if !status.success() {
+        bail!("`{tool} rm -rf {}` failed ({status})", theme.path.display());
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -98,6 +817,25 @@ mod tests {
         f.write_all(content.as_bytes()).unwrap();
     }
 
+    fn make_theme(name: &str) -> SddmTheme {
+        SddmTheme {
+            name: name.to_string(),
+            pretty_name: None,
+            path: PathBuf::from("/tmp"),
+            metadata: HashMap::new(),
+            tags: Vec::new(),
+            screenshot: None,
+            background_size: None,
+            source: ThemeSource::System,
+            modified: None,
+            configurable: false,
+            colors: Vec::new(),
+            metadata_loaded: true,
+            shadowed: false,
+            aliases: Vec::new(),
+        }
+    }
+
     #[test]
     fn test_parse_metadata_reads_fields() {
         let dir = tempfile::tempdir().unwrap();
@@ -106,17 +844,201 @@ mod tests {
             &meta,
             "[SddmGreeterTheme]\nName=Foo\nDescription=A test theme\nAuthor=Tester\n",
         );
-        let (desc, auth) = parse_metadata(&meta);
-        assert_eq!(desc.as_deref(), Some("A test theme"));
-        assert_eq!(auth.as_deref(), Some("Tester"));
+        let parsed = parse_metadata(&meta);
+        assert_eq!(parsed.pretty_name.as_deref(), Some("Foo"));
+        assert_eq!(parsed.entries["Description"], "A test theme");
+        assert_eq!(parsed.entries["Author"], "Tester");
+    }
+
+    #[test]
+    fn test_parse_metadata_keeps_all_greeter_keys() {
+        let content = "[SddmGreeterTheme]\n\
+                       Name=Sugar Candy\n\
+                       Name[de]=Zuckerwatte\n\
+                       Type=sddm-theme\n\
+                       License=GPL-3.0\n\
+                       Website=https://example.org\n\
+                       Theme-Id=sugar-candy\n\
+                       Theme-API=2.0\n\
+                       [Other]\n\
+                       License=MIT\n";
+        let parsed = parse_metadata_content(content, Some("fr_FR"));
+        assert_eq!(parsed.entries["Name"], "Sugar Candy");
+        assert_eq!(parsed.entries["Name[de]"], "Zuckerwatte");
+        assert_eq!(parsed.entries["Type"], "sddm-theme");
+        assert_eq!(parsed.entries["License"], "GPL-3.0");
+        assert_eq!(parsed.entries["Website"], "https://example.org");
+        assert_eq!(parsed.entries["Theme-Id"], "sugar-candy");
+        assert_eq!(parsed.entries["Theme-API"], "2.0");
+        assert_eq!(parsed.entries.len(), 7);
+
+        // The plain key holds the translation for the user's locale
+        let parsed = parse_metadata_content(content, Some("de_DE"));
+        assert_eq!(parsed.entries["Name"], "Zuckerwatte");
     }
 
     #[test]
     fn test_parse_metadata_missing_file() {
         let dir = tempfile::tempdir().unwrap();
-        let (desc, auth) = parse_metadata(&dir.path().join("nonexistent.desktop"));
-        assert!(desc.is_none());
-        assert!(auth.is_none());
+        let parsed = parse_metadata(&dir.path().join("nonexistent.desktop"));
+        assert!(parsed.entries.is_empty());
+        assert!(parsed.screenshot.is_none());
+    }
+
+    #[test]
+    fn test_parse_metadata_screenshot_falls_back_to_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        write_file(&meta, "[SddmGreeterTheme]\nBackground=bg.jpg\n");
+        assert_eq!(parse_metadata(&meta).screenshot.as_deref(), Some("bg.jpg"));
+
+        write_file(&meta, "[SddmGreeterTheme]\nBackground=bg.jpg\nScreenshot=preview.png\n");
+        assert_eq!(parse_metadata(&meta).screenshot.as_deref(), Some("preview.png"));
+    }
+
+    const LOCALIZED: &str = "[SddmGreeterTheme]\n\
+                             Name=Sugar Candy\n\
+                             Name[de]=Zuckerwatte\n\
+                             Name[de_AT]=Zuckerl\n\
+                             Description=Sweet\n\
+                             Description[fr]=Sucré\n";
+
+    #[test]
+    fn test_localized_exact_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        write_file(&meta, LOCALIZED);
+        let parsed = parse_metadata_localized(&meta, Some("de_AT.UTF-8"));
+        assert_eq!(parsed.pretty_name.as_deref(), Some("Zuckerl"));
+        assert_eq!(parsed.entries["Description"], "Sweet");
+    }
+
+    #[test]
+    fn test_localized_language_only_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        write_file(&meta, LOCALIZED);
+        let parsed = parse_metadata_localized(&meta, Some("de_DE"));
+        assert_eq!(parsed.pretty_name.as_deref(), Some("Zuckerwatte"));
+
+        let parsed = parse_metadata_localized(&meta, Some("fr_FR.UTF-8"));
+        assert_eq!(parsed.pretty_name.as_deref(), Some("Sugar Candy"));
+        assert_eq!(parsed.entries["Description"], "Sucré");
+    }
+
+    #[test]
+    fn test_localized_default_fallback() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        write_file(&meta, LOCALIZED);
+        for locale in [Some("ja_JP.UTF-8"), Some("C"), None] {
+            let parsed = parse_metadata_localized(&meta, locale);
+            assert_eq!(parsed.pretty_name.as_deref(), Some("Sugar Candy"));
+            assert_eq!(parsed.entries["Description"], "Sweet");
+        }
+    }
+
+    #[test]
+    fn test_parse_metadata_strips_bom() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        fs::write(&meta, b"\xEF\xBB\xBF[SddmGreeterTheme]\nName=Foo\nAuthor=Tester\n").unwrap();
+        let parsed = parse_metadata_localized(&meta, None);
+        assert_eq!(parsed.pretty_name.as_deref(), Some("Foo"));
+        assert_eq!(parsed.entries["Author"], "Tester");
+    }
+
+    #[test]
+    fn test_parse_metadata_tolerates_invalid_utf8() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        // "Café" in Latin-1: the 0xE9 byte is not valid UTF-8
+        fs::write(
+            &meta,
+            b"[SddmGreeterTheme]\nDescription=Caf\xE9 theme\nAuthor=Ren\xE9\nMainScript=Main.qml\n",
+        )
+        .unwrap();
+        let parsed = parse_metadata_localized(&meta, None);
+        assert_eq!(parsed.entries["Description"], "Caf\u{FFFD} theme");
+        assert_eq!(parsed.entries["Author"], "Ren\u{FFFD}");
+        assert_eq!(parsed.main_script.as_deref(), Some("Main.qml"));
+    }
+
+    #[test]
+    fn test_from_dir_resolves_screenshot_path() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(
+            &dir.path().join("metadata.desktop"),
+            "[SddmGreeterTheme]\nScreenShot=preview.png\n",
+        );
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.screenshot, Some(dir.path().join("preview.png")));
+    }
+
+    #[test]
+    fn test_from_dir_measures_background() {
+        let dir = tempfile::tempdir().unwrap();
+        let meta = dir.path().join("metadata.desktop");
+        write_file(&meta, "[SddmGreeterTheme]\nScreenshot=preview.png\nBackground=bg.jpg\n");
+        write_file(&dir.path().join("preview.png"), "tiny");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        // The named background is missing
+        assert_eq!(theme.background_size, None);
+
+        // Sparse, so the test does not write megabytes
+        fs::File::create(dir.path().join("bg.jpg"))
+            .unwrap()
+            .set_len(LARGE_ASSET_BYTES + 1)
+            .unwrap();
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.background_size, Some(LARGE_ASSET_BYTES + 1));
+        assert!(theme.has_large_background());
+
+        // Without a background the screenshot is measured
+        write_file(&meta, "[SddmGreeterTheme]\nScreenshot=preview.png\n");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.background_size, Some(4));
+        assert!(!theme.has_large_background());
+    }
+
+    #[test]
+    fn test_from_dir_detects_theme_conf() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert!(!theme.configurable);
+
+        write_file(&dir.path().join("theme.conf.user"), "[General]\n");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert!(theme.configurable);
+    }
+
+    #[test]
+    fn test_load_metadata_reads_theme_colors() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(&dir.path().join(THEME_CONF), "[General]\nAccentColor=#ff0080\n");
+        let mut theme =
+            SddmTheme::from_dir_unloaded(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert!(theme.colors.is_empty());
+        theme.load_metadata();
+        assert_eq!(theme.colors, [("AccentColor".to_string(), [255, 0, 128])]);
+    }
+
+    #[test]
+    fn test_from_dir_reads_tags() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(
+            &dir.path().join("metadata.desktop"),
+            "[SddmGreeterTheme]\nCategories=Dark;Minimal;\nKeywords=minimal, anime\n",
+        );
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.tags, ["Dark", "Minimal", "anime"]);
+        assert!(theme.has_tag("dark"));
+        assert!(!theme.has_tag("light"));
+
+        // Without either key there are no tags
+        write_file(&dir.path().join("metadata.desktop"), "[SddmGreeterTheme]\nName=Foo\n");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert!(theme.tags.is_empty());
     }
 
     #[test]
@@ -124,28 +1046,426 @@ mod tests {
         let dir = tempfile::tempdir().unwrap();
         let file = dir.path().join("notadir");
         write_file(&file, "content");
-        assert!(SddmTheme::from_dir(file).is_none());
+        assert!(SddmTheme::from_dir(file, ThemeSource::System).is_none());
+    }
+
+    #[test]
+    fn test_validate_checks_main_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.validate(), Err(vec!["no metadata.desktop".to_string()]));
+
+        write_file(
+            &dir.path().join("metadata.desktop"),
+            "[SddmGreeterTheme]\nMainScript=Login.qml\n",
+        );
+        write_file(&dir.path().join("Main.qml"), "");
+        assert_eq!(theme.validate(), Err(vec!["no Login.qml".to_string()]));
+
+        write_file(&dir.path().join("Login.qml"), "");
+        assert_eq!(theme.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_defaults_to_main_qml() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(&dir.path().join("metadata.desktop"), "[SddmGreeterTheme]\n");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.validate(), Err(vec!["no Main.qml".to_string()]));
+
+        write_file(&dir.path().join("Main.qml"), "");
+        assert_eq!(theme.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_missing_images() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(
+            &dir.path().join("metadata.desktop"),
+            "[SddmGreeterTheme]\nScreenshot=preview.png\nBackground=bg.jpg\n",
+        );
+        write_file(&dir.path().join("Main.qml"), "");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(
+            theme.validate(),
+            Err(vec![
+                "background bg.jpg is missing".to_string(),
+                "preview image preview.png is missing".to_string(),
+            ])
+        );
+
+        write_file(&dir.path().join("bg.jpg"), "");
+        write_file(&dir.path().join("preview.png"), "");
+        assert_eq!(theme.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_validate_reports_background_once_when_used_as_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        write_file(&dir.path().join("metadata.desktop"), "[SddmGreeterTheme]\nBackground=bg.jpg\n");
+        write_file(&dir.path().join("Main.qml"), "");
+        let theme = SddmTheme::from_dir(dir.path().to_path_buf(), ThemeSource::System).unwrap();
+        assert_eq!(theme.validate(), Err(vec!["background bg.jpg is missing".to_string()]));
+    }
+
+    #[test]
+    fn test_parse_color_formats() {
+        assert_eq!(parse_color("#1e90ff"), Some([0x1e, 0x90, 0xff]));
+        assert_eq!(parse_color("#FFF"), Some([255, 255, 255]));
+        assert_eq!(parse_color("#801e90ff"), Some([0x1e, 0x90, 0xff]));
+        assert_eq!(parse_color(" \"#000000\" "), Some([0, 0, 0]));
+        assert_eq!(parse_color("White"), Some([255, 255, 255]));
+        assert_eq!(parse_color("#12345"), None);
+        assert_eq!(parse_color("#gggggg"), None);
+        assert_eq!(parse_color("#éé"), None);
+        assert_eq!(parse_color("Backgrounds/mountain.jpg"), None);
+        assert_eq!(parse_color(""), None);
+    }
+
+    #[test]
+    fn test_theme_colors_from_general_section() {
+        let content = "[General]\n\
+                       Background=Backgrounds/bg.png\n\
+                       MainColor=\"#ffffff\"\n\
+                       AccentColor=#fb884f\n\
+                       BackgroundColour=black\n\
+                       FontSize=12\n\
+                       HeaderTextColor=not-a-colour\n\
+                       MainColor=#000000\n\
+                       [Other]\n\
+                       TextColor=#123456\n";
+        let colors = theme_color_values(content);
+        assert_eq!(
+            colors,
+            vec![
+                ("MainColor".to_string(), [255, 255, 255]),
+                ("AccentColor".to_string(), [0xfb, 0x88, 0x4f]),
+                ("BackgroundColour".to_string(), [0, 0, 0]),
+            ]
+        );
+
+        let content = "[General]\nbackground=#336699\n";
+        let expected = vec![("background".to_string(), [0x33, 0x66, 0x99])];
+        assert_eq!(theme_color_values(content), expected);
+    }
+
+    #[test]
+    fn test_read_theme_colors_missing_file() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(read_theme_colors(&dir.path().join(THEME_CONF)).is_empty());
+    }
+
+    #[test]
+    fn test_discover_merges_roots_with_precedence() {
+        let user = tempfile::tempdir().unwrap();
+        let system = tempfile::tempdir().unwrap();
+        fs::create_dir(user.path().join("breeze")).unwrap();
+        fs::create_dir(system.path().join("breeze")).unwrap();
+        fs::create_dir(system.path().join("maya")).unwrap();
+
+        let roots = vec![
+            (user.path().to_path_buf(), ThemeSource::User),
+            (PathBuf::from("/nonexistent/themewalker"), ThemeSource::User),
+            (system.path().to_path_buf(), ThemeSource::System),
+        ];
+        let themes = discover_themes_in(&roots).unwrap();
+
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["breeze", "breeze", "maya"]);
+        assert_eq!(themes[0].source, ThemeSource::User);
+        assert_eq!(themes[1].source, ThemeSource::System);
+        assert_eq!(themes[2].source, ThemeSource::System);
+    }
+
+    #[test]
+    fn test_file_in_place_of_themes_dir_is_skipped() {
+        let user = tempfile::tempdir().unwrap();
+        fs::create_dir(user.path().join("breeze")).unwrap();
+        let system = user.path().join("themes");
+        fs::write(&system, "").unwrap();
+        let roots = vec![
+            (user.path().to_path_buf(), ThemeSource::User),
+            (system.clone(), ThemeSource::System),
+        ];
+
+        let themes = discover_themes_in(&roots).unwrap();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["breeze"]);
+        assert_eq!(non_directory_roots_in(&roots), [system]);
+        assert!(discover_themes_in(&roots[1..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_later_root_copy_is_shadowed() {
+        let user = tempfile::tempdir().unwrap();
+        let system = tempfile::tempdir().unwrap();
+        fs::create_dir(user.path().join("breeze")).unwrap();
+        fs::create_dir(system.path().join("breeze")).unwrap();
+        fs::create_dir(system.path().join("maya")).unwrap();
+        // The user root again under another path adds no copies
+        let user_link = tempfile::tempdir().unwrap();
+        let link = user_link.path().join("themes");
+        std::os::unix::fs::symlink(user.path(), &link).unwrap();
+
+        let roots = vec![
+            (user.path().to_path_buf(), ThemeSource::User),
+            (system.path().to_path_buf(), ThemeSource::System),
+            (link, ThemeSource::User),
+        ];
+        let themes = discover_themes_in(&roots).unwrap();
+
+        let found: Vec<(&Path, bool)> =
+            themes.iter().map(|t| (t.path.as_path(), t.shadowed)).collect();
+        assert_eq!(
+            found,
+            [
+                (user.path().join("breeze").as_path(), false),
+                (system.path().join("breeze").as_path(), true),
+                (system.path().join("maya").as_path(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_theme_dir_replaces_system_root() {
+        let defaults = vec![
+            (PathBuf::from("/home/a/.local/share/sddm/themes"), ThemeSource::User),
+            (PathBuf::from(THEMES_DIR), ThemeSource::System),
+        ];
+        assert_eq!(replace_system_root(defaults.clone(), None), defaults);
+        let roots = replace_system_root(defaults.clone(), Some(Path::new("/srv/themes")));
+        assert_eq!(roots[0], defaults[0]);
+        assert_eq!(roots[1], (PathBuf::from("/srv/themes"), ThemeSource::System));
+    }
+
+    #[test]
+    fn test_extra_roots_follow_defaults() {
+        let defaults = vec![
+            (PathBuf::from("/home/a/.local/share/sddm/themes"), ThemeSource::User),
+            (PathBuf::from(THEMES_DIR), ThemeSource::System),
+        ];
+        let writable = tempfile::tempdir().unwrap();
+        let extra = [
+            PathBuf::from("/nonexistent/themewalker"),
+            PathBuf::from(THEMES_DIR),
+            writable.path().to_path_buf(),
+        ];
+        let roots = append_roots(defaults.clone(), &extra);
+        assert_eq!(roots[..2], defaults[..]);
+        assert_eq!(
+            roots[2..],
+            [
+                (PathBuf::from("/nonexistent/themewalker"), ThemeSource::System),
+                (writable.path().to_path_buf(), ThemeSource::User),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_discover_skips_unsafe_names() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["breeze", "evil\nRootLogin=true", "[Autologin]", "bell\u{7}"] {
+            fs::create_dir(root.path().join(name)).unwrap();
+        }
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::System)];
+
+        let themes = discover_themes_in(&roots).unwrap();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["breeze"]);
+        assert_eq!(unsafe_theme_dirs_in(&roots).len(), 3);
+        assert!(SddmTheme::from_dir(root.path().join("[Autologin]"), ThemeSource::User).is_none());
+    }
+
+    #[test]
+    fn test_discover_skips_hidden_directories() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["maya", ".themewalker-import-42", ".git"] {
+            fs::create_dir(root.path().join(name)).unwrap();
+        }
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::User)];
+        let themes = discover_themes_in(&roots).unwrap();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["maya"]);
+        assert!(unsafe_theme_dirs_in(&roots).is_empty());
+    }
+
+    #[test]
+    fn test_remove_user_theme() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("maya/faces")).unwrap();
+        fs::write(root.path().join("maya/Main.qml"), "").unwrap();
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::User)];
+        let themes = discover_themes_in(&roots).unwrap();
+
+        let session = EscalationSession::new(None);
+        remove_theme(&themes[0], &session).unwrap();
+        assert!(!root.path().join("maya").exists());
+        assert!(remove_theme(&themes[0], &session).is_err());
+    }
+
+    #[test]
+    fn test_remove_theme_escalates_for_unwritable_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("maya")).unwrap();
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::User)];
+        let themes = discover_themes_in(&roots).unwrap();
+        fs::set_permissions(root.path(), fs::Permissions::from_mode(0o555)).unwrap();
+        let needs_root = themes[0].removal_needs_root();
+        let result = remove_theme(&themes[0], &EscalationSession::new(None));
+        fs::set_permissions(root.path(), fs::Permissions::from_mode(0o755)).unwrap();
+        if !needs_root {
+            // Running as root: permission bits are not enforced
+            return;
+        }
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("neither sudo nor doas"), "{err}");
+        assert!(root.path().join("maya").exists());
+    }
+
+    #[test]
+    fn test_discover_lists_symlinked_theme_once() {
+        use std::os::unix::fs::symlink;
+
+        let root = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("breeze")).unwrap();
+        // Two links, so one is likely read before the real directory
+        symlink(root.path().join("breeze"), root.path().join("aa-default")).unwrap();
+        symlink(root.path().join("breeze"), root.path().join("zz-default")).unwrap();
+        // A link to a theme outside the root is the only entry for it
+        fs::create_dir(outside.path().join("maya-1.0")).unwrap();
+        symlink(outside.path().join("maya-1.0"), root.path().join("maya")).unwrap();
+        symlink(root.path().join("missing"), root.path().join("dangling")).unwrap();
+
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::System)];
+        let themes = discover_themes_in(&roots).unwrap();
+
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["breeze", "maya"]);
+        assert_eq!(themes[0].path, root.path().join("breeze"));
+        assert_eq!(themes[0].aliases, ["aa-default", "zz-default"]);
+        assert!(themes[0].answers_to("zz-default"));
+        assert!(themes[1].aliases.is_empty());
+    }
+
+    #[test]
+    fn test_discover_skips_unreadable_root() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let locked = tempfile::tempdir().unwrap();
+        let system = tempfile::tempdir().unwrap();
+        fs::create_dir(locked.path().join("hidden")).unwrap();
+        fs::create_dir(system.path().join("maya")).unwrap();
+        fs::set_permissions(locked.path(), fs::Permissions::from_mode(0o000)).unwrap();
+        let readable = fs::read_dir(locked.path()).is_ok();
+        fs::set_permissions(locked.path(), fs::Permissions::from_mode(0o700)).unwrap();
+        if readable {
+            // Running as root: permission bits are not enforced
+            return;
+        }
+        fs::set_permissions(locked.path(), fs::Permissions::from_mode(0o000)).unwrap();
+
+        let roots = vec![
+            (locked.path().to_path_buf(), ThemeSource::User),
+            (system.path().to_path_buf(), ThemeSource::System),
+        ];
+        let themes = discover_themes_in(&roots).unwrap();
+        let unreadable = unreadable_dirs_in(&roots);
+        fs::set_permissions(locked.path(), fs::Permissions::from_mode(0o700)).unwrap();
+
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["maya"]);
+        assert_eq!(unreadable, [locked.path().to_path_buf()]);
+    }
+
+    #[test]
+    fn test_missing_root_is_not_unreadable() {
+        let roots = vec![(PathBuf::from("/nonexistent/themewalker"), ThemeSource::System)];
+        assert!(unreadable_dirs_in(&roots).is_empty());
+    }
+
+    #[test]
+    fn test_themes_dir_env_overrides_default_roots() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("fake-one")).unwrap();
+        fs::create_dir(root.path().join("fake-two")).unwrap();
+
+        // No other test reads this variable, so setting it is race-free
+        std::env::set_var(THEMES_DIR_ENV, root.path());
+        let themes = discover_themes();
+        std::env::remove_var(THEMES_DIR_ENV);
+
+        let themes = themes.unwrap();
+        let names: Vec<&str> = themes.iter().map(|t| t.name.as_str()).collect();
+        assert_eq!(names, ["fake-one", "fake-two"]);
+        // The sandbox is writable, so nothing in it needs sudo
+        assert!(themes.iter().all(|t| t.source == ThemeSource::User));
+        assert!(!themes[0].removal_needs_root());
+    }
+
+    #[test]
+    fn test_discover_leaves_metadata_unloaded() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("breeze")).unwrap();
+        write_file(
+            &root.path().join("breeze/metadata.desktop"),
+            "[SddmGreeterTheme]\nDescription=Breeze\n",
+        );
+
+        let roots = vec![(root.path().to_path_buf(), ThemeSource::System)];
+        let themes = discover_themes_in(&roots).unwrap();
+        assert!(!themes[0].metadata_loaded);
+        assert_eq!(themes[0].description(), None);
+
+        let loaded: Vec<SddmTheme> = spawn_metadata_loader(&themes).iter().collect();
+        assert_eq!(loaded.len(), 1);
+        assert!(loaded[0].metadata_loaded);
+        assert_eq!(loaded[0].description(), Some("Breeze"));
+    }
+
+    #[test]
+    fn test_matches_query_checks_name_and_description() {
+        let mut theme = make_theme("sugar-candy");
+        theme.metadata.insert("Description".to_string(), "A Community Theme".to_string());
+        assert!(theme.matches_query("candy"));
+        assert!(theme.matches_query("community"));
+        assert!(!theme.matches_query("breeze"));
+    }
+
+    #[test]
+    fn test_query_match_reports_the_field() {
+        let mut theme = make_theme("sugar-candy");
+        theme.metadata.insert("Description".to_string(), "Sweet".to_string());
+        theme.metadata.insert("Author".to_string(), "Marian Arlt".to_string());
+        assert_eq!(theme.query_match("sugar"), Some(QueryMatch::Name));
+        assert_eq!(theme.query_match("sweet"), Some(QueryMatch::Description));
+        assert_eq!(theme.query_match("arlt"), Some(QueryMatch::Author));
+        assert_eq!(theme.query_match("breeze"), None);
     }
 
     #[test]
     fn test_display_label_with_description() {
-        let theme = SddmTheme {
-            name: "breeze".to_string(),
-            path: PathBuf::from("/tmp"),
-            description: Some("KDE Breeze".to_string()),
-            author: None,
-        };
+        let mut theme = make_theme("breeze");
+        theme.metadata.insert("Description".to_string(), "KDE Breeze".to_string());
         assert_eq!(theme.display_label(), "breeze — KDE Breeze");
     }
 
     #[test]
-    fn test_display_label_without_description() {
-        let theme = SddmTheme {
-            name: "breeze".to_string(),
-            path: PathBuf::from("/tmp"),
-            description: None,
-            author: None,
+    fn test_display_label_prefers_pretty_name() {
+        let mut theme = SddmTheme {
+            pretty_name: Some("Sugar Candy".to_string()),
+            ..make_theme("sugar-candy")
         };
+        theme.metadata.insert("Description".to_string(), "Sweet".to_string());
+        assert_eq!(theme.display_label(), "Sugar Candy — Sweet");
+        assert_eq!(theme.name, "sugar-candy");
+    }
+
+    #[test]
+    fn test_display_label_without_description() {
+        let theme = make_theme("breeze");
         assert_eq!(theme.display_label(), "breeze");
     }
 }
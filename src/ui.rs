@@ -6,39 +6,79 @@
 //! ┌─ Themewalker Theme Changer ────────────────────┐
 //! │ Config: /etc/sddm.conf  │  Current: breeze      │  ← header (3 rows)
 //! └────────────────────────────────────────────────-┘
-//! ┌─ Installed Themes (4 found) ───────────────────┐
-//! │ >> breeze                      [active]         │  ← list (fills)
-//! │    maya                                         │
-//! │    sugar-candy                                  │
+//! ┌─ Installed Themes (4 found) ──┐┌─ Preview ──────┐
+//! │ >> breeze           [active]  ││ breeze         │  ← list (2/3) +
+//! │    maya                       ││ Screenshot:    │    preview (1/3)
+//! │    sugar-candy                ││   preview.png  │
+//! └───────────────────────────────┘└────────────────┘
+//! ┌─ Details ───────────────────────────────────────┐
+//! │ Author: KDE Visual Design Group                 │  ← details (4 rows)
+//! │ KDE Breeze                                      │
 //! └─────────────────────────────────────────────────┘
 //! ┌─────────────────────────────────────────────────┐
 //! │  ↑/↓ k/j  Navigate   Enter  Select   q  Quit   │  ← help bar (3 rows)
 //! └─────────────────────────────────────────────────┘
 //! ```
 //!
-//! When `app.mode == Mode::Confirming` a centred popup overlays the list.
+//! While searching (`/`) the query is shown in the list title and only
+//! matching themes are listed.
+//!
+//! When `app.mode == Mode::Confirming` a centred popup overlays the list;
+//! `Mode::ConfirmingRestore` and `Mode::Error` show similar popups for
+//! restoring the config backup and for error messages, and
+//! `Mode::ChooseTarget` lists the config files a confirmed theme can be
+//! written to.  `Mode::Help` covers
+//! most of the screen with the full keybinding reference,
+//! `Mode::Applied` confirms a theme written without leaving the TUI, and
+//! `Mode::ConfirmDelete` asks for the theme name (or `delete <count>` for
+//! several marked themes) before deleting them.  `Mode::EditKeys` lists the
+//! config's `[Theme]` keys for editing, `Mode::ViewConfig` shows the whole
+//! config file, `Mode::Import` asks for the path of a theme to install,
+//! `Mode::ConfirmQuit` offers to apply the highlighted theme before quitting,
+//! and `Mode::Result` sums up a write made inside the TUI before
+//! quitting.
+
+use std::fs;
+use std::time::Instant;
 
 use ratatui::{
-    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Margin, Rect},
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    symbols,
+    text::{Line, Span, Text},
+    widgets::{
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph, Row, Scrollbar,
+        ScrollbarOrientation, ScrollbarState, Table, TableState, Wrap,
+    },
     Frame,
 };
 
-use crate::app::{App, Mode};
+use crate::app::{delete_prompt, App, Mode, SortKey};
+use crate::config::Escalation;
+use crate::palette::Palette;
+use crate::theme::{QueryMatch, SddmTheme};
+use crate::width::{display_width, pad_right, truncate, wrapped_rows};
 
-// ---------------------------------------------------------------------------
-// Colour palette
-// ---------------------------------------------------------------------------
+/// Largest share of the screen, in percent, a content-sized popup may take
+/// in either direction.
+const POPUP_MAX_PERCENT: u32 = 90;
 
-const CLR_HIGHLIGHT_BG: Color = Color::Blue;
-const CLR_HIGHLIGHT_FG: Color = Color::White;
-const CLR_ACTIVE_BADGE: Color = Color::Green;
-const CLR_HEADER_TITLE: Color = Color::Cyan;
-const CLR_HELP_KEY: Color = Color::Yellow;
-const CLR_POPUP_BORDER: Color = Color::LightYellow;
-const CLR_POPUP_CONFIRM: Color = Color::LightGreen;
+/// Drawn before the highlighted list row; every row is indented by its width.
+const HIGHLIGHT_SYMBOL: &str = ">> ";
+/// Badge right-aligned on the active theme's row.
+const ACTIVE_BADGE: &str = "[active]";
+/// Foregrounds theme names are drawn in; all readable on a dark or light
+/// background and distinct from the highlight's blue.
+const NAME_COLOURS: [Color; 8] = [
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightMagenta,
+    Color::LightCyan,
+];
 
 // ---------------------------------------------------------------------------
 // Entry point
@@ -46,26 +86,53 @@ const CLR_POPUP_CONFIRM: Color = Color::LightGreen;
 
 /// Draw the entire UI for one frame.  Takes `&mut App` because ratatui's
 /// `render_stateful_widget` needs mutable access to `app.list_state`.
-pub fn draw(frame: &mut Frame, app: &mut App) {
+pub fn draw(frame: &mut Frame, app: &mut App, pal: &Palette) {
     let area = frame.area();
 
-    // Three vertical bands: header | list | help
+    // Four vertical bands: header | list | details | help
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Length(3),
             Constraint::Min(3),
+            Constraint::Length(4),
             Constraint::Length(3),
         ])
         .split(area);
 
-    draw_header(frame, app, chunks[0]);
-    draw_theme_list(frame, app, chunks[1]);
-    draw_help_bar(frame, app, chunks[2]);
+    // Middle band: theme list (left two thirds) | preview (right third)
+    let body = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Ratio(2, 3), Constraint::Ratio(1, 3)])
+        .split(chunks[1]);
 
-    // Overlay the confirmation dialog on top of everything
-    if app.mode == Mode::Confirming {
-        draw_confirmation(frame, app, area);
+    draw_header(frame, app, pal, chunks[0]);
+    draw_theme_list(frame, app, pal, body[0]);
+    draw_preview(frame, app, pal, body[1]);
+    draw_details(frame, app, pal, chunks[2]);
+    draw_help_bar(frame, app, pal, chunks[3]);
+
+    // Overlay the confirmation / error dialog on top of everything
+    match &app.mode {
+        Mode::Confirming => draw_confirmation(frame, app, pal, area),
+        Mode::ChooseTarget(name) => draw_target_chooser(frame, app, name, pal, area),
+        Mode::ConfirmingRestore(theme) => {
+            draw_restore_confirmation(frame, app, theme.as_deref(), pal, area)
+        }
+        Mode::Error(msg) => draw_error(frame, msg, pal, area),
+        Mode::Help => draw_help_overlay(frame, pal, area),
+        Mode::Applied(name) => draw_applied(frame, name, pal, area),
+        Mode::ConfirmDelete { names, input } => {
+            draw_delete_confirmation(frame, app, names, input, pal, area)
+        }
+        Mode::Result => draw_result(frame, app, pal, area),
+        Mode::ViewConfig { scroll } => draw_view_config(frame, app, *scroll, pal, area),
+        Mode::Import { input } => draw_import(frame, app, input, pal, area),
+        Mode::ConfirmQuit => draw_confirm_quit(frame, app, pal, area),
+        Mode::EditKeys { keys, selected, input } => {
+            draw_edit_keys(frame, app, keys, *selected, input.as_deref(), pal, area)
+        }
+        _ => {}
     }
 }
 
@@ -73,20 +140,68 @@ pub fn draw(frame: &mut Frame, app: &mut App) {
 // Header
 // ---------------------------------------------------------------------------
 
-fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
-    let current_label = app
-        .current_theme
-        .as_deref()
-        .map(|n| format!("  Current: {}", n))
-        .unwrap_or_else(|| "  Current: (unknown)".to_string());
+fn draw_header(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
+    // A missing config will be created on apply; an existing one edited
+    let current_label = match (app.current_theme.as_deref(), app.config.config_exists) {
+        (Some(name), _) => format!("  Current: {}", name),
+        (None, true) => "  Current: (none set)".to_string(),
+        (None, false) => "  Config not yet created".to_string(),
+    };
 
     let config_label = format!("  Config: {}", app.config.path.display());
+    // Another file overrides the edited one, so say where the theme comes from
+    let (_, source) = app.config.effective_current();
+    let source_label = (source != app.config.path && app.current_theme.is_some())
+        .then(|| format!(" (set by {})", source.display()));
+
+    // An active theme that is not installed is shown as a warning
+    let current_style = if app.current_theme_installed() {
+        pal.active
+    } else {
+        pal.warning
+    };
 
-    let content = Line::from(vec![
-        Span::styled(config_label, Style::default().fg(Color::DarkGray)),
+    let mut spans = vec![
+        Span::styled(config_label, pal.dim),
         Span::raw("   "),
-        Span::styled(current_label, Style::default().fg(CLR_ACTIVE_BADGE).add_modifier(Modifier::BOLD)),
-    ]);
+        Span::styled(current_label, current_style.add_modifier(Modifier::BOLD)),
+    ];
+    if let Some(label) = source_label {
+        spans.push(Span::styled(label, pal.warning));
+    }
+
+    // Ahead of the other warnings: with another display manager none of this shows
+    if let Some(dm) = app.other_display_manager() {
+        spans.push(Span::raw("   "));
+        spans.push(Span::styled(
+            format!("SDDM does not appear to be your display manager ({dm} is enabled)"),
+            pal.warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if !app.config.conflict.is_empty() {
+        spans.push(Span::raw("   "));
+        spans.push(Span::styled(
+            format!("Multiple configs set a theme ({})", app.config.conflict.len()),
+            pal.warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    if let Some(legacy) = &app.config.shadowed_legacy {
+        spans.push(Span::raw("   "));
+        spans.push(Span::styled(
+            format!("{} also sets a theme (not edited)", legacy.display()),
+            pal.warning.add_modifier(Modifier::BOLD),
+        ));
+    }
+
+    // The list already shows the status when it is empty
+    if let Some(status) = app.status.as_deref().filter(|_| !app.themes.is_empty()) {
+        spans.push(Span::raw("   "));
+        spans.push(Span::styled(status.to_string(), pal.warning));
+    }
+
+    let content = Line::from(spans);
 
     let para = Paragraph::new(content)
         .block(
@@ -94,9 +209,7 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
                 .borders(Borders::ALL)
                 .title(Span::styled(
                     " Themewalker Theme Changer ",
-                    Style::default()
-                        .fg(CLR_HEADER_TITLE)
-                        .add_modifier(Modifier::BOLD),
+                    pal.title.add_modifier(Modifier::BOLD),
                 )),
         )
         .alignment(Alignment::Left);
@@ -108,67 +221,410 @@ fn draw_header(frame: &mut Frame, app: &App, area: Rect) {
 // Theme list
 // ---------------------------------------------------------------------------
 
-fn draw_theme_list(frame: &mut Frame, app: &mut App, area: Rect) {
+/// A stable colour for `name`, so a theme looks the same on every run.
+/// Uses FNV-1a rather than the std hasher, whose output may change between
+/// Rust releases.
+pub fn color_for_name(name: &str) -> Color {
+    let hash = name.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    NAME_COLOURS[(hash % NAME_COLOURS.len() as u64) as usize]
+}
+
+fn draw_theme_list(frame: &mut Frame, app: &mut App, pal: &Palette, area: Rect) {
+    // Remembered so mouse clicks can be mapped back to list rows
+    app.list_area = area;
+
+    let sort_label = match app.sort_key {
+        SortKey::Name => String::new(),
+        key => format!("— sorted by {} ", key.label()),
+    };
+
+    let searching = app.mode == Mode::Searching || !app.search_query.is_empty();
+    let title = if searching || app.incomplete_only || app.tag_filter.is_some() {
+        let mut filters = String::new();
+        if app.incomplete_only {
+            filters.push_str(" (metadata-incomplete)");
+        }
+        if let Some(tag) = &app.tag_filter {
+            filters.push_str(&format!(" (tag: {tag})"));
+        }
+        if searching {
+            let cursor = if app.mode == Mode::Searching { "_" } else { "" };
+            filters.push_str(&format!(" — /{}{}", app.search_query, cursor));
+        }
+        format!(
+            " Installed Themes ({}/{}){} {}",
+            app.visible.len(),
+            app.themes.len(),
+            filters,
+            sort_label
+        )
+    } else if app.visible.is_empty() {
+        " Installed Themes ".to_string()
+    } else {
+        match app.theme_source_counts() {
+            (system, user) if system > 0 && user > 0 => {
+                format!(" Installed Themes ({system} system, {user} user) {sort_label}")
+            }
+            _ => format!(" Installed Themes ({} found) {sort_label}", app.visible.len()),
+        }
+    };
+
+    // Status message when there is nothing to list
+    if app.visible.is_empty() {
+        let msg = if app.themes.is_empty() {
+            app.status.as_deref().unwrap_or("No themes found.")
+        } else if !app.search_query.is_empty() {
+            "No themes match the search."
+        } else if app.tag_filter.is_none() {
+            "Every theme has a description and an author."
+        } else {
+            "No themes match the filters."
+        };
+        let para = Paragraph::new(msg)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .alignment(Alignment::Center);
+        frame.render_widget(para, area);
+        return;
+    }
+
+    if app.table {
+        draw_theme_table(frame, app, pal, area, title);
+        draw_list_scrollbar(frame, app, area);
+        return;
+    }
+
     let current = app.current_theme.as_deref().unwrap_or("");
+    // Columns inside the borders, after the highlight symbol indent
+    let row_width = usize::from(area.width.saturating_sub(2))
+        .saturating_sub(display_width(HIGHLIGHT_SYMBOL));
 
+    // Results that did not match by name say which field did
+    let query = app.search_query.to_lowercase();
     let items: Vec<ListItem> = app
-        .themes
-        .iter()
-        .map(|theme| {
-            if theme.name == current {
-                ListItem::new(Line::from(vec![
-                    Span::raw(pad_right(&theme.display_label(), 38)),
-                    Span::styled(
-                        "[active]",
-                        Style::default()
-                            .fg(CLR_ACTIVE_BADGE)
-                            .add_modifier(Modifier::BOLD),
-                    ),
-                ]))
+        .visible_themes()
+        .enumerate()
+        .map(|(pos, theme)| {
+            let source_tag = format!("[{}] ", theme.source.tag());
+            let mark = if app.marked.contains(&theme.name) { "* " } else { "" };
+            let star = if app.is_favorite(theme) { "★ " } else { "" };
+            let label = format!("{mark}{star}{}", theme.display_name());
+            let configurable_tag = if theme.configurable { " [configurable]" } else { "" };
+            let shadowed_tag = if theme.shadowed { " (shadowed)" } else { "" };
+            let match_tag = theme.query_match(&query).map_or("", QueryMatch::tag);
+            // SDDM loads the other copy of a shadowed theme, never this one
+            let is_active = theme.answers_to(current) && !theme.shadowed;
+
+            // The label gives way to the tags; the badge keeps one space before it
+            let badge_width = if is_active { display_width(ACTIVE_BADGE) + 1 } else { 0 };
+            let label_width = row_width.saturating_sub(
+                display_width(&source_tag)
+                    + display_width(configurable_tag)
+                    + display_width(shadowed_tag)
+                    + display_width(match_tag)
+                    + badge_width,
+            );
+            let label = truncate(&label, label_width);
+            // The description line lines up with the name, after the source tag
+            let indent = display_width(&source_tag);
+
+            // The highlighted row keeps the plain highlight style
+            let label_style = if theme.shadowed {
+                pal.dim
+            } else if pal.name_colours && app.list_state.selected() != Some(pos) {
+                Style::default().fg(color_for_name(&theme.name))
             } else {
-                ListItem::new(Span::raw(theme.display_label()))
+                Style::default()
+            };
+            let mut spans = vec![
+                Span::styled(source_tag, pal.dim),
+                Span::styled(label, label_style),
+                Span::styled(configurable_tag, pal.dim),
+                Span::styled(shadowed_tag, pal.dim),
+                Span::styled(match_tag, pal.dim),
+            ];
+            if is_active {
+                let used: usize = spans.iter().map(|s| display_width(&s.content)).sum();
+                let gap = row_width.saturating_sub(used + display_width(ACTIVE_BADGE)).max(1);
+                spans.push(Span::raw(" ".repeat(gap)));
+                spans.push(Span::styled(
+                    ACTIVE_BADGE,
+                    pal.active.add_modifier(Modifier::BOLD),
+                ));
+            }
+            // Group headers and descriptions share the item so list indices
+            // stay theme indices; the highlight symbol marks the name line
+            let mut lines = Vec::with_capacity(3);
+            if let Some(header) = app.group_header(pos) {
+                lines.push(Line::styled(header, pal.title.add_modifier(Modifier::BOLD)));
             }
+            lines.push(Line::from(spans));
+            let description = theme.description().filter(|d| !d.is_empty());
+            if let Some(description) = description.filter(|_| !app.dense) {
+                let text = truncate(description, row_width.saturating_sub(indent));
+                lines.push(Line::styled(format!("{}{text}", " ".repeat(indent)), pal.dim));
+            }
+            ListItem::new(Text::from(lines))
         })
         .collect();
 
-    let title = if items.is_empty() {
-        " Installed Themes ".to_string()
-    } else {
-        format!(" Installed Themes ({} found) ", items.len())
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(pal.highlight.add_modifier(Modifier::BOLD))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+
+    frame.render_stateful_widget(list, area, &mut app.list_state);
+    draw_list_scrollbar(frame, app, area);
+}
+
+/// A scrollbar over the right border of the theme list (or table) in
+/// `area`, only when the themes do not all fit.  The thumb follows the
+/// selection; the track reuses the border line so it blends in.
+fn draw_list_scrollbar(frame: &mut Frame, app: &App, area: Rect) {
+    // Inside the top and bottom border, below the table's column headings
+    let rows = area.height.saturating_sub(2).saturating_sub(u16::from(app.table));
+    if !app.list_overflows(rows) {
+        return;
+    }
+    let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+        .begin_symbol(None)
+        .end_symbol(None)
+        .track_symbol(Some(symbols::line::VERTICAL));
+    let mut state = ScrollbarState::new(app.visible.len())
+        .position(app.list_state.selected().unwrap_or(0));
+    let track = area.inner(Margin { vertical: 1, horizontal: 0 });
+    frame.render_stateful_widget(scrollbar, track, &mut state);
+}
+
+/// The list as a table (Tab): one row per theme with Name, Author and
+/// Description columns, the first two as wide as their longest entry.
+/// Selection and scrolling stay in `app.list_state`, so navigation and
+/// mouse clicks work the same as in the list.
+fn draw_theme_table(frame: &mut Frame, app: &mut App, pal: &Palette, area: Rect, title: String) {
+    let current = app.current_theme.as_deref().unwrap_or("");
+    let query = app.search_query.to_lowercase();
+    let selected = app.list_state.selected();
+
+    let rows: Vec<[Line; 3]> = app
+        .visible_themes()
+        .enumerate()
+        .map(|(pos, theme)| {
+            let mark = if app.marked.contains(&theme.name) { "* " } else { "" };
+            let star = if app.is_favorite(theme) { "★ " } else { "" };
+            let label_style = if theme.shadowed {
+                pal.dim
+            } else if pal.name_colours && selected != Some(pos) {
+                Style::default().fg(color_for_name(&theme.name))
+            } else {
+                Style::default()
+            };
+            let mut name = vec![Span::styled(
+                format!("{mark}{star}{}", theme.display_name()),
+                label_style,
+            )];
+            let tags = [
+                if theme.configurable { " [configurable]" } else { "" },
+                if theme.shadowed { " (shadowed)" } else { "" },
+                theme.query_match(&query).map_or("", QueryMatch::tag),
+            ];
+            let tags = tags.into_iter().filter(|t| !t.is_empty());
+            name.extend(tags.map(|tag| Span::styled(tag, pal.dim)));
+            if theme.answers_to(current) && !theme.shadowed {
+                name.push(Span::raw(" "));
+                name.push(Span::styled(ACTIVE_BADGE, pal.active.add_modifier(Modifier::BOLD)));
+            }
+            let author = theme.author().unwrap_or_default().to_string();
+            let description = theme.description().unwrap_or_default().to_string();
+            [Line::from(name), Line::from(author), Line::styled(description, pal.dim)]
+        })
+        .collect();
+
+    let headings = ["Name", "Author", "Description"];
+    let column_width = |col: usize| {
+        let widest = rows.iter().map(|row| row[col].width()).max().unwrap_or(0);
+        u16::try_from(widest.max(headings[col].len())).unwrap_or(u16::MAX)
     };
+    let widths = [
+        Constraint::Max(column_width(0)),
+        Constraint::Max(column_width(1)),
+        Constraint::Fill(1),
+    ];
 
-    let list = List::new(items)
+    let table = Table::new(rows.into_iter().map(Row::new), widths)
+        .header(Row::new(headings).style(pal.title.add_modifier(Modifier::BOLD)))
         .block(Block::default().borders(Borders::ALL).title(title))
-        .highlight_style(
-            Style::default()
-                .bg(CLR_HIGHLIGHT_BG)
-                .fg(CLR_HIGHLIGHT_FG)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
+        .row_highlight_style(pal.highlight.add_modifier(Modifier::BOLD))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
 
-    // Status message when there are no themes
-    if app.themes.is_empty() {
-        let msg = app.status.as_deref().unwrap_or("No themes found.");
-        let para = Paragraph::new(msg)
-            .block(Block::default().borders(Borders::ALL).title(" Installed Themes "))
-            .alignment(Alignment::Center);
-        frame.render_widget(para, area);
-    } else {
-        frame.render_stateful_widget(list, area, &mut app.list_state);
+    let mut state = TableState::default()
+        .with_offset(app.list_state.offset())
+        .with_selected(selected);
+    frame.render_stateful_widget(table, area, &mut state);
+    *app.list_state.offset_mut() = state.offset();
+}
+
+// ---------------------------------------------------------------------------
+// Preview pane
+// ---------------------------------------------------------------------------
+
+/// Show which preview asset the highlighted theme ships (path and file
+/// size) and the colours its `theme.conf` sets, followed by the screenshot
+/// itself when the terminal can draw images.
+fn draw_preview(frame: &mut Frame, app: &mut App, pal: &Palette, area: Rect) {
+    let label_style = pal.key.add_modifier(Modifier::BOLD);
+
+    let mut lines = Vec::new();
+    if let Some(theme) = app.highlighted_theme() {
+        lines.push(Line::from(Span::styled(
+            theme.display_name().to_string(),
+            pal.title.add_modifier(Modifier::BOLD),
+        )));
+        if theme.pretty_name.is_some() {
+            lines.push(Line::from(Span::styled(format!("id: {}", theme.name), pal.dim)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Screenshot:", label_style)));
+        match &theme.screenshot {
+            Some(path) => {
+                lines.push(Line::from(format!("  {}", path.display())));
+                let size = match fs::metadata(path) {
+                    Ok(m) => format!("  {}", format_size(m.len())),
+                    Err(_) => "  (file missing)".to_string(),
+                };
+                lines.push(Line::from(Span::styled(size, pal.dim)));
+            }
+            None if !theme.metadata_loaded => {
+                lines.push(Line::from(Span::styled("  (loading…)", pal.dim)))
+            }
+            None => lines.push(Line::from(Span::styled("  (none declared)", pal.dim))),
+        }
+
+        // Read by `load_metadata`, off the render path
+        if !theme.colors.is_empty() {
+            lines.push(Line::from(""));
+            lines.push(Line::from(Span::styled("Colors:", label_style)));
+            for (key, [r, g, b]) in &theme.colors {
+                lines.push(color_line(key, Color::Rgb(*r, *g, *b), pal));
+            }
+        }
+    }
+
+    let text_height = lines.len() as u16;
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(" Preview "))
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(para, area);
+
+    // The screenshot itself goes below the text, when there is room for it
+    let screenshot = app
+        .highlighted_theme()
+        .and_then(|t| t.screenshot.clone())
+        .filter(|path| path.is_file());
+    if let (Some(preview), Some(path)) = (app.image_preview.as_mut(), screenshot) {
+        let inner = Rect::new(
+            area.x.saturating_add(1),
+            area.y.saturating_add(1),
+            area.width.saturating_sub(2),
+            area.height.saturating_sub(2),
+        );
+        // One blank line between text and image
+        let offset = text_height + 1;
+        if inner.height > offset + 2 {
+            let image_area = Rect::new(
+                inner.x,
+                inner.y + offset,
+                inner.width,
+                inner.height - offset,
+            );
+            preview.render(frame, &path, image_area);
+        }
     }
 }
 
+// ---------------------------------------------------------------------------
+// Details footer
+// ---------------------------------------------------------------------------
+
+/// Author and full description of the highlighted theme.
+fn draw_details(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
+    let theme = app.highlighted_theme();
+    let author = theme.and_then(|t| t.author()).filter(|a| !a.is_empty());
+    let description = theme
+        .and_then(|t| t.description())
+        .filter(|d| !d.is_empty());
+
+    let mut lines = Vec::new();
+    if theme.is_some_and(|t| !t.metadata_loaded) {
+        lines.push(Line::from(Span::styled("(loading metadata…)", pal.dim)));
+    } else if theme.is_some() && author.is_none() && description.is_none() {
+        lines.push(Line::from(Span::styled("(no metadata)", pal.dim)));
+    }
+    // Author and background size share a line so the size warning is not
+    // pushed out of the two-row pane
+    let mut facts = Vec::new();
+    if let Some(author) = author {
+        facts.push(Span::styled("Author: ", pal.key.add_modifier(Modifier::BOLD)));
+        facts.push(Span::raw(author.to_string()));
+    }
+    if let Some(size) = theme.and_then(|t| t.background_size) {
+        if !facts.is_empty() {
+            facts.push(Span::raw("   "));
+        }
+        facts.push(Span::styled("Background: ", pal.key.add_modifier(Modifier::BOLD)));
+        facts.push(Span::raw(format_size(size)));
+        if theme.is_some_and(SddmTheme::has_large_background) {
+            facts.push(Span::styled(
+                "  large asset: may slow the greeter",
+                pal.caution.add_modifier(Modifier::BOLD),
+            ));
+        }
+    }
+    if !facts.is_empty() {
+        lines.push(Line::from(facts));
+    }
+    if let Some(description) = description {
+        lines.push(Line::from(description.to_string()));
+    }
+    if theme.is_some_and(|t| t.configurable) {
+        lines.push(Line::from(vec![
+            Span::styled("Configurable: ", pal.key.add_modifier(Modifier::BOLD)),
+            Span::raw("options in theme.conf; override them in theme.conf.user"),
+        ]));
+    }
+
+    let para = Paragraph::new(lines)
+        .block(Block::default().borders(Borders::ALL).title(" Details "))
+        .wrap(Wrap { trim: true });
+
+    frame.render_widget(para, area);
+}
+
 // ---------------------------------------------------------------------------
 // Help bar
 // ---------------------------------------------------------------------------
 
-fn draw_help_bar(frame: &mut Frame, _app: &App, area: Rect) {
-    let keys: &[(&str, &str)] = &[
-        ("↑/↓ k/j", "Navigate"),
-        ("Enter", "Select"),
-        ("q / Esc", "Quit"),
-    ];
+fn draw_help_bar(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
+    let keys: &[(&str, &str)] = match app.mode {
+        Mode::Searching => &[
+            ("↑/↓", "Navigate"),
+            ("Enter", "Keep filter"),
+            ("Esc", "Clear search"),
+        ],
+        _ => &[
+            ("↑/↓ k/j", "Navigate"),
+            ("Enter", "Select"),
+            ("/", "Search"),
+            ("s", "Sort"),
+            ("f", "Favorite"),
+            ("o", "Open dir"),
+            ("u", "Back to active"),
+            ("r", "Restore backup"),
+            ("?", "Help"),
+            ("q / Esc", "Quit"),
+        ],
+    };
 
     let mut spans = Vec::new();
     for (i, (key, desc)) in keys.iter().enumerate() {
@@ -177,7 +633,7 @@ fn draw_help_bar(frame: &mut Frame, _app: &App, area: Rect) {
         }
         spans.push(Span::styled(
             format!("[{}]", key),
-            Style::default().fg(CLR_HELP_KEY).add_modifier(Modifier::BOLD),
+            pal.key.add_modifier(Modifier::BOLD),
         ));
         spans.push(Span::raw(format!(" {}", desc)));
     }
@@ -193,68 +649,162 @@ fn draw_help_bar(frame: &mut Frame, _app: &App, area: Rect) {
 // Confirmation popup
 // ---------------------------------------------------------------------------
 
-fn draw_confirmation(frame: &mut Frame, app: &App, area: Rect) {
+fn draw_confirmation(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
     let theme = app.highlighted_theme();
     let theme_name = theme.map(|t| t.name.as_str()).unwrap_or("?");
     let author_line = theme
-        .and_then(|t| t.author.as_deref())
+        .and_then(|t| t.author())
         .map(|a| format!("  by {a}"))
         .unwrap_or_default();
 
-    // Popup is 54 columns wide, 10 rows tall
-    let popup_area = centered_rect(54, 10, area);
-
-    // Clear background so the popup isn't see-through
-    frame.render_widget(Clear, popup_area);
-
+    let already_active = app.is_already_active();
     let mut body = vec![
         Line::from(""),
-        Line::from(vec![
-            Span::raw("  Apply theme  "),
-            Span::styled(
-                theme_name,
-                Style::default()
-                    .fg(CLR_POPUP_CONFIRM)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::raw("  ?"),
-        ]),
+        if already_active {
+            Line::from(vec![
+                Span::raw("  This theme is already active:  "),
+                Span::styled(
+                    theme_name,
+                    pal.active.add_modifier(Modifier::BOLD),
+                ),
+            ])
+        } else {
+            Line::from(vec![
+                Span::raw("  Apply theme  "),
+                Span::styled(
+                    theme_name,
+                    pal.confirm.add_modifier(Modifier::BOLD),
+                ),
+                Span::raw("  ?"),
+            ])
+        },
     ];
 
     if !author_line.is_empty() {
         body.push(Line::from(Span::styled(
             author_line,
-            Style::default().fg(Color::DarkGray),
+            pal.dim,
+        )));
+    }
+    if let Some(theme) = theme {
+        body.push(Line::from(format!("  {}", app.config.describe_change(&theme.name))));
+    }
+
+    let has_problems = !app.validation_problems.is_empty();
+    if has_problems {
+        body.push(Line::from(""));
+        body.push(Line::from(Span::styled(
+            "  This theme may not load:",
+            pal.warning.add_modifier(Modifier::BOLD),
+        )));
+        for problem in &app.validation_problems {
+            body.push(Line::from(Span::styled(format!("   • {problem}"), pal.warning)));
+        }
+    }
+
+    if already_active {
+        body.push(Line::from(Span::styled(
+            "  Re-apply it if the config looks stale.",
+            pal.dim,
+        )));
+    }
+
+    let confirm_label = if has_problems {
+        "  [Y]          Apply anyway"
+    } else if already_active {
+        "  [Enter / y]  Re-apply"
+    } else {
+        "  [Enter / y]  Confirm"
+    };
+    if let Some(remaining) = app.confirm_remaining(Instant::now()) {
+        // Round up so the countdown reaches 0 only as the dialog closes
+        let secs = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+        body.push(Line::from(Span::styled(
+            format!("  Cancels automatically in {secs}s"),
+            pal.dim,
         )));
     }
 
     body.extend([
         Line::from(""),
         Line::from(Span::styled(
-            "  [Enter / y]  Confirm",
-            Style::default().fg(CLR_HELP_KEY),
+            confirm_label,
+            pal.key,
         )),
         Line::from(Span::styled(
             "  [Esc   / n]  Cancel",
-            Style::default().fg(Color::DarkGray),
+            pal.dim,
         )),
         Line::from(""),
-        Line::from(Span::styled(
-            "  (sudo may be required to write config)",
-            Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
-        )),
+        write_note(app, pal),
     ]);
 
+    let popup_area = popup_rect(&body, area);
+
+    // Clear background so the popup isn't see-through
+    frame.render_widget(Clear, popup_area);
+
     let popup = Paragraph::new(body)
         .block(
             Block::default()
                 .borders(Borders::ALL)
-                .border_style(Style::default().fg(CLR_POPUP_BORDER))
+                .border_style(pal.popup_border)
                 .title(Span::styled(
                     " Confirm ",
-                    Style::default()
-                        .fg(CLR_POPUP_BORDER)
-                        .add_modifier(Modifier::BOLD),
+                    pal.popup_border.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .alignment(Alignment::Left)
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Restore popup
+// ---------------------------------------------------------------------------
+
+fn draw_restore_confirmation(
+    frame: &mut Frame,
+    app: &App,
+    theme: Option<&str>,
+    pal: &Palette,
+    area: Rect,
+) {
+    let target = match theme {
+        Some(name) => Line::from(vec![
+            Span::raw("  Restore backup with theme  "),
+            Span::styled(name.to_string(), pal.confirm.add_modifier(Modifier::BOLD)),
+            Span::raw("  ?"),
+        ]),
+        None => Line::from("  Restore backup (it does not set a theme) ?"),
+    };
+
+    let body = vec![
+        Line::from(""),
+        target,
+        Line::from(Span::styled(
+            format!("  {}", app.config.backup_path().display()),
+            pal.dim,
+        )),
+        Line::from(""),
+        Line::from(Span::styled("  [Enter / y]  Restore", pal.key)),
+        Line::from(Span::styled("  [Esc   / n]  Cancel", pal.dim)),
+        Line::from(""),
+        write_note(app, pal),
+    ];
+
+    let popup_area = centered_rect(54, body.len() as u16 + 2, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.popup_border)
+                .title(Span::styled(
+                    " Restore ",
+                    pal.popup_border.add_modifier(Modifier::BOLD),
                 )),
         )
         .alignment(Alignment::Left);
@@ -262,10 +812,627 @@ fn draw_confirmation(frame: &mut Frame, app: &App, area: Rect) {
     frame.render_widget(popup, popup_area);
 }
 
+// ---------------------------------------------------------------------------
+// Config file chooser
+// ---------------------------------------------------------------------------
+
+fn draw_target_chooser(frame: &mut Frame, app: &App, name: &str, pal: &Palette, area: Rect) {
+    let candidates = &app.config.candidates;
+    let items: Vec<ListItem> = candidates
+        .iter()
+        .map(|path| {
+            let mut spans = vec![Span::raw(path.display().to_string())];
+            if *path == app.config.path {
+                spans.push(Span::styled(" (default)", pal.dim));
+            }
+            if !path.exists() {
+                spans.push(Span::styled(" (new file)", pal.dim));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let popup_area = centered_rect(70, candidates.len() as u16 + 6, area);
+    frame.render_widget(Clear, popup_area);
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(pal.popup_border)
+        .title(Span::styled(
+            " Choose config file ",
+            pal.popup_border.add_modifier(Modifier::BOLD),
+        ));
+    let inner = block.inner(popup_area);
+    frame.render_widget(block, popup_area);
+
+    // Prompt | candidate list | key hints
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2),
+            Constraint::Min(1),
+            Constraint::Length(2),
+        ])
+        .split(inner);
+
+    let prompt = Line::from(vec![
+        Span::raw(" Write  "),
+        Span::styled(name.to_string(), pal.confirm.add_modifier(Modifier::BOLD)),
+        Span::raw("  to:"),
+    ]);
+    frame.render_widget(Paragraph::new(prompt), rows[0]);
+
+    let list = List::new(items)
+        .highlight_style(pal.highlight.add_modifier(Modifier::BOLD))
+        .highlight_symbol(HIGHLIGHT_SYMBOL);
+    let mut state = ListState::default().with_selected(Some(app.target_selected));
+    frame.render_stateful_widget(list, rows[1], &mut state);
+
+    let hints = vec![
+        Line::from(""),
+        Line::from(Span::styled(
+            " [↑/↓]  Choose   [Enter]  Write   [Esc / n]  Cancel",
+            pal.key,
+        )),
+    ];
+    frame.render_widget(Paragraph::new(hints), rows[2]);
+}
+
+/// Note on how the config will be written: directly, or through sudo or
+/// doas (just "root" when neither is installed).
+fn write_note(app: &App, pal: &Palette) -> Line<'static> {
+    if app.config.needs_sudo() {
+        let tool = app.config.escalation.map_or("root", Escalation::program);
+        Line::from(Span::styled(
+            format!("  ({tool} required to write config)"),
+            pal.key.add_modifier(Modifier::ITALIC),
+        ))
+    } else {
+        Line::from(Span::styled(
+            "  (writing directly)",
+            pal.confirm.add_modifier(Modifier::ITALIC),
+        ))
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Applied popup
+// ---------------------------------------------------------------------------
+
+fn draw_applied(frame: &mut Frame, name: &str, pal: &Palette, area: Rect) {
+    let body = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("  Applied!  ", pal.confirm.add_modifier(Modifier::BOLD)),
+            Span::styled(name.to_string(), pal.active.add_modifier(Modifier::BOLD)),
+            Span::raw(" is now the SDDM theme."),
+        ]),
+        Line::from("  Restart SDDM (or log out) for it to take effect."),
+        Line::from(""),
+        Line::from(Span::styled("  [Enter / Esc]  Keep browsing", pal.key)),
+        Line::from(Span::styled("  [r]            Quit and restart SDDM", pal.dim)),
+        Line::from(Span::styled("  [q]            Quit", pal.dim)),
+    ];
+
+    let popup_area = centered_rect(54, body.len() as u16 + 2, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.confirm)
+                .title(Span::styled(
+                    " Applied ",
+                    pal.confirm.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Quit confirmation
+// ---------------------------------------------------------------------------
+
+fn draw_confirm_quit(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
+    let Some(theme) = app.highlighted_theme() else {
+        return;
+    };
+    let active = app.current_theme.as_deref().unwrap_or("none");
+    let body = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("  Quit without applying  "),
+            Span::styled(theme.name.clone(), pal.warning.add_modifier(Modifier::BOLD)),
+            Span::raw("  ?"),
+        ]),
+        Line::from(Span::styled(format!("  The active theme stays {active}."), pal.dim)),
+        Line::from(""),
+        Line::from(Span::styled("  [a / Enter]  Apply it", pal.key)),
+        Line::from(Span::styled("  [d]          Quit without applying", pal.dim)),
+        Line::from(Span::styled("  [Esc / c]    Keep browsing", pal.dim)),
+    ];
+
+    let popup_area = popup_rect(&body, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.popup_border)
+                .title(Span::styled(
+                    " Quit ",
+                    pal.popup_border.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Result summary
+// ---------------------------------------------------------------------------
+
+/// Final summary of `app.last_apply`, shown when quitting after a write
+/// made inside the TUI.  A failed write is shown in the same layout, with
+/// the error in the warning style.
+fn draw_result(frame: &mut Frame, app: &App, pal: &Palette, area: Rect) {
+    let Some(result) = &app.last_apply else {
+        return;
+    };
+    let (title, border) = match result.error {
+        None => (" Applied ", pal.confirm),
+        Some(_) => (" Apply failed ", pal.warning),
+    };
+    let mut body = vec![
+        Line::from(""),
+        Line::from(vec![
+            Span::styled(format!("  {:<9}", "Theme"), pal.key),
+            Span::styled(result.theme.clone(), pal.active.add_modifier(Modifier::BOLD)),
+        ]),
+        Line::from(vec![
+            Span::styled(format!("  {:<9}", "Config"), pal.key),
+            Span::raw(result.config.display().to_string()),
+        ]),
+    ];
+    match &result.error {
+        None => {
+            let backup = match &result.backup {
+                Some(path) => path.display().to_string(),
+                None => "none (the config was empty or new)".to_string(),
+            };
+            body.push(Line::from(vec![
+                Span::styled(format!("  {:<9}", "Backup"), pal.key),
+                Span::raw(backup),
+            ]));
+            body.push(Line::from(""));
+            body.push(Line::from(if result.restart_needed {
+                "  Restart SDDM (or log out) for it to take effect."
+            } else {
+                "  This is the theme SDDM was already using; no restart needed."
+            }));
+        }
+        Some(error) => {
+            body.push(Line::from(""));
+            body.push(Line::from(Span::styled(
+                format!("  Write failed: {error}"),
+                pal.warning.add_modifier(Modifier::BOLD),
+            )));
+        }
+    }
+    body.push(Line::from(""));
+    body.push(Line::from(Span::styled("  Press any key to quit", pal.dim)));
+
+    let popup_area = popup_rect(&body, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(border)
+                .title(Span::styled(title, border.add_modifier(Modifier::BOLD))),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Delete confirmation
+// ---------------------------------------------------------------------------
+
+fn draw_delete_confirmation(
+    frame: &mut Frame,
+    app: &App,
+    names: &[String],
+    input: &str,
+    pal: &Palette,
+    area: Rect,
+) {
+    let themes: Vec<&SddmTheme> = names
+        .iter()
+        .filter_map(|name| app.themes.iter().find(|t| t.name == *name && !t.shadowed))
+        .collect();
+    let how = if themes.iter().any(|t| t.removal_needs_root()) {
+        match app.config.escalation {
+            Some(tool) => {
+                format!("  Removed with {} rm -rf; this cannot be undone.", tool.program())
+            }
+            None => "  Needs root to remove; this cannot be undone.".to_string(),
+        }
+    } else {
+        "  This cannot be undone.".to_string()
+    };
+    let prompt = delete_prompt(names);
+    let typed = if input == prompt { pal.confirm } else { pal.warning };
+
+    let mut body = vec![Line::from("")];
+    match themes[..] {
+        [theme] => {
+            body.push(Line::from(vec![
+                Span::raw("  Delete theme  "),
+                Span::styled(theme.name.clone(), pal.warning.add_modifier(Modifier::BOLD)),
+                Span::raw("  ?"),
+            ]));
+            body.push(Line::from(Span::styled(
+                format!("  {}", theme.path.display()),
+                pal.dim,
+            )));
+        }
+        _ => {
+            body.push(Line::from(format!("  Delete {} marked themes?", themes.len())));
+            body.extend(themes.iter().map(|theme| {
+                Line::from(vec![
+                    Span::styled(
+                        format!("  {}", theme.name),
+                        pal.warning.add_modifier(Modifier::BOLD),
+                    ),
+                    Span::styled(format!("  {}", theme.path.display()), pal.dim),
+                ])
+            }));
+        }
+    }
+    body.extend([
+        Line::from(how),
+        Line::from(""),
+        Line::from(vec![
+            Span::raw("  Type "),
+            Span::styled(prompt, pal.key.add_modifier(Modifier::BOLD)),
+            Span::raw(" to confirm:"),
+        ]),
+        Line::from(vec![
+            Span::raw("  > "),
+            Span::styled(input.to_string(), typed.add_modifier(Modifier::BOLD)),
+            Span::raw("_"),
+        ]),
+        Line::from(""),
+        Line::from(Span::styled("  [Enter]  Delete (once the text matches)", pal.key)),
+        Line::from(Span::styled("  [Esc]    Cancel", pal.dim)),
+    ]);
+
+    let popup_area = popup_rect(&body, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.warning)
+                .title(Span::styled(
+                    " Delete ",
+                    pal.warning.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Import prompt
+// ---------------------------------------------------------------------------
+
+fn draw_import(frame: &mut Frame, app: &App, input: &str, pal: &Palette, area: Rect) {
+    let into = match &app.import_dir {
+        Some(dir) => format!("  Installed into {}", dir.display()),
+        None => "  $HOME is not set, so there is nowhere to install it".to_string(),
+    };
+    let body = vec![
+        Line::from(""),
+        Line::from("  Path to a theme directory or .tar.gz archive:"),
+        Line::from(vec![
+            Span::raw("  > "),
+            Span::styled(input.to_string(), pal.key.add_modifier(Modifier::BOLD)),
+            Span::raw("_"),
+        ]),
+        Line::from(Span::styled(into, pal.dim)),
+        Line::from(Span::styled(
+            "  The screen pauses while a large theme is copied or unpacked.",
+            pal.dim,
+        )),
+        Line::from(""),
+        Line::from(Span::styled("  [Enter]  Import", pal.key)),
+        Line::from(Span::styled("  [Esc]    Cancel", pal.dim)),
+    ];
+
+    let popup_area = popup_rect(&body, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.popup_border)
+                .title(Span::styled(" Import ", pal.title)),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// [Theme] key editor
+// ---------------------------------------------------------------------------
+
+fn draw_edit_keys(
+    frame: &mut Frame,
+    app: &App,
+    keys: &[(String, String)],
+    selected: usize,
+    input: Option<&str>,
+    pal: &Palette,
+    area: Rect,
+) {
+    let mut body = vec![
+        Line::from(""),
+        Line::from(Span::styled(format!("  {}", app.config.path.display()), pal.dim)),
+        Line::from(""),
+    ];
+    if keys.is_empty() {
+        body.push(Line::from("  No keys are set in [Theme]"));
+    }
+    let key_width = keys.iter().map(|(key, _)| display_width(key)).max().unwrap_or(0);
+    for (i, (key, value)) in keys.iter().enumerate() {
+        let key = Span::styled(format!("  {}  ", pad_right(key, key_width)), pal.key);
+        body.push(match input.filter(|_| i == selected) {
+            Some(typed) => Line::from(vec![
+                key,
+                Span::styled(typed.to_string(), pal.confirm.add_modifier(Modifier::BOLD)),
+                Span::raw("_"),
+            ]),
+            None if i == selected => {
+                Line::from(vec![key, Span::styled(value.clone(), pal.highlight)])
+            }
+            None => Line::from(vec![key, Span::raw(value.clone())]),
+        });
+    }
+    body.push(Line::from(""));
+    let hints = if input.is_some() {
+        "  [Enter]  Keep value   [Esc]  Cancel"
+    } else {
+        "  [↑/↓]  Choose   [Enter]  Edit   [w]  Write   [Esc]  Close"
+    };
+    body.push(Line::from(Span::styled(hints, pal.key)));
+
+    let popup_area = popup_rect(&body, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.popup_border)
+                .title(Span::styled(
+                    " [Theme] keys ",
+                    pal.popup_border.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Help overlay
+// ---------------------------------------------------------------------------
+
+/// Every keybinding, grouped by category, for the `?` overlay (and
+/// `--help`).
+pub const HELP_SECTIONS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            ("↑ / k", "Move selection up"),
+            ("↓ / j", "Move selection down"),
+            ("a", "Jump to the active theme"),
+            ("u", "Jump back to the originally active theme"),
+            ("w", "Toggle wrap-around at the list ends"),
+            ("a–z, 0–9", "Jump to the next theme starting with it (if not a command)"),
+            ("A–Z", "The same, also for themes starting with a command letter"),
+        ],
+    ),
+    (
+        "Search & sort",
+        &[
+            ("/", "Search by name, description or author"),
+            ("Enter", "Keep filter (while searching)"),
+            ("Esc", "Clear search"),
+            ("s", "Cycle sort: name, author, modified"),
+            ("t", "Filter by the next tag (Categories / Keywords)"),
+            ("m", "Only themes missing an author or description"),
+            ("d", "Dense list: names only, no descriptions"),
+            ("Tab", "Table of name, author and description columns"),
+            ("f", "Toggle favorite (favorites sort first)"),
+        ],
+    ),
+    (
+        "Actions",
+        &[
+            ("Enter", "Apply the highlighted theme (asks first)"),
+            ("o", "Quit and open the theme directory"),
+            ("c", "Copy the theme name to the clipboard"),
+            ("Space", "Mark / unmark the theme for batch f and x"),
+            ("x / Del", "Quit and delete the theme (type its name first)"),
+            ("r", "Restore the config backup (asks first)"),
+            ("e", "View and edit the config's [Theme] keys"),
+            ("v", "View the whole config file (j / k scroll)"),
+            ("i", "Import a theme from a directory or .tar.gz"),
+        ],
+    ),
+    (
+        "Dialogs",
+        &[
+            ("Enter / y", "Confirm"),
+            ("Y", "Apply a theme with problems anyway"),
+            ("↑ / ↓", "Choose the config file (when several exist)"),
+            ("Esc / n", "Cancel"),
+            ("r", "Quit and restart SDDM (after applying)"),
+            ("a / d", "Apply / quit without applying (--confirm-quit)"),
+        ],
+    ),
+    (
+        "General",
+        &[
+            ("F5 / Ctrl-R", "Re-scan themes and re-read the config"),
+            ("?", "Toggle this help"),
+            ("q / Esc", "Quit without changes"),
+            ("Ctrl-C", "Quit at once, from any dialog"),
+        ],
+    ),
+];
+
+fn draw_help_overlay(frame: &mut Frame, pal: &Palette, area: Rect) {
+    let mut body = Vec::new();
+    for (section, keys) in HELP_SECTIONS {
+        body.push(Line::from(""));
+        body.push(Line::from(Span::styled(
+            format!("  {section}"),
+            pal.title.add_modifier(Modifier::BOLD),
+        )));
+        for (key, desc) in keys.iter() {
+            body.push(Line::from(vec![
+                Span::styled(format!("    {}", pad_right(key, 12)), pal.key),
+                Span::raw(*desc),
+            ]));
+        }
+    }
+    body.push(Line::from(""));
+    body.push(Line::from(Span::styled("  [? / Esc / q]  Close", pal.dim)));
+
+    // Three quarters of the terminal, but never smaller than the content needs
+    let width = (area.width / 4 * 3).max(60);
+    let height = (area.height / 4 * 3).max(body.len() as u16 + 2);
+    let popup_area = centered_rect(width, height, area);
+    frame.render_widget(Clear, popup_area);
+
+    let popup = Paragraph::new(body).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(pal.popup_border)
+            .title(Span::styled(
+                " Keybindings ",
+                pal.popup_border.add_modifier(Modifier::BOLD),
+            )),
+    );
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Config viewer
+// ---------------------------------------------------------------------------
+
+fn draw_view_config(frame: &mut Frame, app: &App, scroll: u16, pal: &Palette, area: Rect) {
+    let content = app.config.raw_content();
+    let mut body: Vec<Line> = if content.is_empty() {
+        vec![Line::from(Span::styled("(empty; applying a theme creates it)", pal.dim))]
+    } else {
+        content.lines().map(|line| Line::from(line.to_string())).collect()
+    };
+    body.push(Line::from(""));
+    body.push(Line::from(Span::styled("[j / k] Scroll   [Esc / q] Close", pal.dim)));
+
+    // Same size as the help overlay; long lines are cut, not wrapped, so a
+    // scroll step is always one line of the file
+    let width = (area.width / 4 * 3).max(60);
+    let height = area.height / 4 * 3;
+    let popup_area = centered_rect(width, height, area);
+    frame.render_widget(Clear, popup_area);
+
+    let title = format!(" {} ", app.config.path.display());
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.popup_border)
+                .title(Span::styled(title, pal.popup_border.add_modifier(Modifier::BOLD))),
+        )
+        .scroll((scroll, 0));
+
+    frame.render_widget(popup, popup_area);
+}
+
+// ---------------------------------------------------------------------------
+// Error popup
+// ---------------------------------------------------------------------------
+
+fn draw_error(frame: &mut Frame, msg: &str, pal: &Palette, area: Rect) {
+    let popup_area = centered_rect(54, 9, area);
+    frame.render_widget(Clear, popup_area);
+
+    let body = vec![
+        Line::from(""),
+        Line::from(Span::styled(format!("  {msg}"), pal.warning)),
+        Line::from(""),
+        Line::from(Span::styled("  [Enter / Esc]  OK", pal.key)),
+    ];
+
+    let popup = Paragraph::new(body)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(pal.warning)
+                .title(Span::styled(
+                    " Error ",
+                    pal.warning.add_modifier(Modifier::BOLD),
+                )),
+        )
+        .wrap(Wrap { trim: false });
+
+    frame.render_widget(popup, popup_area);
+}
+
 // ---------------------------------------------------------------------------
 // Helpers
 // ---------------------------------------------------------------------------
 
+/// Area for a popup showing `body` inside borders, centred in `area`: as
+/// wide as its longest line and as tall as its lines, but at most
+/// `POPUP_MAX_PERCENT` of `area`.  Lines wider than that wrap, and the
+/// height allows for the rows they wrap onto.
+fn popup_rect(body: &[Line], area: Rect) -> Rect {
+    let percent = |n: u16| (u32::from(n) * POPUP_MAX_PERCENT / 100) as u16;
+    let content_width = body.iter().map(Line::width).max().unwrap_or(0);
+    // Borders plus a right margin matching the two-space indent on the left
+    let width = u16::try_from(content_width + 4)
+        .unwrap_or(u16::MAX)
+        .min(percent(area.width));
+    let inner = usize::from(width.saturating_sub(2)).max(1);
+    let rows: usize = body
+        .iter()
+        .map(|line| wrapped_rows(&line.to_string(), inner))
+        .sum();
+    let height = u16::try_from(rows + 2)
+        .unwrap_or(u16::MAX)
+        .min(percent(area.height));
+    centered_rect(width, height, area)
+}
+
 /// Return a Rect centred within `r` with the given fixed width and height.
 fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
     let x = r.x.saturating_add(r.width.saturating_sub(width) / 2);
@@ -275,11 +1442,148 @@ fn centered_rect(width: u16, height: u16, r: Rect) -> Rect {
     Rect::new(x, y, w, h)
 }
 
-/// Right-pad a string to at least `len` characters (for column alignment).
-fn pad_right(s: &str, len: usize) -> String {
-    if s.len() >= len {
-        s.to_string()
+/// One `theme.conf` colour: a swatch (unless the palette is monochrome),
+/// the key and the hex value.
+fn color_line(key: &str, color: Color, pal: &Palette) -> Line<'static> {
+    let hex = match color {
+        Color::Rgb(r, g, b) => format!("#{r:02x}{g:02x}{b:02x}"),
+        other => format!("{other:?}"),
+    };
+    let mut spans = vec![Span::raw("  ")];
+    if pal.swatches {
+        spans.push(Span::styled("██", Style::default().fg(color)));
+        spans.push(Span::raw(" "));
+    }
+    spans.push(Span::raw(key.to_string()));
+    spans.push(Span::styled(format!(" {hex}"), pal.dim));
+    Line::from(spans)
+}
+
+/// Human-readable byte count ("512 B", "1.4 KiB", "2.0 MiB").
+fn format_size(bytes: u64) -> String {
+    const KIB: f64 = 1024.0;
+    const MIB: f64 = KIB * 1024.0;
+    let b = bytes as f64;
+    if b >= MIB {
+        format!("{:.1} MiB", b / MIB)
+    } else if b >= KIB {
+        format!("{:.1} KiB", b / KIB)
     } else {
-        format!("{:<width$}", s, width = len)
+        format!("{} B", bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::config::SddmConfig;
+    use crate::theme::{SddmTheme, ThemeSource};
+
+    /// An app confirming `name`, by a long-named author, with two problems.
+    fn confirming(name: &str) -> App {
+        let theme = SddmTheme {
+            name: name.to_string(),
+            pretty_name: None,
+            path: PathBuf::from("/nonexistent").join(name),
+            metadata: HashMap::from([(
+                "Author".to_string(),
+                "Somebody With A Rather Long Name".to_string(),
+            )]),
+            tags: Vec::new(),
+            screenshot: None,
+            background_size: None,
+            source: ThemeSource::System,
+            modified: None,
+            configurable: false,
+            colors: Vec::new(),
+            metadata_loaded: true,
+            shadowed: false,
+            aliases: Vec::new(),
+        };
+        let mut app = App::new(vec![theme], SddmConfig::empty());
+        app.mode = Mode::Confirming;
+        app.validation_problems = vec![
+            "Main.qml is missing".to_string(),
+            "metadata.desktop has no MainScript".to_string(),
+        ];
+        app
+    }
+
+    /// The screen after drawing the confirmation over a `width`x`height`
+    /// terminal, one string per row.
+    fn render(app: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|frame| draw_confirmation(frame, app, &Palette::colour(), frame.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content
+            .chunks(usize::from(width))
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn popup_fits_its_content() {
+        let body = vec![Line::from("  short"), Line::from("  a little longer")];
+        let rect = popup_rect(&body, Rect::new(0, 0, 80, 24));
+        assert_eq!((rect.width, rect.height), (21, 4));
+        // Centred
+        assert_eq!((rect.x, rect.y), (29, 10));
+    }
+
+    #[test]
+    fn name_colours_are_stable_and_varied() {
+        assert_eq!(color_for_name("breeze"), color_for_name("breeze"));
+        let names = ["breeze", "maldives", "elarun", "sugar-candy", "chili", "maya"];
+        let colours: std::collections::HashSet<_> = names.into_iter().map(color_for_name).collect();
+        assert!(colours.len() > 1);
+        assert!(colours.iter().all(|c| NAME_COLOURS.contains(c)));
+    }
+
+    #[test]
+    fn popup_wraps_within_the_screen() {
+        let body = vec![Line::from("x".repeat(100))];
+        let area = Rect::new(0, 0, 50, 20);
+        let rect = popup_rect(&body, area);
+        assert_eq!(rect.width, 45);
+        // 100 columns over the 43 inside the borders take three rows
+        assert_eq!(rect.height, 5);
+        assert_eq!(area.intersection(rect), rect);
+    }
+
+    #[test]
+    fn confirmation_shows_every_line_at_several_sizes() {
+        let app = confirming("sugar-candy");
+        for (width, height) in [(60, 24), (80, 30), (120, 40), (200, 60)] {
+            let screen = render(&app, width, height).join("\n");
+            for text in [
+                "Apply theme  sugar-candy  ?",
+                "by Somebody With A Rather Long Name",
+                "This theme may not load:",
+                "• metadata.desktop has no MainScript",
+                "[Y]          Apply anyway",
+                "[Esc   / n]  Cancel",
+            ] {
+                assert!(screen.contains(text), "{width}x{height} lost {text:?}:\n{screen}");
+            }
+        }
+    }
+
+    #[test]
+    fn confirmation_wraps_on_a_narrow_terminal() {
+        let app = confirming("a-theme-with-an-exceptionally-long-directory-name");
+        let rows = render(&app, 40, 30);
+        // The bottom border is still drawn, so nothing was cut off below it
+        assert_eq!(rows.iter().filter(|row| row.contains('└')).count(), 1);
+        assert!(rows.join("").contains("Cancel"));
+        // Tiny terminals must not panic
+        render(&app, 10, 4);
     }
 }
@@ -0,0 +1,216 @@
+//! Themewalker's own settings file, for things that should persist rather
+//! than be passed as environment variables on every run.
+//!
+//! The file is `$XDG_CONFIG_HOME/themewalker/config.toml` (falling back to
+//! `~/.config`).  It is optional:
+//!
+//! ```toml
+//! # Extra directories to scan for themes, after the default ones
+//! theme_dirs = ["~/src/sddm-themes", "/opt/sddm-themes"]
+//!
+//! # Colours for the TUI: a name, "#rrggbb" or "indexed:N"
+//! [colors]
+//! highlight_bg = "#5f87af"
+//! highlight_fg = "black"
+//! active = "indexed:33"
+//! popup_border = "light-cyan"
+//! ```
+//!
+//! Only string arrays and the string values of the `[colors]` table are
+//! read.  Unlike `prefs`, this module is built without the `tui` feature, so
+//! it parses them itself rather than pulling in `toml` and `serde`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::favorites::config_dir;
+
+/// File name inside the themewalker config directory.
+const CONFIG_FILE: &str = "config.toml";
+
+/// Settings read from `config.toml`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UserConfig {
+    /// Theme roots to scan in addition to the default ones, with a leading
+    /// `~/` expanded to `$HOME`.
+    pub theme_dirs: Vec<PathBuf>,
+    /// The `[colors]` table.
+    pub colors: ColorOverrides,
+}
+
+/// Colours from the `[colors]` table, as written; `Palette::from_config`
+/// parses them and ignores any that do not.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ColorOverrides {
+    /// Background of the selected list row.
+    pub highlight_bg: Option<String>,
+    /// Text of the selected list row.
+    pub highlight_fg: Option<String>,
+    /// `[active]` badge and the current theme in the header.
+    pub active: Option<String>,
+    /// Border and title of popups.
+    pub popup_border: Option<String>,
+}
+
+/// Where the settings file lives, or `None` when there is no config
+/// directory.
+pub fn default_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join(CONFIG_FILE))
+}
+
+/// Read the settings from `path`.  A missing or unreadable file yields the
+/// defaults rather than an error.
+pub fn load(path: &Path) -> UserConfig {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    fs::read_to_string(path)
+        .map(|content| parse(&content, home.as_deref()))
+        .unwrap_or_default()
+}
+
+/// Parse the settings, expanding `~/` against `home`.
+fn parse(content: &str, home: Option<&Path>) -> UserConfig {
+    let theme_dirs = parse_string_array(content, "theme_dirs")
+        .iter()
+        .filter(|dir| !dir.is_empty())
+        .filter_map(|dir| match dir.strip_prefix("~/") {
+            Some(rest) => home.map(|h| h.join(rest)),
+            None => Some(PathBuf::from(dir)),
+        })
+        .collect();
+    let colors = table_strings(content, "colors");
+    let color = |key: &str| colors.iter().find(|(k, _)| k == key).map(|(_, v)| v.clone());
+    UserConfig {
+        theme_dirs,
+        colors: ColorOverrides {
+            highlight_bg: color("highlight_bg"),
+            highlight_fg: color("highlight_fg"),
+            active: color("active"),
+            popup_border: color("popup_border"),
+        },
+    }
+}
+
+/// The `key = "value"` pairs of the `[table]` section in `content`, with
+/// the quotes removed.  Values that are not strings are skipped.
+fn table_strings(content: &str, table: &str) -> Vec<(String, String)> {
+    let header = format!("[{table}]");
+    let mut in_table = false;
+    let mut pairs = Vec::new();
+    for line in content.lines() {
+        let t = line.trim();
+        if t.starts_with('[') {
+            in_table = t.split('#').next().unwrap_or_default().trim_end() == header;
+            continue;
+        }
+        let Some((key, value)) = t.split_once('=').filter(|_| in_table) else {
+            continue;
+        };
+        let value = value.trim_start().strip_prefix('"').and_then(|v| v.split_once('"'));
+        if let Some((value, _)) = value {
+            pairs.push((key.trim().to_string(), value.to_string()));
+        }
+    }
+    pairs
+}
+
+/// The strings of the `key = [...]` array in `content`, which may span
+/// several lines and contain `#` comments.  Empty when the key is missing or
+/// not an array.
+pub(crate) fn parse_string_array(content: &str, key: &str) -> Vec<String> {
+    let Some(start) = content.lines().position(|l| {
+        l.trim_start()
+            .strip_prefix(key)
+            .is_some_and(|rest| rest.trim_start().starts_with('='))
+    }) else {
+        return Vec::new();
+    };
+    // The array may span several lines; read everything from the key onwards
+    let rest: String = content.lines().skip(start).collect::<Vec<_>>().join("\n");
+    let Some((_, array)) = rest.split_once('[') else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::new();
+    let mut chars = array.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            ']' => break,
+            '"' => {
+                let mut value = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => value.extend(chars.next()),
+                        c => value.push(c),
+                    }
+                }
+                values.push(value);
+            }
+            // Comments run to the end of the line
+            '#' => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_theme_dirs_and_expands_home() {
+        let content = "# my collections\n\
+                       theme_dirs = [\n  \"~/sddm-themes\", # cloned\n  \"/opt/themes\",\n  \"\",\n]\n";
+        let config = parse(content, Some(Path::new("/home/alice")));
+        assert_eq!(
+            config.theme_dirs,
+            [PathBuf::from("/home/alice/sddm-themes"), PathBuf::from("/opt/themes")]
+        );
+
+        // Without $HOME a `~/` entry cannot be resolved and is dropped
+        let config = parse(content, None);
+        assert_eq!(config.theme_dirs, [PathBuf::from("/opt/themes")]);
+    }
+
+    #[test]
+    fn array_key_must_match_exactly() {
+        assert!(parse_string_array("theme_dirs_old = [\"a\"]\n", "theme_dirs").is_empty());
+        assert!(parse_string_array("theme_dirs = \"a\"\n", "theme_dirs").is_empty());
+        assert_eq!(parse_string_array("theme_dirs=[\"a\"]", "theme_dirs"), ["a"]);
+    }
+
+    #[test]
+    fn reads_color_table() {
+        let content = "theme_dirs = [\"/opt/themes\"]\n\
+                       [colors] # mine\n\
+                       highlight_bg = \"#5f87af\" # muted blue\n\
+                       active = \"indexed:33\"\n\
+                       popup_border = 12\n\
+                       [other]\n\
+                       highlight_fg = \"white\"\n";
+        let config = parse(content, None);
+        assert_eq!(config.theme_dirs, [PathBuf::from("/opt/themes")]);
+        assert_eq!(
+            config.colors,
+            ColorOverrides {
+                highlight_bg: Some("#5f87af".to_string()),
+                highlight_fg: None,
+                active: Some("indexed:33".to_string()),
+                popup_border: None,
+            }
+        );
+    }
+
+    #[test]
+    fn missing_file_has_no_extra_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(load(&dir.path().join(CONFIG_FILE)), UserConfig::default());
+    }
+}
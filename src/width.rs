@@ -0,0 +1,126 @@
+//! Terminal column widths of strings, for fitting labels into columns.
+//!
+//! Widths are display columns as the terminal draws them: accented letters
+//! built from combining marks take one column, CJK characters two.
+
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Number of terminal columns `s` occupies.
+pub fn display_width(s: &str) -> usize {
+    s.width()
+}
+
+/// Cut `s` to at most `max` columns, ending in "…" when anything was cut.
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.width() <= max {
+        return s.to_string();
+    }
+    let budget = max.saturating_sub(1);
+    let mut out = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > budget {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    if max > 0 {
+        out.push('…');
+    }
+    out
+}
+
+/// Rows `s` takes when word-wrapped to `width` columns.  Errs towards
+/// too many rather than too few: trailing spaces count towards a row, and
+/// words wider than a row are split across as many rows as they fill.
+pub fn wrapped_rows(s: &str, width: usize) -> usize {
+    let width = width.max(1);
+    let mut rows = 1;
+    let mut used = 0;
+    for word in s.split_inclusive(' ') {
+        let mut w = word.width();
+        if used > 0 && used + w > width {
+            rows += 1;
+            used = 0;
+        }
+        while w > width {
+            rows += 1;
+            w -= width;
+        }
+        used += w;
+    }
+    rows
+}
+
+/// Right-pad `s` with spaces to at least `width` columns (for column
+/// alignment).
+pub fn pad_right(s: &str, width: usize) -> String {
+    let padding = width.saturating_sub(s.width());
+    format!("{s}{}", " ".repeat(padding))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_strings_are_unchanged() {
+        assert_eq!(truncate("breeze", 6), "breeze");
+        assert_eq!(truncate("breeze", 40), "breeze");
+        assert_eq!(truncate("", 0), "");
+    }
+
+    #[test]
+    fn long_strings_end_in_ellipsis() {
+        assert_eq!(truncate("sugar-candy — sweet", 8), "sugar-c…");
+        assert_eq!(display_width(&truncate("sugar-candy — sweet", 8)), 8);
+        assert_eq!(truncate("breeze", 1), "…");
+        assert_eq!(truncate("breeze", 0), "");
+    }
+
+    #[test]
+    fn counts_columns_not_bytes() {
+        // "Grüße" with a precomposed ü, and "café" with a combining acute
+        assert_eq!(display_width("Grüße"), 5);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(truncate("cafe\u{301} noir", 5), "cafe\u{301}…");
+        // Wide characters are never split in half
+        assert_eq!(display_width("桜の庭"), 6);
+        assert_eq!(truncate("桜の庭", 4), "桜…");
+        assert_eq!(truncate("桜の庭", 5), "桜の…");
+    }
+
+    #[test]
+    fn pad_right_pads_to_display_width() {
+        assert_eq!(pad_right("breeze", 8), "breeze  ");
+        assert_eq!(pad_right("sugar-candy", 4), "sugar-candy");
+        assert_eq!(pad_right("↑ / k", 7), "↑ / k  ");
+    }
+
+    #[test]
+    fn badge_lines_up_after_non_ascii_names() {
+        let names = ["breeze", "cafe\u{301}-theme", "Grüße", "桜の庭", "桜-e\u{301}"];
+        let columns: Vec<usize> = names
+            .iter()
+            .map(|name| {
+                let row = format!("{}[active]", pad_right(name, 16));
+                let before = row.split("[active]").next().unwrap();
+                display_width(before)
+            })
+            .collect();
+        assert_eq!(columns, [16; 5]);
+    }
+
+    #[test]
+    fn wrapped_rows_breaks_at_words() {
+        assert_eq!(wrapped_rows("", 10), 1);
+        assert_eq!(wrapped_rows("fits in one", 11), 1);
+        // Twelve columns fit two rows of six, but not without splitting words
+        assert_eq!(wrapped_rows("aaaa bbbb cc", 6), 3);
+        // A word wider than the row spills over as many rows as it fills
+        assert_eq!(wrapped_rows("ab cdefghijklmnop", 5), 4);
+        assert_eq!(wrapped_rows("桜の庭", 4), 2);
+    }
+}
@@ -0,0 +1,160 @@
+//! Whole sessions replayed through `App::handle_keys`: each test builds an
+//! app from fake themes on disk and checks where a realistic key sequence
+//! leaves it.
+
+#![cfg(feature = "tui")]
+
+use std::fs;
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use themewalker::app::{App, ExitAction, Mode};
+use themewalker::theme::ThemeSource;
+use themewalker::{SddmConfig, SddmTheme};
+
+/// Installed themes named `names` under `root`, each complete enough to
+/// pass validation.
+fn fake_themes(root: &Path, names: &[&str]) -> Vec<SddmTheme> {
+    names
+        .iter()
+        .map(|name| {
+            let dir = root.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("metadata.desktop"),
+                format!("[SddmGreeterTheme]\nDescription={name} theme\nAuthor=Tester\n"),
+            )
+            .unwrap();
+            fs::write(dir.join("Main.qml"), "").unwrap();
+            SddmTheme::from_dir(dir, ThemeSource::System).unwrap()
+        })
+        .collect()
+}
+
+/// An app over `aerial`, `breeze` and `maya` whose config at `root/sddm.conf`
+/// selects `breeze`.
+fn session(root: &Path) -> App {
+    let config_path = root.join("sddm.conf");
+    fs::write(&config_path, "[Theme]\nCurrent=breeze\n").unwrap();
+    let config = SddmConfig::load_from(&config_path).unwrap();
+    App::new(fake_themes(root, &["aerial", "breeze", "maya"]), config)
+}
+
+fn chars(text: &str) -> impl Iterator<Item = KeyCode> + '_ {
+    text.chars().map(KeyCode::Char)
+}
+
+fn applied(action: Option<ExitAction>) -> Option<String> {
+    match action {
+        Some(ExitAction::ApplyTheme(name)) => Some(name),
+        _ => None,
+    }
+}
+
+#[test]
+fn navigate_and_confirm_applies_the_highlighted_theme() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+    // Starts on the active theme
+    assert_eq!(app.highlighted_theme().unwrap().name, "breeze");
+
+    assert!(app.handle_keys([KeyCode::Down, KeyCode::Enter]).is_none());
+    assert_eq!(app.mode, Mode::Confirming);
+
+    let action = app.handle_keys([KeyCode::Char('y')]);
+    assert_eq!(applied(action).as_deref(), Some("maya"));
+}
+
+#[test]
+fn cancelling_the_dialog_returns_to_browsing_and_q_quits() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+
+    assert!(app.handle_keys([KeyCode::Up, KeyCode::Enter, KeyCode::Esc]).is_none());
+    assert_eq!(app.mode, Mode::Browsing);
+    assert_eq!(app.highlighted_theme().unwrap().name, "aerial");
+
+    // Keys after the one that ends the session are not processed
+    let action = app.handle_keys([KeyCode::Char('q'), KeyCode::Down]);
+    assert!(matches!(action, Some(ExitAction::Quit)));
+    assert_eq!(app.highlighted_theme().unwrap().name, "aerial");
+}
+
+#[test]
+fn search_then_confirm_applies_the_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+
+    app.handle_keys([KeyCode::Char('/')]);
+    assert_eq!(app.mode, Mode::Searching);
+    app.handle_keys(chars("aer"));
+    assert_eq!(app.visible_themes().count(), 1);
+
+    // Enter keeps the filter and leaves the search box; the next opens the dialog
+    assert!(app.handle_keys([KeyCode::Enter, KeyCode::Enter]).is_none());
+    assert_eq!(app.mode, Mode::Confirming);
+    let action = app.handle_keys([KeyCode::Enter]);
+    assert_eq!(applied(action).as_deref(), Some("aerial"));
+}
+
+#[test]
+fn escaping_a_search_restores_the_full_list() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+
+    let keys = [KeyCode::Char('/'), KeyCode::Char('m'), KeyCode::Esc];
+    assert!(app.handle_keys(keys).is_none());
+    assert_eq!(app.mode, Mode::Browsing);
+    assert_eq!(app.visible_themes().count(), 3);
+    assert_eq!(app.highlighted_theme().unwrap().name, "breeze");
+}
+
+#[test]
+fn ctrl_c_quits_halfway_through_a_search() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+
+    app.handle_keys(chars("/ma"));
+    assert_eq!(app.mode, Mode::Searching);
+    let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+    assert!(matches!(app.handle_keys([ctrl_c]), Some(ExitAction::Quit)));
+    // Nothing was written
+    let config = fs::read_to_string(dir.path().join("sddm.conf")).unwrap();
+    assert_eq!(config, "[Theme]\nCurrent=breeze\n");
+}
+
+#[test]
+fn applying_inside_the_tui_writes_and_keeps_browsing() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+    app.apply_in_tui = true;
+
+    assert!(app.handle_keys([KeyCode::Down, KeyCode::Enter, KeyCode::Enter]).is_none());
+    assert_eq!(app.mode, Mode::Applied("maya".to_string()));
+    assert_eq!(app.current_theme.as_deref(), Some("maya"));
+    let written = fs::read_to_string(dir.path().join("sddm.conf")).unwrap();
+    assert_eq!(written, "[Theme]\nCurrent=maya\n");
+
+    assert!(app.handle_keys([KeyCode::Enter]).is_none());
+    assert_eq!(app.mode, Mode::Browsing);
+    // Quitting shows the summary of the write first
+    assert!(app.handle_keys([KeyCode::Char('q')]).is_none());
+    assert_eq!(app.mode, Mode::Result);
+    assert!(matches!(app.handle_keys([KeyCode::Char('q')]), Some(ExitAction::Quit)));
+}
+
+#[test]
+fn several_config_files_ask_for_a_target() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut app = session(dir.path());
+    let dropin = dir.path().join("zz-themewalker.conf");
+    app.config.candidates = vec![dir.path().join("sddm.conf"), dropin.clone()];
+
+    let keys = [KeyCode::Down, KeyCode::Enter, KeyCode::Enter];
+    assert!(app.handle_keys(keys).is_none());
+    assert_eq!(app.mode, Mode::ChooseTarget("maya".to_string()));
+
+    let action = app.handle_keys([KeyCode::Down, KeyCode::Enter]);
+    assert_eq!(applied(action).as_deref(), Some("maya"));
+    assert_eq!(app.config.path, dropin);
+}